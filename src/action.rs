@@ -23,6 +23,16 @@ pub enum Action {
     GitShowDiff,
     GitDiscardFile,
     GitExpandFile,
+    ToggleTreeView,
+    ToggleTurnFilter,
+    StartFilter,
+    SubmoduleList,
+    SubmoduleUpdate,
+    SubmoduleLeave,
+    CompareMenu,
+    CompareReview,
+    GitExportPatch { clipboard: bool },
+    TagList,
 
     // Diff navigation
     DiffScrollUp,
@@ -35,22 +45,57 @@ pub enum Action {
     DiffClose,
     DiffToggleSelect,
     DiffSendLines,
+    DiffToggleBlame,
+    DiffCycleWordDiff,
+    DiffToggleSplit,
+    DiffLoadFull,
+    DiffSearch,
+    DiffGotoLine,
+    DiffNextMatch,
+    DiffPrevMatch,
+    DiffGoPrefix,
+    DiffBottom,
+    DiffHalfPageDown,
+    DiffHalfPageUp,
+    DiffContext(i32),
+    DiffCycleWhitespace,
+    DiffCycleAlgorithm,
+    DiffExpandHunk { above: bool },
+    DiffExportPatch { clipboard: bool },
+    DiffTagCommit,
+    DiffShowCommit,
 
     // Send to Claude
     SendToClaude,
     SendToClaudeWithPrompt,
     ToggleMultiSelect,
 
+    // PTY selection
+    SelectionQuote,
+    SelectionOpenRefs,
+
+    // Context basket
+    BasketAdd,
+    BasketOpen,
+
     // Git operations
     Commit,
     CommitAndPush,
+    DraftCommitMessage,
     Push,
+    ForcePush,
     Pull,
+    Fetch,
     CreateBranch,
     CheckoutBranch(String),
     BranchList,
     Stash,
     StashPop,
+    StashDrop,
+    UndoList,
+    Checkpoint,
+    CheckpointList,
+    CheckpointRollback,
 
     // File browser navigation
     FBNavUp,
@@ -71,6 +116,10 @@ pub enum Action {
     // File browser misc
     FBToggleHidden,
     FBRefresh,
+    FBApplyPatch,
+    FBToggleMark,
+    FBSendToClaude,
+    FBSendToClaudeWithPrompt,
 }
 
 #[derive(Debug, Clone, Copy)]
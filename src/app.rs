@@ -1,24 +1,45 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEventKind};
 use ratatui::layout::Rect;
 
 use tokio::sync::mpsc;
 
 use crate::action::{Action, ActiveTab, FocusTarget};
+use crate::basket::{self, Basket, BasketItem};
+use crate::config::Config;
 use crate::event::AppEvent;
+use crate::file_refs::{self, FileRef};
 use crate::filebrowser::operations;
 use crate::filebrowser::state::FileBrowserState;
-use crate::git::diff::FileDiff;
+use crate::git::commit_draft;
+use crate::git::checkpoint::{Checkpoint, CheckpointFile};
+use crate::git::compare::{self, CompareSpec};
+use crate::git::patch;
+use crate::git::tag::TagInfo;
+use crate::git::turn::{self, TurnSnapshot};
+use crate::git::diff::{DiffLineKind, FileDiff};
 use crate::git::operations::GitOps;
 use crate::git::repo::GitRepo;
-use crate::git::status::FileStatus;
+use crate::git::status::{BranchInfo, FileStatus, FileStatusKind};
+use crate::git::undo::{UndoEntry, UndoKind};
+use crate::history::History;
 use crate::input::handler;
+use crate::prompts::{self, PromptTemplate, TemplateContext};
+use crate::pty::links::{self, Link, LinkTarget};
 use crate::pty::manager::PtyManager;
 use crate::pty::terminal_emulator::TerminalEmulator;
-use crate::ui::diff_view::DiffViewState;
+use crate::ui::diff_view::{DiffPrompt, DiffViewState};
 use crate::ui::layout::AppLayout;
+use crate::ui::picker::{PickerKind, PickerState};
 use crate::ui::prompt_dialog::{PromptDialogState, PromptMode};
-use crate::ui::status_list::StatusListState;
+use crate::ui::status_list::{Section, StatusListState};
+
+/// Largest context the `+` key grows diffs to.
+const MAX_CONTEXT: u32 = 100;
+/// Lines revealed per `[`/`]` press when expanding a hunk in place.
+const EXPAND_STEP: u32 = 10;
+
+const COMPARE_READ_ONLY: &str = "Compare mode is read-only (R to leave)";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Focus {
@@ -26,6 +47,8 @@ pub enum Focus {
     GitStatus,
     DiffView,
     PromptDialog,
+    Picker,
+    Basket,
     FileBrowserLeft,
     FileBrowserRight,
 }
@@ -90,11 +113,46 @@ pub struct App {
     pub git_ops: Option<GitOps>,
     pub files: Vec<FileStatus>,
     pub current_diff: Option<FileDiff>,
-    pub branch: String,
+    pub branch: BranchInfo,
     pub status_state: StatusListState,
     pub diff_state: DiffViewState,
     pub prompt_state: PromptDialogState,
+    pub picker_state: PickerState,
+    /// Entries backing the undo picker, in picker order
+    pub undo_entries: Vec<UndoEntry>,
+    /// Submodule paths backing the submodule picker, in picker order
+    pub submodule_entries: Vec<String>,
+    /// Parent workdirs and submodule paths while drilled into a submodule
+    pub submodule_stack: Vec<(String, String)>,
+    /// Revision range shown instead of the working tree status
+    pub compare: Option<CompareSpec>,
+    /// Stashes backing the compare picker, after its two fixed entries
+    pub compare_stashes: Vec<(String, git2::Oid)>,
+    /// Patch file previewed while the apply picker is open
+    pub apply_patch_path: Option<String>,
+    /// Tags backing the tag picker, in picker order
+    pub tag_entries: Vec<TagInfo>,
+    /// References backing the file reference picker, in picker order
+    pub file_ref_entries: Vec<FileRef>,
+    /// Templates backing the prompt template picker, in picker order
+    pub template_entries: Vec<PromptTemplate>,
+    /// References collected with `i`, sent together from the basket panel
+    pub basket: Basket,
+    pub basket_open: bool,
+    /// Prompts sent from the send dialog, recalled with Up/Down and Ctrl-R
+    pub prompt_history: History,
+    /// Worktree state when the last prompt was sent
+    turn: Option<TurnSnapshot>,
+    /// Prompts sent so far; only the latest one's `TurnRecorded` is kept
+    turn_seq: u64,
+    /// Checkpoints backing the timeline picker, newest first
+    pub checkpoint_entries: Vec<Checkpoint>,
+    /// Checkpoint marked with Space as the base of a timeline diff
+    pub checkpoint_mark: Option<u64>,
+    /// Checkpoint and files backing the restore-file picker
+    pub checkpoint_files: Option<(Checkpoint, Vec<CheckpointFile>)>,
     pub file_browser: FileBrowserState,
+    pub config: Config,
     pub last_pty_area: Rect,
     pub error_message: Option<String>,
     // Stored pane rects for mouse hit-testing (set during draw)
@@ -105,31 +163,58 @@ pub struct App {
     pub tab_bar_rect: Rect,
     pub dragging_divider: bool,
     pub pty_selection: PtySelection,
+    /// Links on the visible PTY screen by row, detected a row at a time as
+    /// the pointer reaches it; cleared whenever the screen changes
+    pty_links: std::collections::HashMap<u16, Vec<Link>>,
+    /// Link under the mouse pointer
+    link_hover: Option<Link>,
+    /// Ctrl held during the last mouse move: underline every link
+    link_all: bool,
     // For async git refresh
     pub event_tx: Option<mpsc::UnboundedSender<AppEvent>>,
+    // Keeps the commit-draft file watcher alive while waiting for Claude
+    draft_watcher: Option<notify::RecommendedWatcher>,
     workdir: String,
+    /// Per-repo state dir for the top-level repository
+    state_dir: Option<std::path::PathBuf>,
+    /// Focus to return to when the basket panel closes
+    basket_return: Focus,
     git_refreshing: bool,
+    git_fetching: bool,
+    git_pushing_tags: bool,
+    last_fetch: std::time::Instant,
     pty_ready: bool,
 }
 
 impl App {
-    pub fn new(pty: PtyManager, cols: u16, rows: u16) -> Self {
+    pub fn new(pty: PtyManager, cols: u16, rows: u16, config: Config) -> Self {
         let workdir = std::env::current_dir()
             .unwrap_or_default()
             .to_string_lossy()
             .to_string();
 
-        let git_repo = GitRepo::open(&workdir).ok();
+        let git_repo = GitRepo::open(&workdir)
+            .ok()
+            .map(|r| r.with_rename_threshold(config.rename_threshold)
+                .with_diff_limits(config.diff_limits())
+                .with_diff_settings(config.diff_settings()));
         let git_ops = git_repo.as_ref()
             .and_then(|r| r.workdir())
             .map(|p| GitOps::new(&p.to_string_lossy()));
 
         let branch = git_repo.as_ref()
-            .and_then(|r| r.branch_name().ok())
-            .unwrap_or_else(|| "N/A".to_string());
+            .and_then(|r| r.branch_info().ok())
+            .unwrap_or_else(|| BranchInfo::without_upstream("N/A"));
 
         let file_browser = FileBrowserState::new(std::path::Path::new(&workdir));
 
+        let state_dir = crate::state::repo_state_dir(&workdir);
+        let mut basket = Basket::new();
+        if let Some(items) = state_dir.as_ref().and_then(|d| basket::read(&basket::current_path(d)).ok()) {
+            basket.replace(items, None);
+        }
+        let prompt_history = History::load(state_dir.as_deref());
+
         Self {
             running: true,
             focus: Focus::Pty,
@@ -144,9 +229,32 @@ impl App {
             current_diff: None,
             branch,
             status_state: StatusListState::new(),
-            diff_state: DiffViewState::new(),
+            diff_state: DiffViewState {
+                word_diff: config.word_diff,
+                settings: config.diff_settings(),
+                ..DiffViewState::new()
+            },
             prompt_state: PromptDialogState::new(),
+            picker_state: PickerState::new(),
+            undo_entries: Vec::new(),
+            checkpoint_entries: Vec::new(),
+            checkpoint_mark: None,
+            checkpoint_files: None,
+            submodule_entries: Vec::new(),
+            submodule_stack: Vec::new(),
+            compare: None,
+            compare_stashes: Vec::new(),
+            apply_patch_path: None,
+            tag_entries: Vec::new(),
+            file_ref_entries: Vec::new(),
+            template_entries: Vec::new(),
+            basket,
+            basket_open: false,
+            prompt_history,
+            turn: None,
+            turn_seq: 0,
             file_browser,
+            config,
             last_pty_area: Rect::default(),
             error_message: None,
             pty_rect: Rect::default(),
@@ -156,9 +264,18 @@ impl App {
             tab_bar_rect: Rect::default(),
             dragging_divider: false,
             pty_selection: PtySelection::default(),
+            pty_links: std::collections::HashMap::new(),
+            link_hover: None,
+            link_all: false,
             event_tx: None,
+            draft_watcher: None,
             workdir: workdir.clone(),
+            state_dir,
+            basket_return: Focus::GitStatus,
             git_refreshing: false,
+            git_fetching: false,
+            git_pushing_tags: false,
+            last_fetch: std::time::Instant::now(),
             pty_ready: false,
         }
     }
@@ -166,16 +283,92 @@ impl App {
     /// Synchronous git refresh (used for initial load)
     pub fn refresh_git_sync(&mut self) {
         if let Some(ref repo) = self.git_repo {
-            match repo.status_list() {
-                Ok(files) => self.files = files,
+            let files = match self.compare {
+                Some(ref spec) => repo.compare_files(spec),
+                None => repo.status_list(),
+            };
+            match files {
+                Ok(files) => {
+                    let changed = match (&self.compare, self.turn_snapshot()) {
+                        (None, Some(snapshot)) => repo.turn_changes(snapshot, &files),
+                        _ => Default::default(),
+                    };
+                    self.files = files;
+                    self.status_state.turn_changed = changed;
+                    self.status_state.rebuild(&self.files);
+                }
                 Err(e) => self.error_message = Some(format!("Git status error: {}", e)),
             }
-            if let Ok(branch) = repo.branch_name() {
+            if let Ok(branch) = repo.branch_info() {
                 self.branch = branch;
             }
         }
     }
 
+    fn set_files(&mut self, files: Vec<FileStatus>) {
+        self.files = files;
+        self.status_state.rebuild(&self.files);
+    }
+
+    /// The last prompt's snapshot, if it was taken in the repository shown.
+    fn turn_snapshot(&self) -> Option<&TurnSnapshot> {
+        let workdir = self.git_repo.as_ref()?.workdir()?;
+        self.turn.as_ref().filter(|t| std::path::Path::new(&t.workdir) == workdir)
+    }
+
+    /// Remember the worktree as a prompt is sent, so the next refresh can
+    /// show what the agent changed in response, and checkpoint it so the
+    /// turn can be rolled back.
+    /// The scan runs on a background thread and arrives as `TurnRecorded`.
+    fn mark_turn(&mut self, prompt: &str) {
+        let (Some(_), Some(tx)) = (&self.git_repo, self.event_tx.clone()) else { return };
+        self.turn_seq += 1;
+        let seq = self.turn_seq;
+        let workdir = self.workdir.clone();
+        let threshold = self.config.rename_threshold;
+        let checkpoint = !self.config.no_checkpoints;
+        let previous = self.turn_snapshot().cloned();
+        let label = prompt.to_string();
+        tokio::task::spawn_blocking(move || {
+            let result = GitRepo::open(&workdir)
+                .map(|r| r.with_rename_threshold(threshold))
+                .and_then(|repo| if checkpoint {
+                    repo.checkpoint_turn(&label, previous.as_ref())
+                        .map(|(snapshot, _, left_out)| (snapshot, left_out))
+                } else {
+                    repo.snapshot_turn(previous.as_ref()).map(|snapshot| (snapshot, Vec::new()))
+                })
+                .map_err(|e| e.to_string());
+            let _ = tx.send(AppEvent::TurnRecorded(seq, result));
+        });
+    }
+
+    /// Ask Claude to review what it changed since the last prompt, by line range.
+    async fn review_turn(&mut self) -> Result<()> {
+        let (Some(repo), Some(snapshot)) = (&self.git_repo, self.turn_snapshot()) else {
+            self.error_message = Some("Nothing sent to Claude from here yet".to_string());
+            return Ok(());
+        };
+        let prefix = self.submodule_prefix();
+        let changes: Vec<_> = self.files.iter()
+            .filter(|f| self.status_state.turn_changed.contains(&f.path))
+            .map(|f| {
+                let change = repo.turn_change(snapshot, &f.path).unwrap_or(turn::TurnChange::Whole);
+                (format!("{}{}", prefix, f.path), change)
+            })
+            .collect();
+        if changes.is_empty() {
+            self.error_message = Some("No changes since the last prompt".to_string());
+            return Ok(());
+        }
+        let request = turn::review_request(&changes);
+        self.mark_turn(&request);
+        self.pty.inject_input(&request).await?;
+        self.pty.write_input(b"\x1b[I").await?;
+        self.focus = Focus::Pty;
+        Ok(())
+    }
+
     /// Async git refresh — runs git status on a background thread
     pub fn refresh_git(&mut self) {
         if self.git_refreshing || self.git_repo.is_none() {
@@ -185,22 +378,103 @@ impl App {
             self.git_refreshing = true;
             let workdir = self.workdir.clone();
             let tx = tx.clone();
+            let threshold = self.config.rename_threshold;
+            let turn = self.turn_snapshot().cloned();
             tokio::task::spawn_blocking(move || {
-                if let Ok(repo) = GitRepo::open(&workdir) {
+                if let Ok(repo) = GitRepo::open(&workdir).map(|r| r.with_rename_threshold(threshold)) {
                     let files = repo.status_list().unwrap_or_default();
-                    let branch = repo.branch_name().unwrap_or_else(|_| "N/A".to_string());
-                    let _ = tx.send(AppEvent::GitStatusUpdate(files, branch));
+                    let branch = repo.branch_info().unwrap_or_else(|_| BranchInfo::without_upstream("N/A"));
+                    let changed = turn.map(|t| repo.turn_changes(&t, &files)).unwrap_or_default();
+                    let _ = tx.send(AppEvent::GitStatusUpdate(files, branch, changed));
                 }
             });
         }
     }
 
+    /// Run `git fetch --all` on a background thread; the result arrives as
+    /// `GitFetchDone` and triggers a refresh so ahead/behind counts update.
+    pub fn fetch_async(&mut self) {
+        if self.git_fetching {
+            return;
+        }
+        let (Some(ops), Some(tx)) = (self.git_ops.clone(), self.event_tx.clone()) else {
+            return;
+        };
+        self.git_fetching = true;
+        self.last_fetch = std::time::Instant::now();
+        tokio::task::spawn_blocking(move || {
+            let err = ops.fetch().err().map(|e| e.to_string());
+            let _ = tx.send(AppEvent::GitFetchDone(err));
+        });
+    }
+
+    /// Push all tags on a background thread; the result arrives as `GitPushTagsDone`.
+    fn push_tags_async(&mut self) {
+        if self.git_pushing_tags {
+            self.error_message = Some("Tag push already running".to_string());
+            return;
+        }
+        let remote = self.git_repo.as_ref().and_then(|r| r.push_remote(&self.branch));
+        let (Some(ops), Some(tx), Some(remote)) = (self.git_ops.clone(), self.event_tx.clone(), remote) else {
+            self.error_message = Some("No remote to push tags to".to_string());
+            return;
+        };
+        self.git_pushing_tags = true;
+        self.error_message = Some(format!("Pushing tags to {}\u{2026}", remote));
+        tokio::task::spawn_blocking(move || {
+            let result = ops.push_tags(&remote).map_err(|e| e.to_string());
+            let _ = tx.send(AppEvent::GitPushTagsDone(result));
+        });
+    }
+
+    /// Stop waiting for a commit-message draft and remove its temp file.
+    fn stop_drafting(&mut self) {
+        self.draft_watcher = None;
+        let _ = std::fs::remove_file(commit_draft::draft_path());
+    }
+
+    fn maybe_fetch(&mut self) {
+        let interval = self.config.fetch_interval;
+        if interval > 0 && self.last_fetch.elapsed().as_secs() >= interval {
+            self.fetch_async();
+        }
+    }
+
+    /// Push the current branch, adding `--set-upstream` on its first push.
+    /// Returns `Ok(None)` when several remotes exist and the picker was opened.
+    fn push_current_branch(&mut self) -> Result<Option<String>> {
+        let Some(ref ops) = self.git_ops else { return Ok(None) };
+        if self.branch.upstream.is_some() {
+            return ops.push().map(Some);
+        }
+        let remotes = self.git_repo.as_ref()
+            .map(|r| r.remotes())
+            .unwrap_or_default();
+        match remotes.len() {
+            0 => anyhow::bail!("no remote configured"),
+            1 => ops.push_set_upstream(&remotes[0], &self.branch.name).map(Some),
+            _ => {
+                let title = format!("Push '{}' to remote", self.branch.name);
+                self.picker_state.open(PickerKind::PushRemote, &title, remotes);
+                self.focus = Focus::Picker;
+                Ok(None)
+            }
+        }
+    }
+
     pub fn refresh_diff(&mut self) {
         if let Some(ref repo) = self.git_repo {
             if let Some(idx) = self.status_state.selected_index() {
                 if let Some(file) = self.files.get(idx) {
                     let staged = file.stage_state == crate::git::status::StageState::Staged;
-                    match repo.diff_file(&file.path, staged) {
+                    let diff = if let Some(ref spec) = self.compare {
+                        repo.compare_diff(spec, &file.path, file.old_path.as_deref(), false)
+                    } else if file.submodule.is_some() {
+                        repo.submodule_diff(&file.path)
+                    } else {
+                        repo.diff_file(&file.path, file.old_path.as_deref(), staged)
+                    };
+                    match diff {
                         Ok(diff) => {
                             self.diff_state.set_file(&file.path);
                             self.diff_state.update_highlight_cache(&diff);
@@ -252,32 +526,66 @@ impl App {
     pub async fn handle_event(&mut self, event: AppEvent) -> Result<()> {
         match event {
             AppEvent::Key(key) => {
-                // Handle prompt dialog input directly
-                if self.focus == Focus::PromptDialog {
-                    self.handle_prompt_key(key).await?;
-                    return Ok(());
-                }
-
-                if let Some(action) = handler::handle_key(key, self.focus, self.active_tab) {
-                    self.handle_action(action).await?;
-                }
+                self.handle_key_event(key).await?;
+                self.measure_send_context();
             }
             AppEvent::PtyOutput(data) => {
                 self.pty_ready = true;
                 self.emulator.process(&data);
+                self.invalidate_links();
             }
             AppEvent::PtyExited => {
                 self.running = false;
             }
             AppEvent::Resize(_, _) => {}
-            AppEvent::Tick | AppEvent::GitRefresh => {
+            AppEvent::Tick => {
+                self.refresh_git();
+                self.maybe_fetch();
+            }
+            AppEvent::GitRefresh => {
+                self.refresh_git();
+            }
+            AppEvent::GitFetchDone(err) => {
+                self.git_fetching = false;
+                if let Some(e) = err {
+                    self.error_message = Some(format!("Fetch failed: {}", e));
+                }
                 self.refresh_git();
             }
-            AppEvent::GitStatusUpdate(files, branch) => {
+            AppEvent::GitPushTagsDone(result) => {
+                self.git_pushing_tags = false;
+                self.error_message = Some(match result {
+                    Ok(_) => "Pushed tags".to_string(),
+                    Err(e) => format!("Tag push failed: {}", e),
+                });
+            }
+            AppEvent::TurnRecorded(seq, _) if seq != self.turn_seq => {}
+            AppEvent::TurnRecorded(_, result) => match result {
+                Ok((snapshot, left_out)) => {
+                    self.turn = Some(snapshot);
+                    self.status_state.set_turn_changed(Default::default(), &self.files);
+                    if !left_out.is_empty() {
+                        self.error_message = Some(format!("Checkpoint left out large files: {}", left_out.join(", ")));
+                    }
+                }
+                Err(e) => self.error_message = Some(format!("Failed to record the worktree: {}", e)),
+            },
+            AppEvent::GitStatusUpdate(files, branch, changed) => {
                 self.git_refreshing = false;
-                self.files = files;
+                // Compare mode lists a fixed revision range; only the branch changes
+                if self.compare.is_none() {
+                    self.status_state.turn_changed = changed;
+                    self.set_files(files);
+                }
                 self.branch = branch;
             }
+            AppEvent::CommitDraftReady(text) => {
+                self.stop_drafting();
+                if self.prompt_state.visible && self.prompt_state.is_commit() {
+                    self.prompt_state.fill_draft(text);
+                    self.focus = Focus::PromptDialog;
+                }
+            }
             AppEvent::FocusGained => {
                 // Forward to PTY if it's focused and ready (has produced output)
                 if self.focus == Focus::Pty && self.pty_ready {
@@ -296,6 +604,58 @@ impl App {
         Ok(())
     }
 
+    async fn handle_key_event(&mut self, key: crossterm::event::KeyEvent) -> Result<()> {
+        // Handle prompt dialog input directly
+        if self.focus == Focus::PromptDialog {
+            self.handle_prompt_key(key).await?;
+            return Ok(());
+        }
+        if self.focus == Focus::Picker {
+            self.handle_picker_key(key).await?;
+            return Ok(());
+        }
+        if self.focus == Focus::Basket {
+            self.handle_basket_key(key).await?;
+            return Ok(());
+        }
+        if self.focus == Focus::GitStatus && self.status_state.filtering {
+            self.handle_filter_key(key);
+            return Ok(());
+        }
+        if self.focus == Focus::DiffView && self.diff_state.prompt.is_some() {
+            self.handle_diff_prompt_key(key);
+            return Ok(());
+        }
+
+        if self.pty_selection.active && self.active_tab == ActiveTab::ClaudeCode {
+            if let Some(action) = handler::handle_selection_key(key) {
+                self.handle_action(action).await?;
+                return Ok(());
+            }
+        }
+
+        if let Some(action) = handler::handle_key(key, self.focus, self.active_tab) {
+            self.handle_action(action).await?;
+        }
+        Ok(())
+    }
+
+    /// Size up the send dialog's references once each time its files or quotes change.
+    fn measure_send_context(&mut self) {
+        let state = &self.prompt_state;
+        if !state.visible || state.mode != PromptMode::SendToClaude || state.context.is_some() {
+            return;
+        }
+        // References are relative to the top-level repository
+        let root = self.submodule_stack.first().map(|(w, _)| w.as_str()).unwrap_or(&self.workdir);
+        let Ok(repo) = GitRepo::open(root) else { return };
+        let mut context = repo.context_size(&state.files, self.config.warn_context_bytes);
+        for quote in &state.quotes {
+            context.add_text(quote);
+        }
+        self.prompt_state.context = Some(context);
+    }
+
     fn on_divider(&self, col: u16, row: u16) -> bool {
         // The divider is the right edge of the PTY pane
         let divider_x = self.pty_rect.right();
@@ -353,6 +713,61 @@ impl App {
         text
     }
 
+    /// Links on PTY row `row`, detected on first use after a change.
+    fn row_links(&mut self, row: u16) -> &[Link] {
+        let workdir = std::path::Path::new(&self.workdir);
+        let (screen, hyperlinks) = (self.emulator.screen(), self.emulator.hyperlinks());
+        self.pty_links.entry(row)
+            .or_insert_with(|| links::detect_row(screen, row, workdir, hyperlinks))
+    }
+
+    fn link_at(&mut self, col: u16, row: u16) -> Option<Link> {
+        self.row_links(row).iter().find(|l| l.contains(col, row)).cloned()
+    }
+
+    fn invalidate_links(&mut self) {
+        self.pty_links.clear();
+        self.link_hover = None;
+    }
+
+    /// Links the PTY pane should underline.
+    pub fn underlined_links(&self) -> Vec<Link> {
+        if self.link_all {
+            self.pty_links.values().flatten().cloned().collect()
+        } else {
+            self.link_hover.iter().cloned().collect()
+        }
+    }
+
+    async fn follow_link(&mut self, target: LinkTarget) -> Result<()> {
+        match target {
+            LinkTarget::File(file_ref) => {
+                self.send_focus_events(self.focus, Focus::DiffView).await?;
+                self.open_file_ref(&file_ref);
+            }
+            LinkTarget::Url(url) => {
+                let opener = self.config.url_opener();
+                let mut parts = opener.split_whitespace();
+                let Some(program) = parts.next() else { return Ok(()) };
+                let spawned = std::process::Command::new(program)
+                    .args(parts)
+                    .arg(&url)
+                    .stdin(std::process::Stdio::null())
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null())
+                    .spawn();
+                match spawned {
+                    // Reap the opener so it does not linger as a zombie
+                    Ok(mut child) => {
+                        tokio::task::spawn_blocking(move || child.wait());
+                    }
+                    Err(e) => self.error_message = Some(format!("Failed to run {}: {}", program, e)),
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn copy_to_clipboard(text: &str) {
         use std::io::Write;
         use std::process::{Command, Stdio};
@@ -375,8 +790,8 @@ impl App {
     async fn handle_mouse(&mut self, mouse: crossterm::event::MouseEvent) -> Result<()> {
         match mouse.kind {
             MouseEventKind::Down(MouseButton::Left) => {
-                // Don't switch focus if prompt is open
-                if self.focus == Focus::PromptDialog {
+                // Don't switch focus if a modal is open
+                if matches!(self.focus, Focus::PromptDialog | Focus::Picker | Focus::Basket) {
                     return Ok(());
                 }
 
@@ -426,6 +841,30 @@ impl App {
                     if self.pty_selection.active {
                         let text = self.extract_selection_text();
                         Self::copy_to_clipboard(&text);
+                    } else {
+                        // A click without a drag follows the link under it
+                        let (col, row) = (self.pty_selection.start_col, self.pty_selection.start_row);
+                        if let Some(link) = self.link_at(col, row) {
+                            self.follow_link(link.target).await?;
+                        }
+                    }
+                }
+            }
+            MouseEventKind::Moved if self.active_tab == ActiveTab::ClaudeCode => {
+                match self.screen_to_pty(mouse.column, mouse.row) {
+                    Some((col, row)) => {
+                        self.link_hover = self.link_at(col, row);
+                        self.link_all = mouse.modifiers.contains(KeyModifiers::CONTROL);
+                        if self.link_all {
+                            let (rows, _) = self.emulator.screen().size();
+                            for row in 0..rows {
+                                self.row_links(row);
+                            }
+                        }
+                    }
+                    None => {
+                        self.link_hover = None;
+                        self.link_all = false;
                     }
                 }
             }
@@ -444,22 +883,20 @@ impl App {
                     self.pty_selection.active = true;
                 }
             }
-            MouseEventKind::ScrollDown => {
-                if self.active_tab == ActiveTab::ClaudeCode {
-                    if rect_contains(self.pty_rect, mouse.column, mouse.row) {
-                        self.emulator.scroll_down(3);
-                    } else if rect_contains(self.diff_rect, mouse.column, mouse.row) {
-                        self.handle_action(Action::DiffScrollAmount(3)).await?;
-                    }
+            MouseEventKind::ScrollDown if self.active_tab == ActiveTab::ClaudeCode => {
+                if rect_contains(self.pty_rect, mouse.column, mouse.row) {
+                    self.emulator.scroll_down(3);
+                    self.invalidate_links();
+                } else if rect_contains(self.diff_rect, mouse.column, mouse.row) {
+                    self.handle_action(Action::DiffScrollAmount(3)).await?;
                 }
             }
-            MouseEventKind::ScrollUp => {
-                if self.active_tab == ActiveTab::ClaudeCode {
-                    if rect_contains(self.pty_rect, mouse.column, mouse.row) {
-                        self.emulator.scroll_up(3);
-                    } else if rect_contains(self.diff_rect, mouse.column, mouse.row) {
-                        self.handle_action(Action::DiffScrollAmount(-3)).await?;
-                    }
+            MouseEventKind::ScrollUp if self.active_tab == ActiveTab::ClaudeCode => {
+                if rect_contains(self.pty_rect, mouse.column, mouse.row) {
+                    self.emulator.scroll_up(3);
+                    self.invalidate_links();
+                } else if rect_contains(self.diff_rect, mouse.column, mouse.row) {
+                    self.handle_action(Action::DiffScrollAmount(-3)).await?;
                 }
             }
             _ => {}
@@ -468,6 +905,9 @@ impl App {
     }
 
     async fn handle_action(&mut self, action: Action) -> Result<()> {
+        if !matches!(action, Action::DiffGoPrefix) {
+            self.diff_state.pending_g = false;
+        }
         match action {
             Action::Quit => {
                 self.running = false;
@@ -504,6 +944,8 @@ impl App {
                     Focus::Pty => Focus::GitStatus,
                     Focus::GitStatus | Focus::DiffView => Focus::Pty,
                     Focus::PromptDialog => Focus::PromptDialog,
+                    Focus::Picker => Focus::Picker,
+                    Focus::Basket => Focus::Basket,
                     Focus::FileBrowserLeft | Focus::FileBrowserRight => self.focus,
                 };
                 self.send_focus_events(old, self.focus).await?;
@@ -532,39 +974,66 @@ impl App {
                 };
             }
             Action::PtyInput(bytes) => {
-                self.emulator.reset_scroll();
+                if self.emulator.is_scrolled() {
+                    self.emulator.reset_scroll();
+                    self.invalidate_links();
+                }
                 self.pty_selection.clear();
                 self.pty.write_input(&bytes).await?;
             }
             Action::GitNavUp => {
-                let len = self.files.len();
-                self.status_state.move_up(len);
+                self.status_state.move_up();
                 self.refresh_diff();
             }
             Action::GitNavDown => {
-                let len = self.files.len();
-                self.status_state.move_down(len);
+                self.status_state.move_down();
                 self.refresh_diff();
             }
+            Action::GitToggleStage if self.compare.is_some() => {
+                self.error_message = Some(COMPARE_READ_ONLY.to_string());
+            }
             Action::GitToggleStage => {
-                if let Some(idx) = self.status_state.selected_index() {
-                    if let Some(file) = self.files.get(idx) {
-                        if let Some(ref ops) = self.git_ops {
-                            let path = file.path.clone();
-                            let result = if file.stage_state == crate::git::status::StageState::Staged {
-                                ops.unstage_file(&path)
-                            } else {
-                                ops.stage_file(&path)
-                            };
-                            if let Err(e) = result {
-                                self.error_message = Some(format!("{}", e));
-                            }
-                            self.refresh_git_sync();
-                            self.refresh_diff();
-                        }
+                // A directory row stages (or, if fully staged, unstages) every file below it;
+                // a section row unstages the Staged section and stages the others
+                let indices = self.status_state.selected_row_files(&self.files);
+                if let (false, Some(ref ops)) = (indices.is_empty(), &self.git_ops) {
+                    let unstage = match self.status_state.selected_section() {
+                        Some(section) => section == Section::Staged,
+                        None => indices.iter()
+                            .all(|&i| self.files[i].stage_state == crate::git::status::StageState::Staged),
+                    };
+                    // Renames touch both paths so git keeps pairing them up
+                    let paths: Vec<&str> = indices.iter()
+                        .flat_map(|&i| self.files[i].paths())
+                        .collect();
+                    let result = if unstage {
+                        ops.unstage_files(&paths)
+                    } else {
+                        ops.stage_files(&paths)
+                    };
+                    if let Err(e) = result {
+                        self.error_message = Some(format!("{}", e));
                     }
+                    self.refresh_git_sync();
+                    self.refresh_diff();
+                }
+            }
+            Action::ToggleTreeView => {
+                self.status_state.toggle_tree_view(&self.files);
+            }
+            Action::ToggleTurnFilter => {
+                if self.turn_snapshot().is_none() && !self.status_state.turn_only {
+                    self.error_message = Some("Nothing sent to Claude from here yet".to_string());
+                } else {
+                    self.status_state.toggle_turn_only(&self.files);
                 }
             }
+            Action::StartFilter => {
+                self.status_state.start_filter();
+            }
+            Action::GitStageAll if self.compare.is_some() => {
+                self.error_message = Some(COMPARE_READ_ONLY.to_string());
+            }
             Action::GitStageAll => {
                 if let Some(ref ops) = self.git_ops {
                     if let Err(e) = ops.stage_all() {
@@ -574,6 +1043,17 @@ impl App {
                 }
             }
             Action::GitShowDiff => {
+                if self.status_state.toggle_fold(&self.files) {
+                    return Ok(());
+                }
+                let submodule = self.status_state.selected_index()
+                    .and_then(|i| self.files.get(i))
+                    .filter(|f| f.submodule.is_some())
+                    .map(|f| f.path.clone());
+                if let Some(path) = submodule {
+                    self.enter_submodule(&path);
+                    return Ok(());
+                }
                 self.refresh_diff();
                 if self.current_diff.is_some() {
                     self.focus = Focus::DiffView;
@@ -584,7 +1064,11 @@ impl App {
                     if let Some(idx) = self.status_state.selected_index() {
                         if let Some(file) = self.files.get(idx) {
                             let staged = file.stage_state == crate::git::status::StageState::Staged;
-                            match repo.file_contents(&file.path, staged) {
+                            let contents = match self.compare {
+                                Some(ref spec) => repo.compare_diff(spec, &file.path, file.old_path.as_deref(), true),
+                                None => repo.file_contents(&file.path, file.old_path.as_deref(), staged),
+                            };
+                            match contents {
                                 Ok(diff) => {
                                     self.diff_state.set_file(&file.path);
                                     self.diff_state.update_highlight_cache(&diff);
@@ -599,15 +1083,17 @@ impl App {
                     }
                 }
             }
+            Action::GitDiscardFile if self.compare.is_some() => {
+                self.error_message = Some(COMPARE_READ_ONLY.to_string());
+            }
             Action::GitDiscardFile => {
                 if let Some(idx) = self.status_state.selected_index() {
                     if let Some(file) = self.files.get(idx) {
-                        if let Some(ref ops) = self.git_ops {
-                            let path = file.path.clone();
-                            if let Err(e) = ops.discard_file(&path) {
-                                self.error_message = Some(format!("{}", e));
-                            }
-                            self.refresh_git_sync();
+                        if file.submodule.is_some() {
+                            self.error_message = Some("Use U to reset a submodule to its recorded commit".to_string());
+                        } else if self.git_ops.is_some() {
+                            self.prompt_state.open_confirm(PromptMode::ConfirmDiscard, file.path.clone());
+                            self.focus = Focus::PromptDialog;
                         }
                     }
                 }
@@ -617,7 +1103,7 @@ impl App {
             }
             Action::DiffScrollDown => {
                 let max = self.current_diff.as_ref()
-                    .map(|d| d.total_lines())
+                    .map(|d| self.diff_state.row_count(d))
                     .unwrap_or(0);
                 self.diff_state.cursor_down(max);
                 // viewport height is approximate; ensure_visible will be called in draw
@@ -630,7 +1116,7 @@ impl App {
                     self.diff_state.scroll_up((-delta) as u16);
                 } else {
                     let max = self.current_diff.as_ref()
-                        .map(|d| d.total_lines() as u16)
+                        .map(|d| self.diff_state.row_count(d) as u16)
                         .unwrap_or(0);
                     self.diff_state.scroll_down(delta as u16, max);
                 }
@@ -643,10 +1129,9 @@ impl App {
             }
             Action::DiffNextHunk => {
                 if let Some(ref diff) = self.current_diff {
-                    let lines = diff.all_lines();
                     let current = self.diff_state.cursor;
-                    for (i, line) in lines.iter().enumerate().skip(current + 1) {
-                        if line.kind == crate::git::diff::DiffLineKind::HunkHeader {
+                    for i in current + 1..self.diff_state.row_count(diff) {
+                        if self.diff_state.is_header_row(diff, i) {
                             self.diff_state.cursor = i;
                             self.diff_state.ensure_visible(
                                 self.diff_rect.height.saturating_sub(2)
@@ -658,10 +1143,9 @@ impl App {
             }
             Action::DiffPrevHunk => {
                 if let Some(ref diff) = self.current_diff {
-                    let lines = diff.all_lines();
                     let current = self.diff_state.cursor;
                     for i in (0..current).rev() {
-                        if lines[i].kind == crate::git::diff::DiffLineKind::HunkHeader {
+                        if self.diff_state.is_header_row(diff, i) {
                             self.diff_state.cursor = i;
                             self.diff_state.ensure_visible(
                                 self.diff_rect.height.saturating_sub(2)
@@ -674,33 +1158,193 @@ impl App {
             Action::DiffToggleSelect => {
                 self.diff_state.toggle_select();
             }
+            Action::DiffSearch => {
+                self.diff_state.open_prompt(DiffPrompt::Search);
+            }
+            Action::DiffGotoLine => {
+                self.diff_state.open_prompt(DiffPrompt::GotoLine);
+            }
+            Action::DiffNextMatch | Action::DiffPrevMatch => {
+                let viewport = self.diff_rect.height.saturating_sub(2);
+                self.diff_state.next_match(matches!(action, Action::DiffNextMatch), viewport);
+            }
+            Action::DiffGoPrefix => {
+                // `gg` goes to the top
+                if std::mem::take(&mut self.diff_state.pending_g) {
+                    self.diff_state.top();
+                } else {
+                    self.diff_state.pending_g = true;
+                }
+            }
+            Action::DiffBottom | Action::DiffHalfPageDown | Action::DiffHalfPageUp => {
+                let viewport = self.diff_rect.height.saturating_sub(2);
+                if let Some(ref diff) = self.current_diff {
+                    match action {
+                        Action::DiffBottom => self.diff_state.bottom(diff, viewport),
+                        Action::DiffHalfPageDown => self.diff_state.half_page(diff, true, viewport),
+                        _ => self.diff_state.half_page(diff, false, viewport),
+                    }
+                }
+            }
+            Action::DiffContext(delta) => {
+                let settings = &mut self.diff_state.settings;
+                settings.context = settings.context.saturating_add_signed(delta).min(MAX_CONTEXT);
+                let msg = format!("Context: {} lines", settings.context);
+                self.apply_diff_settings(msg);
+            }
+            Action::DiffCycleWhitespace => {
+                let settings = &mut self.diff_state.settings;
+                settings.whitespace = settings.whitespace.next();
+                let msg = format!("Diff: {}", settings.whitespace.label());
+                self.apply_diff_settings(msg);
+            }
+            Action::DiffCycleAlgorithm => {
+                let settings = &mut self.diff_state.settings;
+                settings.algorithm = settings.algorithm.next();
+                let msg = format!("Diff algorithm: {}", settings.algorithm.label());
+                self.apply_diff_settings(msg);
+            }
+            Action::DiffExpandHunk { above } => {
+                self.expand_hunk(above);
+            }
+            Action::DiffExportPatch { clipboard } => {
+                match self.diff_view_patch() {
+                    Ok(patch) => {
+                        let name = self.current_diff.as_ref()
+                            .map(|d| d.path.rsplit('/').next().unwrap_or(&d.path).to_string())
+                            .unwrap_or_default();
+                        self.export_patch(patch, format!("{}.patch", name), clipboard);
+                    }
+                    Err(e) => self.error_message = Some(format!("{}", e)),
+                }
+            }
+            Action::DiffTagCommit => {
+                if let Some(sha) = self.diff_state.commit_view.clone() {
+                    self.prompt_state.open_create_tag(Some(sha));
+                    self.focus = Focus::PromptDialog;
+                } else {
+                    self.error_message = Some("Open a commit first (Enter in blame); T in the status list tags HEAD".to_string());
+                }
+            }
+            Action::DiffLoadFull => {
+                let collapsed = self.current_diff.as_ref().and_then(|d| d.collapsed);
+                let (Some(collapsed), Some(repo), Some(path)) = (collapsed, &self.git_repo, self.diff_state.file_path.clone()) else {
+                    return Ok(());
+                };
+                let file = self.files.iter().find(|f| f.path == path);
+                let staged = file.is_some_and(|f| f.stage_state == crate::git::status::StageState::Staged);
+                let old_path = file.and_then(|f| f.old_path.as_deref());
+                let full = match self.compare {
+                    Some(ref spec) => repo.load_full_compare_diff(spec, &path, old_path, collapsed),
+                    None => repo.load_full_diff(&path, old_path, staged, collapsed),
+                };
+                match full {
+                    Ok(diff) => {
+                        self.diff_state.update_highlight_cache(&diff);
+                        self.current_diff = Some(diff);
+                    }
+                    Err(e) => self.error_message = Some(format!("{}", e)),
+                }
+            }
+            Action::DiffToggleSplit => {
+                self.diff_state.toggle_split();
+                self.diff_state.ensure_visible(self.diff_rect.height.saturating_sub(2));
+            }
+            Action::DiffCycleWordDiff => {
+                self.diff_state.word_diff = self.diff_state.word_diff.next();
+                if let Some(ref diff) = self.current_diff {
+                    self.diff_state.update_emphasis(diff);
+                }
+                self.error_message = Some(format!("Word diff: {}", self.diff_state.word_diff.label()));
+            }
+            Action::DiffToggleBlame => {
+                if self.diff_state.blame.is_some() {
+                    self.diff_state.blame = None;
+                } else if self.compare.is_some() {
+                    self.error_message = Some("Blame is not available in compare mode".to_string());
+                } else if self.diff_state.commit_view.is_some() {
+                    self.error_message = Some("Blame is not available in the commit view".to_string());
+                } else if let (Some(repo), Some(path)) = (&self.git_repo, self.diff_state.file_path.clone()) {
+                    // Blame annotates the whole file, so switch to the expanded view
+                    let file = self.files.iter().find(|f| f.path == path);
+                    let staged = file.is_some_and(|f| f.stage_state == crate::git::status::StageState::Staged);
+                    let old_path = file.and_then(|f| f.old_path.as_deref());
+                    match repo.file_contents(&path, old_path, staged).and_then(|d| Ok((d, repo.blame_file(&path, staged)?))) {
+                        Ok((diff, blame)) => {
+                            self.diff_state.update_highlight_cache(&diff);
+                            self.current_diff = Some(diff);
+                            self.diff_state.blame = Some(blame);
+                        }
+                        Err(e) => self.error_message = Some(format!("{}", e)),
+                    }
+                }
+            }
+            Action::DiffShowCommit => {
+                let Some(ref diff) = self.current_diff else { return Ok(()) };
+                let lines = diff.all_lines();
+                let lineno = self.diff_state.row_lines(self.diff_state.cursor).iter()
+                    .find_map(|&i| lines.get(i).and_then(|l| l.new_lineno));
+                let sha = self.diff_state.blame_for(lineno).and_then(|b| b.commit.clone());
+                match (sha, &self.git_repo) {
+                    (Some(sha), Some(repo)) => match repo.commit_diff(&sha) {
+                        Ok(diff) => {
+                            self.diff_state.set_file(&diff.path);
+                            self.diff_state.update_highlight_cache(&diff);
+                            self.diff_state.commit_view = Some(sha);
+                            self.current_diff = Some(diff);
+                        }
+                        Err(e) => self.error_message = Some(format!("{}", e)),
+                    },
+                    (None, _) if self.diff_state.blame.is_some() => {
+                        self.error_message = Some("Line is not committed yet".to_string());
+                    }
+                    _ => {}
+                }
+            }
             Action::DiffSendLines => {
+                if self.diff_state.commit_view.is_some() {
+                    self.error_message = Some("Sending lines is not available in the commit view".to_string());
+                    return Ok(());
+                }
                 if let Some(ref diff) = self.current_diff {
                     let all_lines = diff.all_lines();
-                    // Get line range from selection or just cursor line
-                    let (start_idx, end_idx) = self.diff_state.selection_range()
-                        .unwrap_or((self.diff_state.cursor, self.diff_state.cursor));
+                    // Lines under the selection or cursor (new-file side in split view)
+                    let selected = self.diff_state.selected_lines();
 
-                    // Collect real line numbers from the selected range
+                    // Collect real line numbers (and blame commits) from the selected range
                     let mut line_nums: Vec<u32> = Vec::new();
-                    for i in start_idx..=end_idx {
+                    let mut commits: Vec<String> = Vec::new();
+                    for i in selected {
                         if i < all_lines.len() {
                             if let Some(n) = all_lines[i].new_lineno {
                                 line_nums.push(n);
                             } else if let Some(n) = all_lines[i].old_lineno {
                                 line_nums.push(n);
                             }
+                            if let Some(b) = self.diff_state.blame_for(all_lines[i].new_lineno) {
+                                if b.commit.is_some() && !commits.iter().any(|c| c == b.short_sha()) {
+                                    commits.push(b.short_sha().to_string());
+                                }
+                            }
                         }
                     }
 
                     if !line_nums.is_empty() {
                         let first = line_nums[0];
                         let last = *line_nums.last().unwrap();
-                        let cmd = if first == last {
-                            format!("@{}:{}\n", diff.path, first)
+                        // Relative to the top-level repo while drilled into a submodule
+                        let path = format!("{}{}", self.submodule_prefix(), diff.path);
+                        let range = if first == last {
+                            format!("@{}:{}", path, first)
+                        } else {
+                            format!("@{}:{}-{}", path, first, last)
+                        };
+                        let cmd = if commits.is_empty() {
+                            format!("{}\n", range)
                         } else {
-                            format!("@{}:{}-{}\n", diff.path, first, last)
+                            format!("{} (introduced in commits: {})\n", range, commits.join(", "))
                         };
+                        self.mark_turn(&cmd);
                         self.pty.inject_input(&cmd).await?;
                         self.pty.write_input(b"\x1b[I").await?;
                         self.diff_state.clear_select();
@@ -713,31 +1357,61 @@ impl App {
                 self.focus = Focus::GitStatus;
             }
             Action::SendToClaude => {
-                let selected = self.status_state.selected_files(&self.files);
+                let selected = self.expand_refs(self.status_state.selected_refs(&self.files));
                 if !selected.is_empty() {
                     let file_refs: Vec<String> = selected.iter()
-                        .map(|f| format!("@{}", f.path))
+                        .map(|p| format!("@{}", p))
                         .collect();
                     let cmd = format!("{}\n", file_refs.join(" "));
+                    self.mark_turn(&cmd);
                     self.pty.inject_input(&cmd).await?;
                     self.pty.write_input(b"\x1b[I").await?;
                     self.focus = Focus::Pty;
                 }
             }
             Action::SendToClaudeWithPrompt => {
-                let selected = self.status_state.selected_files(&self.files);
-                if !selected.is_empty() {
-                    let files: Vec<String> = selected.iter()
-                        .map(|f| f.path.clone())
-                        .collect();
+                let files = self.expand_refs(self.status_state.selected_refs(&self.files));
+                if !files.is_empty() {
+                    self.prompt_state.open_send(files);
+                    self.focus = Focus::PromptDialog;
+                }
+            }
+            Action::SelectionQuote => {
+                let text = self.extract_selection_text();
+                if !text.trim().is_empty() {
+                    let refs = file_refs::parse(&text, std::path::Path::new(&self.workdir));
+                    let prefix = self.submodule_prefix();
+                    let files = refs.iter().map(|r| format!("{}{}", prefix, r.reference())).collect();
                     self.prompt_state.open_send(files);
+                    self.prompt_state.set_quotes(vec![text]);
+                    self.pty_selection.clear();
+                    self.send_focus_events(self.focus, Focus::PromptDialog).await?;
                     self.focus = Focus::PromptDialog;
                 }
             }
+            Action::SelectionOpenRefs => {
+                let text = self.extract_selection_text();
+                self.file_ref_entries = file_refs::parse(&text, std::path::Path::new(&self.workdir));
+                if self.file_ref_entries.is_empty() {
+                    self.error_message = Some("No file:line references in the selection".to_string());
+                } else {
+                    let labels = self.file_ref_entries.iter().map(|r| r.label()).collect();
+                    self.pty_selection.clear();
+                    self.send_focus_events(self.focus, Focus::Picker).await?;
+                    self.picker_state.open(PickerKind::FileRef, "Open reference", labels);
+                    self.focus = Focus::Picker;
+                }
+            }
+            Action::BasketAdd => self.basket_add(),
+            Action::BasketOpen => {
+                self.basket_return = self.focus;
+                self.basket_open = true;
+                self.focus = Focus::Basket;
+            }
             Action::ToggleMultiSelect => {
                 self.status_state.toggle_multi_select();
                 if self.status_state.multi_select {
-                    self.status_state.toggle_select();
+                    self.status_state.toggle_select(&self.files);
                 }
             }
             Action::Commit => {
@@ -748,14 +1422,52 @@ impl App {
                 self.prompt_state.open_commit_and_push();
                 self.focus = Focus::PromptDialog;
             }
+            Action::DraftCommitMessage => {
+                let Some(ref repo) = self.git_repo else { return Ok(()) };
+                let staged: Vec<(String, usize, usize)> = self.files.iter()
+                    .filter(|f| f.index_status.is_some())
+                    .filter_map(|f| {
+                        let (adds, dels) = repo.diff_file(&f.path, f.old_path.as_deref(), true).ok()?.stats();
+                        Some((f.path.clone(), adds, dels))
+                    })
+                    .collect();
+                if staged.is_empty() {
+                    self.error_message = Some("Nothing staged to describe".to_string());
+                    return Ok(());
+                }
+                let Some(ref tx) = self.event_tx else { return Ok(()) };
+
+                let path = commit_draft::draft_path();
+                let _ = std::fs::remove_file(&path);
+                match commit_draft::watch(path.clone(), tx.clone()) {
+                    Ok(watcher) => {
+                        self.draft_watcher = Some(watcher);
+                        let cmd = commit_draft::build_request(&staged, &path);
+                        self.pty.inject_input(&cmd).await?;
+                        self.prompt_state.drafting = true;
+                    }
+                    Err(e) => self.error_message = Some(format!("Draft failed: {}", e)),
+                }
+            }
             Action::Push => {
-                if let Some(ref ops) = self.git_ops {
-                    match ops.push() {
-                        Ok(msg) => self.error_message = Some(format!("Pushed: {}", msg.trim())),
-                        Err(e) => self.error_message = Some(format!("Push failed: {}", e)),
+                match self.push_current_branch() {
+                    Ok(Some(msg)) => {
+                        self.error_message = Some(format!("Pushed: {}", msg.trim()));
+                        self.refresh_git_sync();
                     }
+                    Ok(None) => {}
+                    Err(e) => self.error_message = Some(format!("Push failed: {}", e)),
+                }
+            }
+            Action::ForcePush => {
+                if self.git_ops.is_some() {
+                    self.prompt_state.open_confirm(PromptMode::ConfirmForcePush, self.branch.name.clone());
+                    self.focus = Focus::PromptDialog;
                 }
             }
+            Action::Fetch => {
+                self.fetch_async();
+            }
             Action::Pull => {
                 if let Some(ref ops) = self.git_ops {
                     match ops.pull() {
@@ -785,6 +1497,64 @@ impl App {
                     self.refresh_git_sync();
                 }
             }
+            Action::StashDrop => {
+                match self.git_repo.as_ref().and_then(|r| r.stash_top()) {
+                    Some((_, msg)) => {
+                        self.prompt_state.open_confirm(PromptMode::ConfirmStashDrop, msg);
+                        self.focus = Focus::PromptDialog;
+                    }
+                    None => self.error_message = Some("No stash entries".to_string()),
+                }
+            }
+            Action::UndoList => {
+                if let Some(ref repo) = self.git_repo {
+                    match repo.undo_entries() {
+                        Ok(entries) if entries.is_empty() => {
+                            self.error_message = Some("Undo list is empty".to_string());
+                        }
+                        Ok(entries) => {
+                            let now = std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .map(|d| d.as_secs() as i64)
+                                .unwrap_or(0);
+                            let labels = entries.iter().map(|e| e.label(now)).collect();
+                            self.undo_entries = entries;
+                            self.picker_state.open(PickerKind::Undo, "Undo", labels);
+                            self.focus = Focus::Picker;
+                        }
+                        Err(e) => self.error_message = Some(format!("{}", e)),
+                    }
+                }
+            }
+            Action::Checkpoint => {
+                if let Some(ref repo) = self.git_repo {
+                    self.error_message = Some(match repo.create_checkpoint("taken with w", false) {
+                        Ok((Some(checkpoint), left_out)) if !left_out.is_empty() => format!(
+                            "Saved checkpoint #{} without large files: {}",
+                            checkpoint.number,
+                            left_out.join(", "),
+                        ),
+                        Ok((Some(checkpoint), _)) => format!("Saved checkpoint #{}", checkpoint.number),
+                        Ok((None, _)) => "Worktree unchanged since the last checkpoint".to_string(),
+                        Err(e) => format!("Checkpoint failed: {}", e),
+                    });
+                }
+            }
+            Action::CheckpointList => {
+                self.open_checkpoint_picker(0);
+            }
+            Action::CheckpointRollback => {
+                // The newest checkpoint that differs from the worktree: the
+                // state before the last prompt, or before the last rollback
+                let Some(ref repo) = self.git_repo else { return Ok(()) };
+                let target = repo.checkpoints().map(|list| list.into_iter()
+                    .find(|c| repo.checkpoint_files(c).is_ok_and(|f| !f.is_empty())));
+                match target {
+                    Ok(Some(checkpoint)) => self.restore_checkpoint(&checkpoint, None),
+                    Ok(None) => self.error_message = Some("No checkpoint differs from the worktree".to_string()),
+                    Err(e) => self.error_message = Some(format!("{}", e)),
+                }
+            }
             Action::CreateBranch => {
                 self.prompt_state.open_create_branch();
                 self.focus = Focus::PromptDialog;
@@ -797,14 +1567,93 @@ impl App {
                     self.refresh_git_sync();
                 }
             }
+            Action::SubmoduleList => {
+                if let Some(ref repo) = self.git_repo {
+                    match repo.submodules() {
+                        Ok(subs) if subs.is_empty() => {
+                            self.error_message = Some("No submodules".to_string());
+                        }
+                        Ok(subs) => {
+                            let items = subs.iter()
+                                .map(|(path, state)| match state.label() {
+                                    l if l.is_empty() => path.clone(),
+                                    l => format!("{}  ({})", path, l),
+                                })
+                                .collect();
+                            self.submodule_entries = subs.into_iter().map(|(p, _)| p).collect();
+                            self.picker_state.open(PickerKind::Submodule, "Submodules", items);
+                            self.focus = Focus::Picker;
+                        }
+                        Err(e) => self.error_message = Some(format!("{}", e)),
+                    }
+                }
+            }
+            Action::SubmoduleUpdate => {
+                // The highlighted submodule, or every submodule otherwise
+                let path = self.status_state.selected_index()
+                    .and_then(|i| self.files.get(i))
+                    .filter(|f| f.submodule.is_some())
+                    .map(|f| f.path.clone());
+                self.update_submodules(path.as_deref());
+            }
+            Action::SubmoduleLeave => {
+                if self.compare.is_some() {
+                    self.leave_compare();
+                } else {
+                    self.leave_submodule();
+                }
+            }
+            Action::CompareMenu => {
+                if self.compare.is_some() {
+                    self.leave_compare();
+                } else if let Some(ref repo) = self.git_repo {
+                    let main = repo.default_branch().unwrap_or_else(|| "main".to_string());
+                    self.compare_stashes = repo.stashes();
+                    let mut items = vec![
+                        format!("HEAD vs merge-base with {}", main),
+                        "Revisions (A..B)\u{2026}".to_string(),
+                    ];
+                    items.extend(self.compare_stashes.iter().map(|(name, _)| format!("{} vs HEAD", name)));
+                    self.picker_state.open(PickerKind::Compare, "Compare", items);
+                    self.focus = Focus::Picker;
+                }
+            }
+            Action::GitExportPatch { clipboard } => {
+                let Some(ref ops) = self.git_ops else { return Ok(()) };
+                let range = self.compare.as_ref().map(|c| c.range());
+                match ops.diff_patch(range.as_deref()) {
+                    Ok(patch) if patch.is_empty() => {
+                        let what = if range.is_some() { "No changes in the compared range" } else { "No staged changes" };
+                        self.error_message = Some(format!("{} to export", what));
+                    }
+                    Ok(patch) => {
+                        let name = if range.is_some() { "compare.patch" } else { "staged.patch" };
+                        self.export_patch(patch, name.to_string(), clipboard);
+                    }
+                    Err(e) => self.error_message = Some(format!("{}", e)),
+                }
+            }
+            Action::TagList => {
+                self.open_tag_picker();
+            }
+            Action::CompareReview => {
+                if let Some(spec) = self.compare.clone() {
+                    let paths = self.files.iter().map(|f| f.path.clone()).collect();
+                    let files = self.expand_refs(paths);
+                    let request = compare::review_request(&spec, &files);
+                    self.mark_turn(&request);
+                    self.pty.inject_input(&request).await?;
+                    self.focus = Focus::Pty;
+                } else {
+                    self.review_turn().await?;
+                }
+            }
             Action::BranchList => {
-                // TODO: branch picker UI — for now show branches in error_message
                 if let Some(ref ops) = self.git_ops {
                     match ops.branch_list() {
                         Ok(branches) => {
-                            self.error_message = Some(
-                                format!("Branches: {}", branches.join(", "))
-                            );
+                            self.picker_state.open(PickerKind::Branch, "Branches", branches);
+                            self.focus = Focus::Picker;
                         }
                         Err(e) => self.error_message = Some(format!("{}", e)),
                     }
@@ -895,18 +1744,61 @@ impl App {
                 self.file_browser.left.refresh();
                 self.file_browser.right.refresh();
             }
+            Action::FBToggleMark => {
+                self.file_browser.active_panel_mut().toggle_mark();
+            }
+            Action::FBSendToClaude => {
+                let refs = self.file_browser_refs();
+                if !refs.is_empty() {
+                    let file_refs: Vec<String> = refs.iter()
+                        .map(|p| format!("@{}", p))
+                        .collect();
+                    let cmd = format!("{}\n", file_refs.join(" "));
+                    self.mark_turn(&cmd);
+                    self.pty.inject_input(&cmd).await?;
+                    self.pty.write_input(b"\x1b[I").await?;
+                    self.file_browser.left.marked.clear();
+                    self.file_browser.right.marked.clear();
+                    self.active_tab = ActiveTab::ClaudeCode;
+                    self.focus = Focus::Pty;
+                }
+            }
+            Action::FBSendToClaudeWithPrompt => {
+                let refs = self.file_browser_refs();
+                if !refs.is_empty() {
+                    self.prompt_state.open_send(refs);
+                    self.focus = Focus::PromptDialog;
+                }
+            }
+            Action::FBApplyPatch => {
+                let entry = self.file_browser.active_panel_mut().selected_entry()
+                    .filter(|e| !e.is_dir)
+                    .map(|e| (e.path.clone(), e.name.clone()));
+                if let Some((path, name)) = entry {
+                    self.preview_patch(&path, &name);
+                }
+            }
         }
         Ok(())
     }
 
     async fn handle_prompt_key(&mut self, key: crossterm::event::KeyEvent) -> Result<()> {
+        if self.prompt_state.history_search.is_some() {
+            self.handle_history_search_key(key);
+            return Ok(());
+        }
         match (key.modifiers, key.code) {
             (KeyModifiers::NONE, KeyCode::Esc) => {
                 let was_fb = matches!(self.prompt_state.mode,
                     PromptMode::FBRename | PromptMode::FBMkdir | PromptMode::FBConfirmDelete
                 );
+                let was_fb = was_fb || self.active_tab == ActiveTab::FileBrowser;
+                let was_basket = self.prompt_state.basket || self.prompt_state.mode == PromptMode::SaveBasket;
                 self.prompt_state.close();
-                if was_fb {
+                self.stop_drafting();
+                if was_basket {
+                    self.focus = Focus::Basket;
+                } else if was_fb {
                     self.focus = match self.file_browser.active_panel {
                         crate::filebrowser::state::PanelSide::Left => Focus::FileBrowserLeft,
                         crate::filebrowser::state::PanelSide::Right => Focus::FileBrowserRight,
@@ -918,9 +1810,9 @@ impl App {
             (KeyModifiers::NONE, KeyCode::Enter) => {
                 match self.prompt_state.mode {
                     PromptMode::Commit => {
-                        if !self.prompt_state.input.is_empty() {
+                        if !self.prompt_state.input.text().is_empty() {
                             if let Some(ref ops) = self.git_ops {
-                                let msg = self.prompt_state.input.clone();
+                                let msg = self.prompt_state.input.text().to_string();
                                 if let Err(e) = ops.commit(&msg) {
                                     self.error_message = Some(format!("{}", e));
                                 }
@@ -928,35 +1820,143 @@ impl App {
                             }
                         }
                         self.prompt_state.close();
+                        self.stop_drafting();
                         self.focus = Focus::GitStatus;
                     }
                     PromptMode::CommitAndPush => {
-                        if !self.prompt_state.input.is_empty() {
+                        if !self.prompt_state.input.text().is_empty() {
                             if let Some(ref ops) = self.git_ops {
-                                let msg = self.prompt_state.input.clone();
+                                let msg = self.prompt_state.input.text().to_string();
                                 if let Err(e) = ops.stage_all() {
                                     self.error_message = Some(format!("Stage failed: {}", e));
                                 } else {
-                                    match ops.commit(&msg) {
-                                        Ok(()) => {
-                                            match ops.push() {
-                                                Ok(out) => self.error_message = Some(format!("Committed & pushed: {}", out.trim())),
-                                                Err(e) => self.error_message = Some(format!("Committed but push failed: {}", e)),
-                                            }
+                                    if let Err(e) = ops.commit(&msg) {
+                                        self.error_message = Some(format!("{}", e));
+                                    } else {
+                                        self.prompt_state.close();
+                                        self.focus = Focus::GitStatus;
+                                        match self.push_current_branch() {
+                                            Ok(Some(out)) => self.error_message = Some(format!("Committed & pushed: {}", out.trim())),
+                                            Ok(None) => {}
+                                            Err(e) => self.error_message = Some(format!("Committed but push failed: {}", e)),
                                         }
-                                        Err(e) => self.error_message = Some(format!("{}", e)),
                                     }
                                 }
                                 self.refresh_git_sync();
                             }
                         }
-                        self.prompt_state.close();
-                        self.focus = Focus::GitStatus;
-                    }
+                        if self.prompt_state.visible {
+                            self.prompt_state.close();
+                            self.focus = Focus::GitStatus;
+                        }
+                        self.stop_drafting();
+                    }
+                    PromptMode::ConfirmDiscard => {
+                        if self.prompt_state.confirmed() {
+                            if let Some(path) = self.prompt_state.files.first().cloned() {
+                                self.discard_path(&path);
+                            }
+                        }
+                        self.prompt_state.close();
+                        self.focus = Focus::GitStatus;
+                    }
+                    PromptMode::ConfirmStashDrop => {
+                        if self.prompt_state.confirmed() {
+                            self.drop_stash();
+                        }
+                        self.prompt_state.close();
+                        self.focus = Focus::GitStatus;
+                    }
+                    PromptMode::ConfirmDeleteBranch => {
+                        if self.prompt_state.confirmed() {
+                            if let Some(name) = self.prompt_state.files.first().cloned() {
+                                self.delete_branch(&name);
+                            }
+                        }
+                        self.prompt_state.close();
+                        self.focus = Focus::GitStatus;
+                    }
+                    PromptMode::ConfirmDeleteTag => {
+                        if self.prompt_state.confirmed() {
+                            if let Some(name) = self.prompt_state.files.first().cloned() {
+                                self.delete_tag(&name);
+                            }
+                        }
+                        self.prompt_state.close();
+                        self.focus = Focus::GitStatus;
+                        self.refresh_git_sync();
+                    }
+                    PromptMode::CreateTag => {
+                        let input = self.prompt_state.input.text().trim().to_string();
+                        let target = self.prompt_state.tag_target.clone();
+                        self.prompt_state.close();
+                        self.focus = Focus::GitStatus;
+                        if let (false, Some(ops)) = (input.is_empty(), &self.git_ops) {
+                            let (name, message) = match input.split_once(char::is_whitespace) {
+                                Some((name, message)) => (name, Some(message.trim())),
+                                None => (input.as_str(), None),
+                            };
+                            let on = target.as_deref().map_or("HEAD".to_string(), |s| s[..s.len().min(7)].to_string());
+                            self.error_message = Some(match ops.create_tag(name, target.as_deref(), message) {
+                                Ok(()) => format!("Tagged {} as {}", on, name),
+                                Err(e) => format!("{}", e),
+                            });
+                            self.refresh_git_sync();
+                        }
+                    }
+                    PromptMode::ConfirmForcePush => {
+                        if self.prompt_state.confirmed() {
+                            if let Some(ref ops) = self.git_ops {
+                                match ops.push_force_with_lease() {
+                                    Ok(out) => self.error_message = Some(format!("Force-pushed: {}", out.trim())),
+                                    Err(e) => self.error_message = Some(format!("{}", e)),
+                                }
+                                self.refresh_git_sync();
+                            }
+                        }
+                        self.prompt_state.close();
+                        self.focus = Focus::GitStatus;
+                    }
+                    PromptMode::ExportPatch => {
+                        let target = self.prompt_state.input.text().trim().to_string();
+                        let patch = self.prompt_state.export_patch.take();
+                        self.prompt_state.close();
+                        self.focus = Focus::GitStatus;
+                        if let (Some(patch), false) = (patch, target.is_empty()) {
+                            use std::io::Write;
+                            let path = std::path::Path::new(&self.workdir).join(&target);
+                            // Never clobber an existing file; ask for another name instead
+                            let written = std::fs::OpenOptions::new()
+                                .write(true)
+                                .create_new(true)
+                                .open(&path)
+                                .and_then(|mut f| f.write_all(patch.as_bytes()));
+                            match written {
+                                Ok(()) => self.error_message = Some(format!("Wrote {} ({} lines)", target, patch.lines().count())),
+                                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                                    self.error_message = Some(format!("{} already exists, choose another name", target));
+                                    self.prompt_state.open_export_patch(patch, target);
+                                    self.focus = Focus::PromptDialog;
+                                }
+                                Err(e) => self.error_message = Some(format!("Failed to write {}: {}", target, e)),
+                            }
+                        }
+                    }
+                    PromptMode::CompareRevs => {
+                        let input = self.prompt_state.input.text().to_string();
+                        self.prompt_state.close();
+                        self.focus = Focus::GitStatus;
+                        if let (Some((base, target, merge_base)), Some(repo)) = (compare::parse_range(&input), &self.git_repo) {
+                            match repo.compare_revs(&base, &target, merge_base) {
+                                Ok(spec) => self.enter_compare(spec),
+                                Err(e) => self.error_message = Some(format!("{:#}", e)),
+                            }
+                        }
+                    }
                     PromptMode::CreateBranch => {
-                        if !self.prompt_state.input.is_empty() {
+                        if !self.prompt_state.input.text().is_empty() {
                             if let Some(ref ops) = self.git_ops {
-                                let name = self.prompt_state.input.clone();
+                                let name = self.prompt_state.input.text().to_string();
                                 if let Err(e) = ops.create_branch(&name) {
                                     self.error_message = Some(format!("{}", e));
                                 } else {
@@ -969,10 +1969,34 @@ impl App {
                         self.focus = Focus::GitStatus;
                     }
                     PromptMode::SendToClaude => {
-                        if !self.prompt_state.input.is_empty() || !self.prompt_state.files.is_empty() {
-                            let cmd = self.prompt_state.build_command();
-                            self.pty.inject_input(&cmd).await?;
+                        if !self.prompt_state.input.text().is_empty()
+                            || !self.prompt_state.files.is_empty()
+                            || !self.prompt_state.quotes.is_empty()
+                        {
+                            self.mark_turn(&self.prompt_state.build_command());
+                            if self.prompt_state.quotes.is_empty() && !self.prompt_state.input.text().contains('\n') {
+                                let cmd = self.prompt_state.build_command();
+                                self.pty.inject_input(&cmd).await?;
+                            } else {
+                                self.pty.paste(&self.prompt_state.build_message()).await?;
+                                self.pty.write_input(b"\r").await?;
+                            }
                             self.pty.write_input(b"\x1b[I").await?;
+                            if !self.prompt_state.input.text().trim().is_empty() {
+                                if let Err(e) = self.prompt_history.push(self.prompt_state.input.text(), &self.prompt_state.files) {
+                                    self.error_message = Some(format!("Failed to save prompt history: {:#}", e));
+                                }
+                            }
+                            if self.prompt_state.basket {
+                                self.basket.clear();
+                                self.save_basket();
+                                self.basket_open = false;
+                            }
+                            if self.active_tab == ActiveTab::FileBrowser {
+                                self.file_browser.left.marked.clear();
+                                self.file_browser.right.marked.clear();
+                                self.active_tab = ActiveTab::ClaudeCode;
+                            }
                             self.prompt_state.close();
                             self.focus = Focus::Pty;
                             return Ok(());
@@ -980,9 +2004,28 @@ impl App {
                         self.prompt_state.close();
                         self.focus = Focus::GitStatus;
                     }
+                    PromptMode::SaveBasket => {
+                        let name = self.prompt_state.input.text().trim().to_string();
+                        if !name.is_empty() {
+                            let saved = match self.state_dir {
+                                Some(ref dir) => basket::saved_path(dir, &name)
+                                    .and_then(|path| basket::write(&path, &self.basket.items)),
+                                None => Err(anyhow::anyhow!("No state directory to save baskets in")),
+                            };
+                            match saved {
+                                Ok(()) => {
+                                    self.basket.name = Some(name.clone());
+                                    self.error_message = Some(format!("Saved basket '{}'", name));
+                                }
+                                Err(e) => self.error_message = Some(format!("{:#}", e)),
+                            }
+                        }
+                        self.prompt_state.close();
+                        self.focus = Focus::Basket;
+                    }
                     PromptMode::FBConfirmDelete => {
                         // input == "yes" to confirm
-                        if self.prompt_state.input.to_lowercase() == "yes" {
+                        if self.prompt_state.confirmed() {
                             if let Some(ref path) = self.prompt_state.fb_delete_path {
                                 let p = path.clone();
                                 match operations::delete_path(&p) {
@@ -1001,10 +2044,10 @@ impl App {
                         };
                     }
                     PromptMode::FBRename => {
-                        if !self.prompt_state.input.is_empty() {
+                        if !self.prompt_state.input.text().is_empty() {
                             if let Some(ref path) = self.prompt_state.fb_rename_path {
                                 let p = path.clone();
-                                let new_name = self.prompt_state.input.clone();
+                                let new_name = self.prompt_state.input.text().to_string();
                                 match operations::rename_entry(&p, &new_name) {
                                     Ok(()) => {
                                         self.file_browser.left.refresh();
@@ -1021,10 +2064,10 @@ impl App {
                         };
                     }
                     PromptMode::FBMkdir => {
-                        if !self.prompt_state.input.is_empty() {
+                        if !self.prompt_state.input.text().is_empty() {
                             if let Some(ref parent) = self.prompt_state.fb_mkdir_parent {
                                 let p = parent.clone();
-                                let name = self.prompt_state.input.clone();
+                                let name = self.prompt_state.input.text().to_string();
                                 match operations::create_dir(&p, &name) {
                                     Ok(()) => {
                                         self.file_browser.left.refresh();
@@ -1042,17 +2085,1010 @@ impl App {
                     }
                 }
             }
+            (KeyModifiers::CONTROL, KeyCode::Char('t')) if self.prompt_state.mode == PromptMode::SendToClaude => {
+                self.open_template_picker();
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('r')) if self.prompt_state.mode == PromptMode::SendToClaude => {
+                self.prompt_state.start_search(&self.prompt_history);
+            }
+            // Up/Down move between input lines and recall history past the first/last
+            (KeyModifiers::NONE, KeyCode::Up)
+                if self.prompt_state.mode == PromptMode::SendToClaude && self.prompt_state.input.on_first_line() =>
+            {
+                self.prompt_state.history_prev(&self.prompt_history);
+            }
+            (KeyModifiers::NONE, KeyCode::Down)
+                if self.prompt_state.mode == PromptMode::SendToClaude && self.prompt_state.input.on_last_line() =>
+            {
+                self.prompt_state.history_next(&self.prompt_history);
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('g')) if self.prompt_state.is_commit() => {
+                self.handle_action(Action::DraftCommitMessage).await?;
+            }
+            _ => {
+                let multiline = self.prompt_state.is_multiline();
+                self.prompt_state.input.handle_key(key, multiline);
+            }
+        }
+        Ok(())
+    }
+
+    /// Keys while Ctrl-R searches the prompt history.
+    fn handle_history_search_key(&mut self, key: crossterm::event::KeyEvent) {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) | (KeyModifiers::CONTROL, KeyCode::Char('g')) => {
+                self.prompt_state.cancel_search();
+            }
+            (_, KeyCode::Enter) => self.prompt_state.accept_search(),
+            (KeyModifiers::CONTROL, KeyCode::Char('r')) => {
+                self.prompt_state.next_search_result(&self.prompt_history);
+            }
+            (_, KeyCode::Backspace) => {
+                if let Some(ref mut search) = self.prompt_state.history_search {
+                    search.query.pop();
+                }
+                self.prompt_state.update_search(&self.prompt_history);
+            }
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                if let Some(ref mut search) = self.prompt_state.history_search {
+                    search.query.push(c);
+                }
+                self.prompt_state.update_search(&self.prompt_history);
+            }
+            _ => {}
+        }
+    }
+
+    /// Add what is under the cursor to the basket: the selected status files,
+    /// the selected diff lines or the highlighted file browser entry.
+    fn basket_add(&mut self) {
+        let items: Vec<BasketItem> = if self.active_tab == ActiveTab::FileBrowser {
+            let refs = self.file_browser_refs();
+            self.file_browser.active_panel_mut().marked.clear();
+            refs.into_iter()
+                .map(|path| BasketItem::File { path })
+                .collect()
+        } else if self.focus == Focus::DiffView {
+            if self.diff_state.commit_view.is_some() {
+                self.error_message = Some("Adding lines is not available in the commit view".to_string());
+                return;
+            }
+            let Some((path, start, end)) = self.diff_line_range() else { return };
+            self.diff_state.clear_select();
+            vec![BasketItem::Lines { path, start, end }]
+        } else {
+            self.expand_refs(self.status_state.selected_refs(&self.files)).into_iter()
+                .map(|path| BasketItem::File { path })
+                .collect()
+        };
+        if items.is_empty() {
+            return;
+        }
+        let added = items.into_iter().filter(|i| self.basket.add(i.clone())).count();
+        self.save_basket();
+        self.error_message = Some(match added {
+            0 => "Already in the basket".to_string(),
+            n => format!("Added {} to the basket ({} items, I to open)", n, self.basket.items.len()),
+        });
+    }
+
+    /// Path (relative to the top-level repo) and first/last line under the
+    /// diff view's selection or cursor.
+    fn diff_line_range(&self) -> Option<(String, u32, u32)> {
+        let diff = self.current_diff.as_ref()?;
+        let all_lines = diff.all_lines();
+        let line_nums: Vec<u32> = self.diff_state.selected_lines().into_iter()
+            .filter_map(|i| all_lines.get(i))
+            .filter_map(|l| l.new_lineno.or(l.old_lineno))
+            .collect();
+        let (&start, &end) = (line_nums.first()?, line_nums.last()?);
+        Some((format!("{}{}", self.submodule_prefix(), diff.path), start, end))
+    }
+
+    /// Show the whole file (with its changes) in the diff view at the
+    /// referenced line.
+    fn open_file_ref(&mut self, file_ref: &FileRef) {
+        let Some(ref repo) = self.git_repo else { return };
+        match repo.file_contents(&file_ref.path, None, false) {
+            Ok(diff) => {
+                self.diff_state.set_file(&diff.path);
+                self.diff_state.update_highlight_cache(&diff);
+                let viewport = self.diff_rect.height.saturating_sub(2);
+                if !self.diff_state.goto_line(&diff, file_ref.line, viewport) {
+                    self.error_message = Some(format!("{} has no line {}", file_ref.path, file_ref.line));
+                }
+                self.current_diff = Some(diff);
+                self.focus = Focus::DiffView;
+            }
+            Err(e) => self.error_message = Some(format!("{}", e)),
+        }
+    }
+
+    fn open_template_picker(&mut self) {
+        let root = self.submodule_stack.first().map(|(w, _)| w.as_str()).unwrap_or(&self.workdir);
+        self.template_entries = prompts::load(root);
+        let labels = self.template_entries.iter().map(|t| t.label()).collect();
+        self.picker_state.open(PickerKind::PromptTemplate, "Prompt templates", labels);
+        self.focus = Focus::Picker;
+    }
+
+    /// Fill the send dialog with `template`, rendered for its files, the
+    /// quoted (or PTY) selection, the branch and the diff view's line range.
+    fn apply_template(&mut self, template: &PromptTemplate) {
+        let files = self.prompt_state.files.clone();
+        let selection = if self.prompt_state.quotes.is_empty() {
+            Some(self.extract_selection_text()).filter(|s| !s.trim().is_empty())
+        } else {
+            Some(self.prompt_state.quotes.join("\n\n"))
+        };
+        let diff = match (&self.git_ops, template.uses("diff")) {
+            (Some(ops), true) => {
+                let prefix = self.submodule_prefix();
+                let paths: Vec<String> = files.iter()
+                    .map(|f| f.strip_prefix(prefix.as_str()).unwrap_or(f).to_string())
+                    .collect();
+                match ops.diff_files(&paths) {
+                    Ok(diff) => Some(diff),
+                    Err(e) => {
+                        self.error_message = Some(format!("{:#}", e));
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        let line_range = match self.diff_line_range() {
+            Some((path, start, end)) if start == end => Some(format!("{}:{}", path, start)),
+            Some((path, start, end)) => Some(format!("{}:{}-{}", path, start, end)),
+            None => None,
+        };
+        let ctx = TemplateContext {
+            files,
+            selection,
+            branch: self.branch.name.clone(),
+            diff,
+            line_range: line_range.filter(|_| self.diff_state.commit_view.is_none()),
+        };
+        if template.uses("selection") {
+            self.prompt_state.set_quotes(Vec::new());
+        }
+        self.prompt_state.set_input(prompts::render(&template.body, &ctx));
+    }
+
+    /// Marked (or highlighted) file browser entries, relative to the
+    /// top-level repo when inside it.
+    fn file_browser_refs(&mut self) -> Vec<String> {
+        let root = self.submodule_stack.first().map(|(w, _)| w.clone()).unwrap_or_else(|| self.workdir.clone());
+        self.file_browser.active_panel_mut().marked_or_selected().iter()
+            .map(|p| p.strip_prefix(&root).unwrap_or(p).to_string_lossy().to_string())
+            .collect()
+    }
+
+    async fn handle_basket_key(&mut self, key: crossterm::event::KeyEvent) -> Result<()> {
+        match (key.modifiers, key.code) {
+            (KeyModifiers::NONE, KeyCode::Esc) | (KeyModifiers::NONE, KeyCode::Char('q')) => {
+                self.basket_open = false;
+                self.focus = self.basket_return;
+            }
+            (KeyModifiers::NONE, KeyCode::Char('k')) | (KeyModifiers::NONE, KeyCode::Up) => {
+                self.basket.move_up();
+            }
+            (KeyModifiers::NONE, KeyCode::Char('j')) | (KeyModifiers::NONE, KeyCode::Down) => {
+                self.basket.move_down();
+            }
+            (KeyModifiers::SHIFT, KeyCode::Char('K')) | (KeyModifiers::SHIFT, KeyCode::Char('k')) => {
+                self.basket.reorder(true);
+                self.save_basket();
+            }
+            (KeyModifiers::SHIFT, KeyCode::Char('J')) | (KeyModifiers::SHIFT, KeyCode::Char('j')) => {
+                self.basket.reorder(false);
+                self.save_basket();
+            }
+            (KeyModifiers::NONE, KeyCode::Char('d')) | (KeyModifiers::NONE, KeyCode::Delete) => {
+                self.basket.remove_selected();
+                self.save_basket();
+            }
+            (KeyModifiers::NONE, KeyCode::Char('c')) => {
+                self.basket.clear();
+                self.save_basket();
+            }
+            (KeyModifiers::NONE, KeyCode::Char('p')) => {
+                let text = self.extract_selection_text();
+                if text.trim().is_empty() {
+                    self.error_message = Some("Select text in the Claude pane first".to_string());
+                } else {
+                    self.basket.add(BasketItem::Text { text });
+                    self.pty_selection.clear();
+                    self.save_basket();
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Char('w')) if !self.basket.items.is_empty() => {
+                self.prompt_state.open_save_basket(self.basket.name.as_deref());
+                self.focus = Focus::PromptDialog;
+            }
+            (KeyModifiers::NONE, KeyCode::Char('o')) => self.open_basket_picker(),
+            (KeyModifiers::NONE, KeyCode::Enter) if !self.basket.items.is_empty() => {
+                self.prompt_state.open_send_basket(self.basket.references(), self.basket.quotes());
+                self.focus = Focus::PromptDialog;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn open_basket_picker(&mut self) {
+        let names = self.state_dir.as_ref().map(|d| basket::saved_names(d)).unwrap_or_default();
+        if names.is_empty() {
+            self.error_message = Some("No saved baskets (w saves one)".to_string());
+            return;
+        }
+        self.picker_state.open(PickerKind::Basket, "Open saved basket", names);
+        self.focus = Focus::Picker;
+    }
+
+    /// Keep the working basket on disk so it survives restarts.
+    fn save_basket(&mut self) {
+        let Some(ref dir) = self.state_dir else { return };
+        let path = basket::current_path(dir);
+        let result = if self.basket.items.is_empty() {
+            std::fs::remove_file(&path).or_else(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            }).map_err(anyhow::Error::from)
+        } else {
+            basket::write(&path, &self.basket.items)
+        };
+        if let Err(e) = result {
+            self.error_message = Some(format!("Failed to save the basket: {:#}", e));
+        }
+    }
+
+    /// Snapshot a file into the undo log, then discard its changes
+    /// (or delete it when untracked).
+    fn discard_path(&mut self, path: &str) {
+        let file = self.files.iter().find(|f| f.path == path);
+        let untracked = file.is_some_and(|f| f.worktree_status == Some(FileStatusKind::Untracked));
+        // A worktree rename is an untracked new path plus a deleted old one
+        let renamed_from = file
+            .filter(|f| f.worktree_status == Some(FileStatusKind::Renamed))
+            .and_then(|f| f.old_path.clone());
+        if let (Some(repo), Some(ops)) = (&self.git_repo, &self.git_ops) {
+            let entry = match repo.snapshot_discard(path) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.error_message = Some(format!("Discard aborted, snapshot failed: {}", e));
+                    return;
+                }
+            };
+            let result = match renamed_from {
+                Some(ref old) => ops.clean_file(path).and_then(|_| ops.discard_file(old)),
+                None if untracked => ops.clean_file(path),
+                None => ops.discard_file(path),
+            };
+            if let Err(e) = result {
+                let _ = repo.drop_undo(&entry);
+                self.error_message = Some(format!("{}", e));
+            }
+        }
+        self.refresh_git_sync();
+        self.refresh_diff();
+    }
+
+    /// Replace submodule paths with the changed files inside them (when any)
+    /// and make refs relative to the top-level repo while drilled in.
+    fn expand_refs(&self, refs: Vec<String>) -> Vec<String> {
+        let prefix = self.submodule_prefix();
+        let mut out = Vec::new();
+        for r in refs {
+            let is_submodule = self.files.iter().any(|f| f.path == r && f.submodule.is_some());
+            let changed = match (is_submodule, &self.git_repo) {
+                (true, Some(repo)) => repo.submodule_changed_files(&r).unwrap_or_default(),
+                _ => Vec::new(),
+            };
+            if changed.is_empty() {
+                out.push(format!("{}{}", prefix, r));
+            } else {
+                out.extend(changed.into_iter().map(|c| format!("{}{}", prefix, c)));
+            }
+        }
+        out
+    }
+
+    /// Path of the current submodule relative to the top-level repo ("" at the top).
+    fn submodule_prefix(&self) -> String {
+        self.submodule_stack.iter()
+            .map(|(_, path)| format!("{}/", path))
+            .collect()
+    }
+
+    fn update_submodules(&mut self, path: Option<&str>) {
+        if let Some(ref ops) = self.git_ops {
+            match ops.submodule_update(path) {
+                Ok(_) => self.error_message = Some(format!("Updated {}", path.unwrap_or("all submodules"))),
+                Err(e) => self.error_message = Some(format!("{}", e)),
+            }
+            self.refresh_git_sync();
+            self.refresh_diff();
+        }
+    }
+
+    /// Point the git pane at a submodule's own repository.
+    fn enter_submodule(&mut self, path: &str) {
+        let Some(ref repo) = self.git_repo else { return };
+        match repo.open_submodule(path) {
+            Ok(sub) => {
+                let workdir = sub.workdir()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                self.git_ops = Some(GitOps::new(&workdir));
+                self.git_repo = Some(sub);
+                let parent = std::mem::replace(&mut self.workdir, workdir);
+                self.compare = None;
+                self.submodule_stack.push((parent, path.to_string()));
+                self.reload_repo();
+            }
+            Err(e) => self.error_message = Some(format!("{}", e)),
+        }
+    }
+
+    /// Return to the parent repository after `enter_submodule`.
+    fn leave_submodule(&mut self) {
+        let Some((workdir, _)) = self.submodule_stack.pop() else { return };
+        self.git_repo = GitRepo::open(&workdir)
+            .ok()
+            .map(|r| r.with_rename_threshold(self.config.rename_threshold)
+                .with_diff_limits(self.config.diff_limits())
+                .with_diff_settings(self.diff_state.settings));
+        self.git_ops = self.git_repo.as_ref()
+            .and_then(|r| r.workdir())
+            .map(|p| GitOps::new(&p.to_string_lossy()));
+        self.workdir = workdir;
+        self.compare = None;
+        self.reload_repo();
+    }
+
+    /// Patch for the diff view: the hunks under the selection, or the whole diff.
+    fn diff_view_patch(&self) -> Result<String> {
+        let diff = self.current_diff.as_ref().context("No diff to export")?;
+        let hunks = self.diff_state.selection_range().map(|_| {
+            let mut hunks: Vec<usize> = self.diff_state.selected_lines()
+                .into_iter()
+                .filter_map(|line| diff.hunk_of_line(line))
+                .collect();
+            hunks.dedup();
+            hunks
+        });
+        let file = self.files.iter().find(|f| f.path == diff.path);
+        let old_path = file.and_then(|f| f.old_path.as_deref());
+        let staged = file.is_some_and(|f| f.stage_state == crate::git::status::StageState::Staged);
+        let modes = self.git_repo.as_ref()
+            .map_or((0, 0), |r| r.file_modes(&diff.path, old_path, staged, self.compare.as_ref()));
+        patch::format_patch(diff, old_path, hunks.as_deref(), modes)
+    }
+
+    /// Copy `patch` to the clipboard, or ask for a file to write it to.
+    fn export_patch(&mut self, patch: String, default_name: String, clipboard: bool) {
+        if clipboard {
+            Self::copy_to_clipboard(&patch);
+            self.error_message = Some(format!("Copied patch ({} lines) to the clipboard", patch.lines().count()));
+        } else {
+            self.prompt_state.open_export_patch(patch, default_name);
+            self.focus = Focus::PromptDialog;
+        }
+    }
+
+    /// Show a patch file in the diff view and ask where to apply it;
+    /// each target is checked first so the picker says whether it applies.
+    fn preview_patch(&mut self, path: &std::path::Path, name: &str) {
+        let (Some(repo), Some(ops)) = (&self.git_repo, &self.git_ops) else { return };
+        let preview = match std::fs::read(path).map_err(anyhow::Error::from).and_then(|b| repo.patch_preview(&b, name)) {
+            Ok(preview) => preview,
+            Err(e) => {
+                self.error_message = Some(format!("{}", e));
+                return;
+            }
+        };
+        let patch = path.to_string_lossy().to_string();
+        let check = |cached: bool| match ops.apply_patch(&patch, cached, false, true) {
+            Ok(_) => "applies cleanly".to_string(),
+            Err(e) => format!(
+                "does not apply: {}",
+                e.to_string().lines().last().unwrap_or("").trim().trim_start_matches("error: "),
+            ),
+        };
+        let items = vec![
+            format!("Worktree \u{2014} {}", check(false)),
+            format!("Index (--cached) \u{2014} {}", check(true)),
+            "Worktree, leaving rejected hunks in *.rej files".to_string(),
+        ];
+        let (adds, dels) = preview.stats();
+        let title = format!("Apply {} (+{} -{})", name, adds, dels);
+
+        // Neither the file browser nor the picker is the PTY, so no focus events are due
+        self.active_tab = ActiveTab::ClaudeCode;
+        self.diff_state.reset();
+        self.diff_state.set_file(&preview.path);
+        self.diff_state.update_highlight_cache(&preview);
+        self.current_diff = Some(preview);
+        self.apply_patch_path = Some(patch);
+        self.picker_state.open(PickerKind::ApplyPatch, &title, items);
+        self.focus = Focus::Picker;
+    }
+
+    fn apply_patch(&mut self, patch: &str, cached: bool, reject: bool) {
+        let Some(ref ops) = self.git_ops else { return };
+        let name = patch.rsplit('/').next().unwrap_or(patch);
+        let target = if cached { "the index" } else { "the worktree" };
+        self.error_message = Some(match ops.apply_patch(patch, cached, reject, false) {
+            Ok(_) => format!("Applied {} to {}", name, target),
+            Err(e) => match e.to_string().matches("Rejected hunk").count() {
+                0 => format!("{}", e),
+                n => format!("Applied {} with {} rejected hunk(s); see the .rej files", name, n),
+            },
+        });
+        self.diff_state.reset();
+        self.current_diff = None;
+        self.refresh_git_sync();
+        self.refresh_diff();
+    }
+
+    /// Recompute the shown diff after the context/whitespace/algorithm settings changed.
+    fn apply_diff_settings(&mut self, msg: String) {
+        if let Some(ref mut repo) = self.git_repo {
+            repo.set_diff_settings(self.diff_state.settings);
+        }
+        let diff = match (&self.git_repo, self.diff_state.commit_view.clone()) {
+            (Some(repo), Some(sha)) => Some(repo.commit_diff(&sha)),
+            _ => None,
+        };
+        match diff {
+            Some(Ok(diff)) => {
+                self.diff_state.update_highlight_cache(&diff);
+                self.current_diff = Some(diff);
+            }
+            Some(Err(e)) => self.error_message = Some(format!("{}", e)),
+            None => {
+                // Blame needs the whole file; the settings apply to the plain diff
+                self.diff_state.blame = None;
+                self.refresh_diff();
+            }
+        }
+        if let Some(ref diff) = self.current_diff {
+            let max = self.diff_state.row_count(diff).saturating_sub(1);
+            self.diff_state.cursor = self.diff_state.cursor.min(max);
+            self.diff_state.select_anchor = None;
+        }
+        self.error_message = Some(msg);
+    }
+
+    /// Show `EXPAND_STEP` more lines above or below the hunk under the cursor.
+    fn expand_hunk(&mut self, above: bool) {
+        let (Some(repo), Some(diff)) = (&self.git_repo, self.current_diff.as_mut()) else { return };
+        if diff.collapsed.is_some() || self.diff_state.commit_view.is_some() {
+            self.error_message = Some("Nothing to expand here".to_string());
+            return;
+        }
+        let Some(line) = self.diff_state.row_lines(self.diff_state.cursor).first().copied() else { return };
+        let Some(hunk) = diff.hunk_of_line(line) else { return };
+        let new_side = match self.compare {
+            Some(ref spec) => repo.compare_new_side_lines(spec, &diff.path),
+            None => {
+                let staged = self.files.iter()
+                    .any(|f| f.path == diff.path && f.stage_state == crate::git::status::StageState::Staged);
+                repo.new_side_lines(&diff.path, staged)
+            }
+        };
+        let new_side = match new_side {
+            Ok(lines) => lines,
+            Err(e) => {
+                self.error_message = Some(format!("{}", e));
+                return;
+            }
+        };
+
+        let before = diff.total_lines();
+        let was_header = diff.all_lines().get(line).is_some_and(|l| l.kind == DiffLineKind::HunkHeader);
+        let (up, down) = if above { (EXPAND_STEP, 0) } else { (0, EXPAND_STEP) };
+        if !diff.expand_hunk(hunk, &new_side, up, down) {
+            self.error_message = Some("This hunk cannot be expanded".to_string());
+            return;
+        }
+        // Lines added above the cursor shift it down; keep it on the same line
+        let target = if above && !was_header {
+            (line + diff.total_lines()).saturating_sub(before)
+        } else {
+            line
+        };
+        self.diff_state.update_highlight_cache(diff);
+        let viewport = self.diff_rect.height.saturating_sub(2);
+        self.diff_state.move_to_line(target, viewport);
+    }
+
+    /// Show the files changed in `spec` instead of the working tree status.
+    fn enter_compare(&mut self, spec: CompareSpec) {
+        self.compare = Some(spec);
+        self.reload_repo();
+    }
+
+    fn leave_compare(&mut self) {
+        self.compare = None;
+        self.reload_repo();
+    }
+
+    fn reload_repo(&mut self) {
+        let tree_view = self.status_state.tree_view;
+        self.status_state = StatusListState::new();
+        self.status_state.tree_view = tree_view;
+        self.diff_state.reset();
+        self.current_diff = None;
+        self.refresh_git_sync();
+        self.refresh_diff();
+        self.focus = Focus::GitStatus;
+    }
+
+    fn drop_stash(&mut self) {
+        if let (Some(repo), Some(ops)) = (&self.git_repo, &self.git_ops) {
+            let Some((oid, msg)) = repo.stash_top() else { return };
+            let entry = match repo.snapshot_commit(UndoKind::StashDrop, &msg, oid) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.error_message = Some(format!("Drop aborted, snapshot failed: {}", e));
+                    return;
+                }
+            };
+            match ops.stash_drop() {
+                Ok(out) => self.error_message = Some(out),
+                Err(e) => {
+                    let _ = repo.drop_undo(&entry);
+                    self.error_message = Some(format!("{}", e));
+                }
+            }
+        }
+    }
+
+    fn delete_branch(&mut self, name: &str) {
+        if let (Some(repo), Some(ops)) = (&self.git_repo, &self.git_ops) {
+            let snapshot = repo.branch_tip(name)
+                .and_then(|tip| repo.snapshot_commit(UndoKind::BranchDelete, name, tip));
+            let entry = match snapshot {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.error_message = Some(format!("Delete aborted, snapshot failed: {}", e));
+                    return;
+                }
+            };
+            match ops.delete_branch(name) {
+                Ok(()) => self.error_message = Some(format!("Deleted branch '{}'", name)),
+                Err(e) => {
+                    let _ = repo.drop_undo(&entry);
+                    self.error_message = Some(format!("{}", e));
+                }
+            }
+        }
+    }
+
+    fn open_tag_picker(&mut self) {
+        let Some(ref repo) = self.git_repo else { return };
+        match repo.tags() {
+            Ok(tags) => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0);
+                let items = tags.iter().map(|t| t.label(now)).collect();
+                let title = if tags.is_empty() { "Tags (none yet)" } else { "Tags" };
+                self.tag_entries = tags;
+                self.picker_state.open(PickerKind::Tag, title, items);
+                self.focus = Focus::Picker;
+            }
+            Err(e) => self.error_message = Some(format!("{}", e)),
+        }
+    }
+
+    fn delete_tag(&mut self, name: &str) {
+        if let (Some(repo), Some(ops)) = (&self.git_repo, &self.git_ops) {
+            let snapshot = repo.tag_target(name)
+                .and_then(|target| repo.snapshot_commit(UndoKind::TagDelete, name, target));
+            let entry = match snapshot {
+                Ok(entry) => entry,
+                Err(e) => {
+                    self.error_message = Some(format!("Delete aborted, snapshot failed: {}", e));
+                    return;
+                }
+            };
+            match ops.delete_tag(name) {
+                Ok(()) => self.error_message = Some(format!("Deleted tag '{}'", name)),
+                Err(e) => {
+                    let _ = repo.drop_undo(&entry);
+                    self.error_message = Some(format!("{}", e));
+                }
+            }
+        }
+    }
+
+    fn restore_undo(&mut self, entry: &UndoEntry) {
+        if let (Some(repo), Some(ops)) = (&self.git_repo, &self.git_ops) {
+            let result = match entry.kind {
+                UndoKind::Discard => repo.restore_discard(entry),
+                UndoKind::StashDrop => repo.undo_parent(entry)
+                    .and_then(|oid| ops.stash_store(&oid.to_string(), &entry.target)),
+                UndoKind::BranchDelete => repo.undo_parent(entry)
+                    .and_then(|oid| ops.restore_branch(&entry.target, &oid.to_string())),
+                UndoKind::TagDelete => repo.undo_parent(entry)
+                    .and_then(|oid| ops.create_tag(&entry.target, Some(&oid.to_string()), None)),
+            };
+            match result {
+                Ok(()) => {
+                    let _ = repo.drop_undo(entry);
+                    self.error_message = Some(format!("Restored {} {}", entry.kind.as_str(), entry.target));
+                }
+                Err(e) => self.error_message = Some(format!("Undo failed: {}", e)),
+            }
+        }
+        self.refresh_git_sync();
+        self.refresh_diff();
+    }
+
+    /// Open the checkpoint timeline with row `selected` highlighted.
+    fn open_checkpoint_picker(&mut self, selected: usize) {
+        let Some(ref repo) = self.git_repo else { return };
+        match repo.checkpoints() {
+            Ok(list) if list.is_empty() => {
+                self.error_message = Some("No checkpoints yet (w takes one)".to_string());
+            }
+            Ok(list) => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or(0);
+                let labels = list.iter()
+                    .map(|c| {
+                        let mark = if self.checkpoint_mark == Some(c.number) { '*' } else { ' ' };
+                        format!("{} {}", mark, c.line(now))
+                    })
+                    .collect();
+                self.picker_state.open(PickerKind::Checkpoint, "Checkpoints", labels);
+                self.picker_state.list_state.select(Some(selected.min(list.len() - 1)));
+                self.checkpoint_entries = list;
+                self.focus = Focus::Picker;
+            }
+            Err(e) => self.error_message = Some(format!("{}", e)),
+        }
+    }
+
+    /// Put the worktree, or just `paths`, back to `checkpoint`.
+    fn restore_checkpoint(&mut self, checkpoint: &Checkpoint, paths: Option<&[String]>) {
+        if let Some(ref repo) = self.git_repo {
+            match repo.restore_checkpoint(checkpoint, paths) {
+                Ok((before, count)) => {
+                    self.error_message = Some(format!(
+                        "Restored {} file{} from #{} (previous state saved as #{})",
+                        count, if count == 1 { "" } else { "s" }, checkpoint.number, before.number,
+                    ));
+                }
+                Err(e) => self.error_message = Some(format!("Restore failed: {:#}", e)),
+            }
+        }
+        self.refresh_git_sync();
+        self.refresh_diff();
+    }
+
+    /// Keys while typing a `/` search or `:` line number in the diff view.
+    fn handle_diff_prompt_key(&mut self, key: crossterm::event::KeyEvent) {
+        let viewport = self.diff_rect.height.saturating_sub(2);
+        let search = self.diff_state.prompt == Some(DiffPrompt::Search);
+        match (key.modifiers, key.code) {
+            (KeyModifiers::NONE, KeyCode::Esc) => {
+                self.diff_state.cancel_prompt(viewport);
+            }
+            (KeyModifiers::NONE, KeyCode::Enter) => {
+                if !search {
+                    let input = self.diff_state.prompt_input.trim().to_string();
+                    match (input.parse::<u32>(), &self.current_diff) {
+                        (Ok(n), Some(diff)) if !self.diff_state.goto_line(diff, n, viewport) => {
+                            self.error_message = Some(format!("Line {} is not in this diff", n));
+                        }
+                        (Err(_), _) if !input.is_empty() => {
+                            self.error_message = Some(format!("Not a line number: {}", input));
+                        }
+                        _ => {}
+                    }
+                    self.diff_state.prompt_input.clear();
+                }
+                self.diff_state.prompt = None;
+            }
             (KeyModifiers::NONE, KeyCode::Backspace) => {
-                self.prompt_state.delete_char();
+                self.diff_state.prompt_input.pop();
+                if let (true, Some(diff)) = (search, &self.current_diff) {
+                    self.diff_state.update_search(diff, viewport);
+                }
+            }
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                if search || c.is_ascii_digit() {
+                    self.diff_state.prompt_input.push(c);
+                }
+                if let (true, Some(diff)) = (search, &self.current_diff) {
+                    self.diff_state.update_search(diff, viewport);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Filter-as-you-type in the status list. Enter keeps the filter, Esc clears it.
+    fn handle_filter_key(&mut self, key: crossterm::event::KeyEvent) {
+        match (key.modifiers, key.code) {
+            (KeyModifiers::NONE, KeyCode::Esc) => {
+                self.status_state.clear_filter(&self.files);
+            }
+            (KeyModifiers::NONE, KeyCode::Enter) => {
+                self.status_state.filtering = false;
+                self.refresh_diff();
             }
-            (KeyModifiers::NONE, KeyCode::Left) => {
-                self.prompt_state.move_cursor_left();
+            (KeyModifiers::NONE, KeyCode::Backspace) => {
+                self.status_state.pop_filter(&self.files);
             }
-            (KeyModifiers::NONE, KeyCode::Right) => {
-                self.prompt_state.move_cursor_right();
+            (KeyModifiers::NONE, KeyCode::Down) => self.status_state.move_down(),
+            (KeyModifiers::NONE, KeyCode::Up) => self.status_state.move_up(),
+            (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(c)) => {
+                self.status_state.push_filter(c, &self.files);
             }
-            (_, KeyCode::Char(c)) => {
-                self.prompt_state.insert_char(c);
+            _ => {}
+        }
+    }
+
+    async fn handle_picker_key(&mut self, key: crossterm::event::KeyEvent) -> Result<()> {
+        match (key.modifiers, key.code) {
+            (KeyModifiers::NONE, KeyCode::Esc) | (KeyModifiers::NONE, KeyCode::Char('q')) => {
+                self.focus = match self.picker_state.kind {
+                    PickerKind::Basket => Focus::Basket,
+                    // The send dialog is still open underneath
+                    PickerKind::PromptTemplate => Focus::PromptDialog,
+                    _ => Focus::GitStatus,
+                };
+                if self.picker_state.kind == PickerKind::ApplyPatch {
+                    // Drop the patch preview and show the selected file again
+                    self.apply_patch_path = None;
+                    self.diff_state.reset();
+                    self.current_diff = None;
+                    self.refresh_diff();
+                }
+                self.picker_state.close();
+            }
+            (KeyModifiers::NONE, KeyCode::Char('k')) | (KeyModifiers::NONE, KeyCode::Up) => {
+                self.picker_state.move_up();
+            }
+            (KeyModifiers::NONE, KeyCode::Char('j')) | (KeyModifiers::NONE, KeyCode::Down) => {
+                self.picker_state.move_down();
+            }
+            (KeyModifiers::NONE, KeyCode::Char('d')) if self.picker_state.kind == PickerKind::Branch => {
+                if let Some(name) = self.picker_state.selected().map(|s| s.to_string()) {
+                    self.picker_state.close();
+                    self.prompt_state.open_confirm(PromptMode::ConfirmDeleteBranch, name);
+                    self.focus = Focus::PromptDialog;
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Char('n')) if self.picker_state.kind == PickerKind::Tag => {
+                self.picker_state.close();
+                self.prompt_state.open_create_tag(None);
+                self.focus = Focus::PromptDialog;
+            }
+            (KeyModifiers::NONE, KeyCode::Char('d')) if self.picker_state.kind == PickerKind::Tag => {
+                let name = self.picker_state.selected_index()
+                    .and_then(|i| self.tag_entries.get(i))
+                    .map(|t| t.name.clone());
+                if let Some(name) = name {
+                    self.picker_state.close();
+                    self.prompt_state.open_confirm(PromptMode::ConfirmDeleteTag, name);
+                    self.focus = Focus::PromptDialog;
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Char('p')) if self.picker_state.kind == PickerKind::Tag => {
+                self.picker_state.close();
+                self.focus = Focus::GitStatus;
+                self.push_tags_async();
+            }
+            (KeyModifiers::NONE, KeyCode::Char('d')) if self.picker_state.kind == PickerKind::Basket => {
+                let path = match (self.picker_state.selected(), &self.state_dir) {
+                    (Some(name), Some(dir)) => basket::saved_path(dir, name).ok(),
+                    _ => None,
+                };
+                if let Some(path) = path {
+                    if let Err(e) = std::fs::remove_file(&path) {
+                        self.error_message = Some(format!("Failed to delete {}: {}", path.display(), e));
+                    }
+                    self.picker_state.close();
+                    self.focus = Focus::Basket;
+                    self.open_basket_picker();
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Char('b')) if self.picker_state.kind == PickerKind::FileRef => {
+                let path = self.picker_state.selected_index()
+                    .and_then(|i| self.file_ref_entries.get(i))
+                    .map(|r| std::path::Path::new(&self.workdir).join(&r.path));
+                self.picker_state.close();
+                self.focus = Focus::GitStatus;
+                if let Some(path) = path {
+                    self.file_browser.active_panel_mut().reveal(&path);
+                    self.handle_action(Action::SwitchTab(ActiveTab::FileBrowser)).await?;
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Char('u')) if self.picker_state.kind == PickerKind::Submodule => {
+                let path = self.picker_state.selected_index()
+                    .and_then(|i| self.submodule_entries.get(i))
+                    .cloned();
+                self.picker_state.close();
+                self.focus = Focus::GitStatus;
+                if let Some(path) = path {
+                    self.update_submodules(Some(&path));
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Char(' ')) if self.picker_state.kind == PickerKind::Checkpoint => {
+                if let Some(i) = self.picker_state.selected_index() {
+                    let number = self.checkpoint_entries.get(i).map(|c| c.number);
+                    self.checkpoint_mark = if self.checkpoint_mark == number { None } else { number };
+                    self.open_checkpoint_picker(i);
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Char('r')) if self.picker_state.kind == PickerKind::Checkpoint => {
+                let checkpoint = self.picker_state.selected_index()
+                    .and_then(|i| self.checkpoint_entries.get(i))
+                    .cloned();
+                self.picker_state.close();
+                self.focus = Focus::GitStatus;
+                if let Some(checkpoint) = checkpoint {
+                    self.restore_checkpoint(&checkpoint, None);
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Char('f')) if self.picker_state.kind == PickerKind::Checkpoint => {
+                let checkpoint = self.picker_state.selected_index()
+                    .and_then(|i| self.checkpoint_entries.get(i))
+                    .cloned();
+                let (Some(checkpoint), Some(repo)) = (checkpoint, &self.git_repo) else { return Ok(()) };
+                match repo.checkpoint_files(&checkpoint) {
+                    Ok(files) if files.is_empty() => {
+                        self.error_message = Some(format!("Worktree matches checkpoint #{}", checkpoint.number));
+                    }
+                    Ok(files) => {
+                        let title = format!("Restore from checkpoint #{}", checkpoint.number);
+                        let items = files.iter().map(|f| f.line()).collect();
+                        self.checkpoint_files = Some((checkpoint, files));
+                        self.picker_state.open(PickerKind::CheckpointFile, &title, items);
+                    }
+                    Err(e) => self.error_message = Some(format!("{}", e)),
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Char('d')) if self.picker_state.kind == PickerKind::Checkpoint => {
+                if let Some(i) = self.picker_state.selected_index() {
+                    if let (Some(checkpoint), Some(repo)) = (self.checkpoint_entries.get(i), &self.git_repo) {
+                        if let Err(e) = repo.drop_checkpoint(checkpoint) {
+                            self.error_message = Some(format!("Failed to delete checkpoint: {}", e));
+                        }
+                    }
+                    self.picker_state.close();
+                    self.focus = Focus::GitStatus;
+                    self.open_checkpoint_picker(i);
+                }
+            }
+            (KeyModifiers::NONE, KeyCode::Enter) => {
+                let choice = self.picker_state.selected().map(|s| s.to_string());
+                let index = self.picker_state.selected_index();
+                let kind = self.picker_state.kind.clone();
+                self.picker_state.close();
+                self.focus = Focus::GitStatus;
+                let Some(choice) = choice else { return Ok(()) };
+                match kind {
+                    PickerKind::Branch => {
+                        self.handle_action(Action::CheckoutBranch(choice)).await?;
+                    }
+                    PickerKind::Undo => {
+                        if let Some(entry) = index.and_then(|i| self.undo_entries.get(i)).cloned() {
+                            self.restore_undo(&entry);
+                        }
+                    }
+                    PickerKind::Submodule => {
+                        if let Some(path) = index.and_then(|i| self.submodule_entries.get(i)).cloned() {
+                            self.enter_submodule(&path);
+                        }
+                    }
+                    PickerKind::Compare => {
+                        let spec = match (index, &self.git_repo) {
+                            (Some(0), Some(repo)) => Some(repo.compare_merge_base()),
+                            (Some(1), _) => {
+                                self.prompt_state.open_compare_revs();
+                                self.focus = Focus::PromptDialog;
+                                None
+                            }
+                            (Some(i), Some(repo)) => self.compare_stashes.get(i - 2)
+                                .map(|(name, oid)| repo.compare_stash(*oid, name.split(':').next().unwrap_or(name))),
+                            _ => None,
+                        };
+                        match spec {
+                            Some(Ok(spec)) => self.enter_compare(spec),
+                            Some(Err(e)) => self.error_message = Some(format!("{:#}", e)),
+                            None => {}
+                        }
+                    }
+                    PickerKind::Tag => {
+                        let target = index.and_then(|i| self.tag_entries.get(i)).map(|t| t.target.to_string());
+                        if let (Some(sha), Some(repo)) = (target, &self.git_repo) {
+                            match repo.commit_diff(&sha) {
+                                Ok(diff) => {
+                                    self.diff_state.set_file(&diff.path);
+                                    self.diff_state.update_highlight_cache(&diff);
+                                    self.diff_state.commit_view = Some(sha);
+                                    self.current_diff = Some(diff);
+                                    self.focus = Focus::DiffView;
+                                }
+                                Err(e) => self.error_message = Some(format!("{}", e)),
+                            }
+                        }
+                    }
+                    PickerKind::Basket => {
+                        self.focus = Focus::Basket;
+                        let loaded = match self.state_dir {
+                            Some(ref dir) => basket::saved_path(dir, &choice).and_then(|p| basket::read(&p)),
+                            None => return Ok(()),
+                        };
+                        match loaded {
+                            Ok(items) => {
+                                self.basket.replace(items, Some(choice));
+                                self.save_basket();
+                            }
+                            Err(e) => self.error_message = Some(format!("{:#}", e)),
+                        }
+                    }
+                    PickerKind::FileRef => {
+                        if let Some(file_ref) = index.and_then(|i| self.file_ref_entries.get(i)).cloned() {
+                            self.open_file_ref(&file_ref);
+                        }
+                    }
+                    PickerKind::PromptTemplate => {
+                        self.focus = Focus::PromptDialog;
+                        if let Some(template) = index.and_then(|i| self.template_entries.get(i)).cloned() {
+                            self.apply_template(&template);
+                        }
+                    }
+                    PickerKind::ApplyPatch => {
+                        if let (Some(patch), Some(i)) = (self.apply_patch_path.take(), index) {
+                            self.apply_patch(&patch, i == 1, i == 2);
+                        }
+                    }
+                    PickerKind::Checkpoint => {
+                        // Against the marked checkpoint, else the one before it
+                        let Some(i) = index else { return Ok(()) };
+                        let marked = self.checkpoint_mark.take()
+                            .and_then(|n| self.checkpoint_entries.iter().position(|c| c.number == n))
+                            .filter(|&m| m != i);
+                        let (newer, older) = match marked {
+                            Some(m) => (i.min(m), Some(i.max(m))),
+                            None => (i, Some(i + 1)),
+                        };
+                        let target = &self.checkpoint_entries[newer];
+                        let base = older.and_then(|o| self.checkpoint_entries.get(o));
+                        if let Some(ref repo) = self.git_repo {
+                            match repo.compare_checkpoints(base, target) {
+                                Ok(spec) => self.enter_compare(spec),
+                                Err(e) => self.error_message = Some(format!("{:#}", e)),
+                            }
+                        }
+                    }
+                    PickerKind::CheckpointFile => {
+                        let Some((checkpoint, files)) = self.checkpoint_files.take() else { return Ok(()) };
+                        if let Some(file) = index.and_then(|i| files.get(i)) {
+                            self.restore_checkpoint(&checkpoint, Some(std::slice::from_ref(&file.path)));
+                        }
+                    }
+                    PickerKind::PushRemote => {
+                        if let Some(ref ops) = self.git_ops {
+                            match ops.push_set_upstream(&choice, &self.branch.name) {
+                                Ok(msg) => self.error_message = Some(format!("Pushed: {}", msg.trim())),
+                                Err(e) => self.error_message = Some(format!("Push failed: {}", e)),
+                            }
+                            self.refresh_git_sync();
+                        }
+                    }
+                }
             }
             _ => {}
         }
@@ -1075,6 +3111,7 @@ impl App {
         if cols > 0 && rows > 0 && area != self.last_pty_area {
             self.last_pty_area = area;
             self.emulator.set_size(rows, cols);
+            self.invalidate_links();
             let _ = self.pty.resize(cols, rows);
         }
     }
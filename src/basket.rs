@@ -0,0 +1,174 @@
+//! Context basket: references collected from the status list, diff view,
+//! file browser and PTY selections, sent to Claude together with one prompt.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum BasketItem {
+    /// A file or directory, relative to the top-level repository
+    File { path: String },
+    /// A new-file line range
+    Lines { path: String, start: u32, end: u32 },
+    /// Text selected in the PTY, quoted into the prompt
+    Text { text: String },
+}
+
+impl BasketItem {
+    /// `@` reference for files and line ranges; None for quoted text.
+    pub fn reference(&self) -> Option<String> {
+        match self {
+            BasketItem::File { path } => Some(path.clone()),
+            BasketItem::Lines { path, start, end } if start == end => Some(format!("{}:{}", path, start)),
+            BasketItem::Lines { path, start, end } => Some(format!("{}:{}-{}", path, start, end)),
+            BasketItem::Text { .. } => None,
+        }
+    }
+
+    /// One-line description for the basket panel.
+    pub fn label(&self) -> String {
+        match self {
+            BasketItem::Text { text } => {
+                let first = text.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+                let more = text.lines().count().saturating_sub(1);
+                if more > 0 {
+                    format!("“{}” (+{} line{})", first, more, if more == 1 { "" } else { "s" })
+                } else {
+                    format!("“{}”", first)
+                }
+            }
+            _ => format!("@{}", self.reference().unwrap_or_default()),
+        }
+    }
+}
+
+pub struct Basket {
+    pub items: Vec<BasketItem>,
+    pub cursor: usize,
+    /// Name of the saved basket this was loaded from or saved as
+    pub name: Option<String>,
+}
+
+impl Basket {
+    pub fn new() -> Self {
+        Self { items: Vec::new(), cursor: 0, name: None }
+    }
+
+    /// Append `item` unless it is already in the basket. Returns whether it was added.
+    pub fn add(&mut self, item: BasketItem) -> bool {
+        if self.items.contains(&item) {
+            return false;
+        }
+        self.items.push(item);
+        true
+    }
+
+    pub fn remove_selected(&mut self) {
+        if self.cursor < self.items.len() {
+            self.items.remove(self.cursor);
+            self.cursor = self.cursor.min(self.items.len().saturating_sub(1));
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.cursor = 0;
+        self.name = None;
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.cursor + 1 < self.items.len() {
+            self.cursor += 1;
+        }
+    }
+
+    /// Swap the selected item with its neighbour (`up` towards the front).
+    pub fn reorder(&mut self, up: bool) {
+        if self.cursor >= self.items.len() {
+            return;
+        }
+        let other = if up {
+            match self.cursor.checked_sub(1) {
+                Some(i) => i,
+                None => return,
+            }
+        } else if self.cursor + 1 < self.items.len() {
+            self.cursor + 1
+        } else {
+            return;
+        };
+        self.items.swap(self.cursor, other);
+        self.cursor = other;
+    }
+
+    /// File and line references, in basket order.
+    pub fn references(&self) -> Vec<String> {
+        self.items.iter().filter_map(|i| i.reference()).collect()
+    }
+
+    /// Quoted PTY selections, in basket order.
+    pub fn quotes(&self) -> Vec<String> {
+        self.items.iter()
+            .filter_map(|i| match i {
+                BasketItem::Text { text } => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn replace(&mut self, items: Vec<BasketItem>, name: Option<String>) {
+        self.items = items;
+        self.cursor = 0;
+        self.name = name;
+    }
+}
+
+/// The working basket, restored on the next start.
+pub fn current_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("basket.json")
+}
+
+/// A named basket saved with `w`.
+pub fn saved_path(state_dir: &Path, name: &str) -> Result<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        anyhow::bail!("Invalid basket name '{}'", name);
+    }
+    Ok(state_dir.join("baskets").join(format!("{}.json", name)))
+}
+
+/// Names of the saved baskets, most recently saved first.
+pub fn saved_names(state_dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(state_dir.join("baskets")) else { return Vec::new() };
+    let mut named: Vec<(std::time::SystemTime, String)> = entries.flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().strip_suffix(".json")?.to_string();
+            let modified = e.metadata().and_then(|m| m.modified()).ok()?;
+            Some((modified, name))
+        })
+        .collect();
+    named.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    named.into_iter().map(|(_, name)| name).collect()
+}
+
+pub fn read(path: &Path) -> Result<Vec<BasketItem>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn write(path: &Path, items: &[BasketItem]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let text = serde_json::to_string_pretty(items)?;
+    std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+}
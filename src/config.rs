@@ -0,0 +1,85 @@
+use clap::Parser;
+
+use crate::git::diff::{DiffAlgorithm, DiffLimits, DiffSettings, Whitespace};
+use crate::git::word_diff::WordDiffMode;
+
+/// Runtime configuration, taken from the command line.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "rataclaude", version, about = "Claude Code in a PTY with lazygit-style git management")]
+pub struct Config {
+    /// Seconds between background `git fetch --all` runs (0 disables)
+    #[arg(long, default_value_t = 300)]
+    pub fetch_interval: u64,
+
+    /// Similarity percentage (0-100) for rename and copy detection
+    #[arg(long, default_value_t = crate::git::repo::DEFAULT_RENAME_THRESHOLD)]
+    pub rename_threshold: u16,
+
+    /// Granularity of intra-line change highlighting in diffs
+    #[arg(long, value_enum, default_value_t = WordDiffMode::Word)]
+    pub word_diff: WordDiffMode,
+
+    /// Files larger than this many bytes show a summary until loaded with `L`
+    #[arg(long, default_value_t = DiffLimits::default().max_bytes)]
+    pub max_diff_bytes: u64,
+
+    /// Diffs longer than this many lines show a summary until loaded with `L`
+    #[arg(long, default_value_t = DiffLimits::default().max_lines)]
+    pub max_diff_lines: usize,
+
+    /// Show lockfiles and minified files in full instead of collapsed
+    #[arg(long)]
+    pub expand_generated: bool,
+
+    /// Context lines around each hunk (`+`/`-` in the diff view)
+    #[arg(long, default_value_t = DiffSettings::default().context)]
+    pub context: u32,
+
+    /// Whitespace handling in diffs (`W` in the diff view)
+    #[arg(long, value_enum, default_value_t = Whitespace::Show)]
+    pub whitespace: Whitespace,
+
+    /// Diff algorithm (`A` in the diff view)
+    #[arg(long, value_enum, default_value_t = DiffAlgorithm::Myers)]
+    pub diff_algorithm: DiffAlgorithm,
+
+    /// Command that opens URLs clicked in the Claude pane
+    /// (default: `open` on macOS, `xdg-open` elsewhere)
+    #[arg(long)]
+    pub url_opener: Option<String>,
+
+    /// The send dialog warns about referenced files larger than this many bytes
+    #[arg(long, default_value_t = 100 * 1024)]
+    pub warn_context_bytes: u64,
+
+    /// Don't checkpoint the worktree each time a prompt is sent
+    /// (`w` still takes one on demand)
+    #[arg(long)]
+    pub no_checkpoints: bool,
+}
+
+impl Config {
+    pub fn diff_limits(&self) -> DiffLimits {
+        DiffLimits {
+            max_bytes: self.max_diff_bytes,
+            max_lines: self.max_diff_lines,
+            collapse_generated: !self.expand_generated,
+        }
+    }
+
+    pub fn url_opener(&self) -> String {
+        match self.url_opener {
+            Some(ref cmd) => cmd.clone(),
+            None if cfg!(target_os = "macos") => "open".to_string(),
+            None => "xdg-open".to_string(),
+        }
+    }
+
+    pub fn diff_settings(&self) -> DiffSettings {
+        DiffSettings {
+            context: self.context,
+            whitespace: self.whitespace,
+            algorithm: self.diff_algorithm,
+        }
+    }
+}
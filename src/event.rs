@@ -1,6 +1,9 @@
+use std::collections::HashSet;
+
 use crossterm::event::{Event as CrosstermEvent, KeyEvent, KeyEventKind, MouseEvent};
 
-use crate::git::status::FileStatus;
+use crate::git::status::{BranchInfo, FileStatus};
+use crate::git::turn::TurnSnapshot;
 
 #[derive(Debug)]
 pub enum AppEvent {
@@ -11,8 +14,18 @@ pub enum AppEvent {
     PtyExited,
     Tick,
     GitRefresh,
-    /// Async git status result from background thread
-    GitStatusUpdate(Vec<FileStatus>, String),
+    /// Async git status result from background thread, with the paths
+    /// changed since the last prompt was sent
+    GitStatusUpdate(Vec<FileStatus>, BranchInfo, HashSet<String>),
+    /// Background fetch finished (error message on failure)
+    GitFetchDone(Option<String>),
+    /// Background `git push --tags` finished (output, or error message)
+    GitPushTagsDone(Result<String, String>),
+    /// Worktree recorded for the prompt with this sequence number: the turn
+    /// snapshot and files too large for its checkpoint, or an error message
+    TurnRecorded(u64, Result<(TurnSnapshot, Vec<String>), String>),
+    /// Claude finished writing the drafted commit message
+    CommitDraftReady(String),
     /// Terminal focus gained (from real terminal)
     FocusGained,
     /// Terminal focus lost (from real terminal)
@@ -0,0 +1,75 @@
+//! `path:line` references found in terminal text: compiler errors, stack
+//! traces, test failures and the agent's own tool output.
+
+use std::path::Path;
+
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRef {
+    /// Relative to the workdir the reference was resolved against
+    pub path: String,
+    pub line: u32,
+    pub column: Option<u32>,
+}
+
+impl FileRef {
+    /// "path:line", as sent to Claude with an `@`.
+    pub fn reference(&self) -> String {
+        format!("{}:{}", self.path, self.line)
+    }
+
+    pub fn label(&self) -> String {
+        match self.column {
+            Some(col) => format!("{}:{}:{}", self.path, self.line, col),
+            None => self.reference(),
+        }
+    }
+}
+
+/// References in `text` to files that exist under `workdir`, in order of
+/// appearance without duplicates. Understands `path:line[:col]`,
+/// `path(line,col)` and Python's `File "path", line N`.
+pub fn parse(text: &str, workdir: &Path) -> Vec<FileRef> {
+    let patterns = [
+        r#"File "(?P<path>[^"]+)", line (?P<line>\d+)"#,
+        r"(?P<path>[\w.~/\\@+-]+)\((?P<line>\d+),(?P<col>\d+)\)",
+        r"(?P<path>[\w.~/\\@+-]+):(?P<line>\d+)(?::(?P<col>\d+))?",
+    ];
+    let mut found: Vec<(usize, FileRef)> = Vec::new();
+    for pattern in patterns {
+        let re = Regex::new(pattern).expect("valid regex");
+        for caps in re.captures_iter(text) {
+            let Some(path) = resolve(&caps["path"], workdir) else { continue };
+            let Ok(line) = caps["line"].parse() else { continue };
+            let column = caps.name("col").and_then(|c| c.as_str().parse().ok());
+            let start = caps.get(0).map(|m| m.start()).unwrap_or(0);
+            if !found.iter().any(|(_, r)| r.path == path && r.line == line) {
+                found.push((start, FileRef { path, line, column }));
+            }
+        }
+    }
+    found.sort_by_key(|(start, _)| *start);
+    found.into_iter().map(|(_, r)| r).collect()
+}
+
+/// `candidate` relative to `workdir` if it names a file there. Accepts
+/// absolute paths inside the workdir and `./`, `a/`, `b/` prefixes.
+pub fn resolve(candidate: &str, workdir: &Path) -> Option<String> {
+    let candidate = candidate.trim_start_matches('@').trim_start_matches("./").trim_end_matches(['.', ',']);
+    let path = Path::new(candidate);
+    let relative = if path.is_absolute() {
+        path.strip_prefix(workdir).ok()?.to_path_buf()
+    } else {
+        path.to_path_buf()
+    };
+    let mut tries = vec![relative.clone()];
+    for prefix in ["a/", "b/"] {
+        if let Ok(rest) = relative.strip_prefix(prefix) {
+            tries.push(rest.to_path_buf());
+        }
+    }
+    tries.into_iter()
+        .find(|p| !p.as_os_str().is_empty() && workdir.join(p).is_file())
+        .map(|p| p.to_string_lossy().to_string())
+}
@@ -16,6 +16,8 @@ pub struct PanelState {
     pub cursor: usize,
     pub scroll_offset: usize,
     pub show_hidden: bool,
+    /// Entries marked with Space, possibly in other directories
+    pub marked: Vec<PathBuf>,
 }
 
 impl PanelState {
@@ -26,6 +28,7 @@ impl PanelState {
             cursor: 0,
             scroll_offset: 0,
             show_hidden: false,
+            marked: Vec::new(),
         };
         panel.refresh();
         panel
@@ -33,6 +36,7 @@ impl PanelState {
 
     pub fn refresh(&mut self) {
         self.entries.clear();
+        self.marked.retain(|p| p.exists());
 
         let read_dir = match std::fs::read_dir(&self.current_dir) {
             Ok(rd) => rd,
@@ -127,10 +131,46 @@ impl PanelState {
         }
     }
 
+    /// Show `path`'s directory with the cursor on it.
+    pub fn reveal(&mut self, path: &Path) {
+        let Some(parent) = path.parent() else { return };
+        self.current_dir = parent.to_path_buf();
+        self.cursor = 0;
+        self.scroll_offset = 0;
+        self.refresh();
+        if let Some(pos) = self.entries.iter().position(|e| e.path == path) {
+            self.cursor = pos;
+        }
+    }
+
     pub fn selected_entry(&self) -> Option<&DirEntry> {
         self.entries.get(self.cursor)
     }
 
+    /// Mark or unmark the entry under the cursor and move to the next one.
+    pub fn toggle_mark(&mut self) {
+        let Some(path) = self.selected_entry().map(|e| e.path.clone()) else { return };
+        if let Some(pos) = self.marked.iter().position(|p| *p == path) {
+            self.marked.remove(pos);
+        } else {
+            self.marked.push(path);
+        }
+        self.cursor_down();
+    }
+
+    pub fn is_marked(&self, path: &Path) -> bool {
+        self.marked.iter().any(|p| p == path)
+    }
+
+    /// Marked entries, or the entry under the cursor when nothing is marked.
+    pub fn marked_or_selected(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            self.selected_entry().map(|e| e.path.clone()).into_iter().collect()
+        } else {
+            self.marked.clone()
+        }
+    }
+
     pub fn toggle_hidden(&mut self) {
         self.show_hidden = !self.show_hidden;
         self.refresh();
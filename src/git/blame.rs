@@ -0,0 +1,28 @@
+/// Blame annotation for one line of the working-tree file.
+#[derive(Debug, Clone)]
+pub struct BlameLine {
+    /// Full commit id, or None for lines not committed yet
+    pub commit: Option<String>,
+    pub author: String,
+    /// Commit time (unix seconds); 0 for uncommitted lines
+    pub time: i64,
+    pub summary: String,
+}
+
+impl BlameLine {
+    pub fn uncommitted() -> Self {
+        Self {
+            commit: None,
+            author: "You".to_string(),
+            time: 0,
+            summary: "Not committed yet".to_string(),
+        }
+    }
+
+    pub fn short_sha(&self) -> &str {
+        match self.commit {
+            Some(ref c) => &c[..c.len().min(7)],
+            None => "0000000",
+        }
+    }
+}
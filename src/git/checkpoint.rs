@@ -0,0 +1,62 @@
+//! Checkpoints of the working tree, taken when a prompt is sent or on demand.
+//!
+//! Each checkpoint is a commit under `refs/rataclaude/checkpoints/<n>` whose
+//! tree is HEAD with every changed or untracked (but not ignored) file
+//! replaced by its worktree content. Writing one touches neither the index
+//! nor HEAD. The commits have no parents, so dropping old refs lets git
+//! collect them.
+
+pub const CHECKPOINT_REF_PREFIX: &str = "refs/rataclaude/checkpoints/";
+
+/// Older checkpoints are dropped once there are more than this many.
+pub const MAX_CHECKPOINTS: usize = 100;
+
+/// Changed files bigger than this keep HEAD's version in a checkpoint, so a
+/// large build artifact is not copied into the object store on every send.
+pub const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Characters of the prompt kept as a checkpoint's label.
+const LABEL_CHARS: usize = 60;
+
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub refname: String,
+    /// Increases with each checkpoint
+    pub number: u64,
+    pub commit: git2::Oid,
+    /// The prompt it was taken for, or why it was taken
+    pub label: String,
+    /// Unix timestamp
+    pub time: i64,
+}
+
+impl Checkpoint {
+    pub fn line(&self, now: i64) -> String {
+        let age = crate::ui::file_panel::format_age(now.saturating_sub(self.time).max(0) as u64);
+        format!("#{:<4} {:>4}  {}", self.number, age, self.label)
+    }
+}
+
+/// First line of `text`, shortened to a label.
+pub fn label(text: &str) -> String {
+    let first = text.lines().map(str::trim).find(|l| !l.is_empty()).unwrap_or("");
+    if first.chars().count() > LABEL_CHARS {
+        format!("{}…", first.chars().take(LABEL_CHARS - 1).collect::<String>())
+    } else {
+        first.to_string()
+    }
+}
+
+/// One file that differs between a checkpoint and the worktree.
+#[derive(Debug, Clone)]
+pub struct CheckpointFile {
+    pub path: String,
+    /// 'M' changed, 'A' created or 'D' deleted since the checkpoint
+    pub change: char,
+}
+
+impl CheckpointFile {
+    pub fn line(&self) -> String {
+        format!("{} {}", self.change, self.path)
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use crate::event::AppEvent;
+
+/// Per-process temp file Claude is asked to write the drafted message into.
+pub fn draft_path() -> PathBuf {
+    std::env::temp_dir().join(format!("rataclaude-commit-msg-{}.txt", std::process::id()))
+}
+
+/// Build the single-line request injected into the agent PTY.
+/// `staged` holds (path, additions, deletions) for every staged file.
+pub fn build_request(staged: &[(String, usize, usize)], out: &Path) -> String {
+    let summary = staged.iter()
+        .map(|(path, adds, dels)| format!("{} (+{} -{})", path, adds, dels))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "Draft a conventional-commit message (type(scope): subject, blank line, optional body) \
+         for the currently staged changes (see `git diff --cached`). Staged files: {}. \
+         Write ONLY the message to {} and do not commit.\n",
+        summary,
+        out.display(),
+    )
+}
+
+/// Quiet time after the last write before the draft counts as finished.
+const SETTLE: Duration = Duration::from_millis(500);
+
+/// Watch the draft file and send its contents once Claude has written it,
+/// i.e. once no event arrived for `SETTLE`, so a draft written in several
+/// chunks is not taken half-way. The returned watcher must be kept alive
+/// for events to keep flowing.
+pub fn watch(path: PathBuf, tx: mpsc::UnboundedSender<AppEvent>) -> Result<RecommendedWatcher> {
+    let dir = path.parent()
+        .context("draft path has no parent directory")?
+        .to_path_buf();
+    let target = path.clone();
+    let generation = Arc::new(AtomicU64::new(0));
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else { return };
+        if !event.paths.iter().any(|p| p == &target) {
+            return;
+        }
+        let current = generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let (generation, target, tx) = (generation.clone(), target.clone(), tx.clone());
+        std::thread::spawn(move || {
+            std::thread::sleep(SETTLE);
+            if generation.load(Ordering::SeqCst) != current {
+                return;
+            }
+            if let Ok(text) = std::fs::read_to_string(&target) {
+                let text = text.trim().to_string();
+                if !text.is_empty() {
+                    let _ = tx.send(AppEvent::CommitDraftReady(text));
+                }
+            }
+        });
+    })
+    .context("create file watcher")?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)
+        .context("watch temp dir")?;
+    Ok(watcher)
+}
@@ -0,0 +1,58 @@
+//! Compare mode: the status list shows the files changed between two
+//! revisions instead of the working tree status.
+
+/// Two commits being compared; `base` is the old side.
+#[derive(Debug, Clone)]
+pub struct CompareSpec {
+    pub base: git2::Oid,
+    pub target: git2::Oid,
+    /// Shown in the status list title, e.g. "merge-base(main)..HEAD"
+    pub label: String,
+}
+
+impl CompareSpec {
+    /// Full commit ids, "base..target", for passing to `git diff`.
+    pub fn range(&self) -> String {
+        format!("{}..{}", self.base, self.target)
+    }
+
+    /// "abc1234..def5678", for showing the range to people.
+    pub fn short_range(&self) -> String {
+        format!("{:.7}..{:.7}", self.base.to_string(), self.target.to_string())
+    }
+}
+
+/// Split "A..B", "A...B" (merge-base of A and B vs B) or "A B" into
+/// (base, target, use merge-base). A missing target means HEAD.
+pub fn parse_range(input: &str) -> Option<(String, String, bool)> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+    let (base, target, merge_base) = if let Some((a, b)) = input.split_once("...") {
+        (a, b, true)
+    } else if let Some((a, b)) = input.split_once("..") {
+        (a, b, false)
+    } else if let Some((a, b)) = input.split_once(char::is_whitespace) {
+        (a, b, false)
+    } else {
+        (input, "", false)
+    };
+    let or_head = |s: &str| if s.trim().is_empty() { "HEAD".to_string() } else { s.trim().to_string() };
+    Some((or_head(base), or_head(target), merge_base))
+}
+
+/// Single-line request injected into the agent PTY to review a changeset.
+pub fn review_request(spec: &CompareSpec, files: &[String]) -> String {
+    let refs = files.iter()
+        .map(|f| format!("@{}", f))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "Review the changes in {} (`git diff {}`) as you would a pull request: \
+         correctness, edge cases, missing tests and leftover debug code. Changed files: {}\n",
+        spec.label,
+        spec.short_range(),
+        refs,
+    )
+}
@@ -0,0 +1,116 @@
+//! Size of the context a send dialog is about to inject: bytes, lines and a
+//! rough token count for its `@path`, `@path:A-B` and `@dir/` references,
+//! with warnings for references that are probably a mistake.
+
+use std::io::{self, BufRead};
+
+use crate::ui::file_panel::format_size;
+
+/// Rough bytes per token for source code and prose.
+const BYTES_PER_TOKEN: u64 = 4;
+
+/// Files read for a directory reference; the rest are only counted.
+pub const MAX_DIR_FILES: usize = 2000;
+
+/// A directory reference with more files than this gets a warning.
+pub const DIR_WARN_FILES: usize = 50;
+
+/// Totals for one send.
+#[derive(Debug, Clone, Default)]
+pub struct ContextSize {
+    /// Text files (or ranges) counted
+    pub files: usize,
+    /// Bytes of text, binary files excluded
+    pub bytes: u64,
+    pub lines: usize,
+    /// One entry per suspicious reference, e.g. "binary: logo.png (12.0K)"
+    pub warnings: Vec<String>,
+}
+
+impl ContextSize {
+    pub fn tokens(&self) -> u64 {
+        self.bytes.div_ceil(BYTES_PER_TOKEN)
+    }
+
+    /// "3 files, 12.4K, 310 lines, ~3.2k tokens"
+    pub fn summary(&self) -> String {
+        format!(
+            "{} file{}, {}, {} line{}, ~{} tokens",
+            self.files,
+            if self.files == 1 { "" } else { "s" },
+            format_size(self.bytes),
+            self.lines,
+            if self.lines == 1 { "" } else { "s" },
+            format_tokens(self.tokens()),
+        )
+    }
+
+    /// Count quoted text that goes out with the references.
+    pub fn add_text(&mut self, text: &str) {
+        self.bytes += text.len() as u64;
+        self.lines += text.lines().count();
+    }
+}
+
+/// "950", "3.2k", "1.4M"
+pub fn format_tokens(tokens: u64) -> String {
+    if tokens < 1000 {
+        tokens.to_string()
+    } else if tokens < 1_000_000 {
+        format!("{:.1}k", tokens as f64 / 1000.0)
+    } else {
+        format!("{:.1}M", tokens as f64 / 1_000_000.0)
+    }
+}
+
+/// Split `path:A-B` or `path:A` into the path and a 1-based inclusive line range.
+pub fn parse_reference(reference: &str) -> (&str, Option<(usize, usize)>) {
+    let Some((path, range)) = reference.rsplit_once(':') else { return (reference, None) };
+    let (start, end) = range.split_once('-').unwrap_or((range, range));
+    match (start.parse::<usize>(), end.parse::<usize>()) {
+        (Ok(start), Ok(end)) if start > 0 && end >= start => (path, Some((start, end))),
+        _ => (reference, None),
+    }
+}
+
+/// Bytes and lines of `content`, or of lines `range` of it.
+pub fn measure_text(content: &[u8], range: Option<(usize, usize)>) -> (u64, usize) {
+    match range {
+        None => {
+            let lines = content.split(|&b| b == b'\n').count()
+                - usize::from(content.is_empty() || content.ends_with(b"\n"));
+            (content.len() as u64, lines)
+        }
+        Some((start, end)) => content.split_inclusive(|&b| b == b'\n')
+            .skip(start - 1)
+            .take(end + 1 - start)
+            .fold((0, 0), |(bytes, lines), l| (bytes + l.len() as u64, lines + 1)),
+    }
+}
+
+/// Bytes and lines of lines `start..=end` of `reader`, reading no further
+/// than the end of the range.
+pub fn measure_range(mut reader: impl BufRead, start: usize, end: usize) -> io::Result<(u64, usize)> {
+    let mut line = Vec::new();
+    let (mut bytes, mut lines) = (0, 0);
+    for number in 1..=end {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        if number >= start {
+            bytes += line.len() as u64;
+            lines += 1;
+        }
+    }
+    Ok((bytes, lines))
+}
+
+/// Lines of a file of `len` bytes, extrapolated from its first bytes `head`.
+pub fn estimate_lines(head: &[u8], len: u64) -> usize {
+    if head.is_empty() {
+        return 0;
+    }
+    let (_, head_lines) = measure_text(head, None);
+    (head_lines as u64 * len / head.len() as u64) as usize
+}
@@ -24,6 +24,253 @@ pub struct DiffHunk {
 pub struct FileDiff {
     pub path: String,
     pub hunks: Vec<DiffHunk>,
+    /// Set when the hunks are only a summary (see `GitRepo::load_full_diff`)
+    pub collapsed: Option<Collapsed>,
+}
+
+/// Why a diff was replaced by a summary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollapseReason {
+    Binary,
+    Large,
+    Generated,
+}
+
+impl CollapseReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            CollapseReason::Binary => "binary file",
+            CollapseReason::Large => "large file",
+            CollapseReason::Generated => "generated file",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Collapsed {
+    pub reason: CollapseReason,
+    /// The summary stands in for the whole-file view rather than the diff
+    pub full_context: bool,
+}
+
+/// Thresholds above which a diff is shown as a summary and loaded on demand.
+#[derive(Debug, Clone, Copy)]
+pub struct DiffLimits {
+    pub max_bytes: u64,
+    pub max_lines: usize,
+    /// Collapse lockfiles and minified files
+    pub collapse_generated: bool,
+}
+
+impl Default for DiffLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1024 * 1024,
+            max_lines: 5000,
+            collapse_generated: true,
+        }
+    }
+}
+
+/// How whitespace differences are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Whitespace {
+    /// Every whitespace change is a change
+    Show,
+    /// Ignore changes in the amount of whitespace (`git diff -b`)
+    IgnoreChange,
+    /// Ignore all whitespace (`git diff -w`)
+    IgnoreAll,
+}
+
+impl Whitespace {
+    pub fn next(self) -> Self {
+        match self {
+            Whitespace::Show => Whitespace::IgnoreChange,
+            Whitespace::IgnoreChange => Whitespace::IgnoreAll,
+            Whitespace::IgnoreAll => Whitespace::Show,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Whitespace::Show => "whitespace shown",
+            Whitespace::IgnoreChange => "ignore whitespace change",
+            Whitespace::IgnoreAll => "ignore all whitespace",
+        }
+    }
+}
+
+/// Diff algorithm; libgit2 has no histogram, patience is the closest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DiffAlgorithm {
+    Myers,
+    Patience,
+    Minimal,
+}
+
+impl DiffAlgorithm {
+    pub fn next(self) -> Self {
+        match self {
+            DiffAlgorithm::Myers => DiffAlgorithm::Patience,
+            DiffAlgorithm::Patience => DiffAlgorithm::Minimal,
+            DiffAlgorithm::Minimal => DiffAlgorithm::Myers,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DiffAlgorithm::Myers => "myers",
+            DiffAlgorithm::Patience => "patience",
+            DiffAlgorithm::Minimal => "minimal",
+        }
+    }
+}
+
+/// Options every file diff is computed with, toggled from the diff view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffSettings {
+    pub context: u32,
+    pub whitespace: Whitespace,
+    pub algorithm: DiffAlgorithm,
+}
+
+impl Default for DiffSettings {
+    fn default() -> Self {
+        Self {
+            context: 3,
+            whitespace: Whitespace::Show,
+            algorithm: DiffAlgorithm::Myers,
+        }
+    }
+}
+
+impl DiffSettings {
+    pub fn apply(&self, opts: &mut git2::DiffOptions) {
+        opts.context_lines(self.context)
+            .ignore_whitespace_change(self.whitespace == Whitespace::IgnoreChange)
+            .ignore_whitespace(self.whitespace == Whitespace::IgnoreAll)
+            .patience(self.algorithm == DiffAlgorithm::Patience)
+            .minimal(self.algorithm == DiffAlgorithm::Minimal);
+    }
+
+    /// Short description of what differs from the defaults, for the diff title.
+    pub fn label(&self) -> String {
+        let default = Self::default();
+        let mut parts = Vec::new();
+        if self.context != default.context {
+            parts.push(format!("U{}", self.context));
+        }
+        match self.whitespace {
+            Whitespace::Show => {}
+            Whitespace::IgnoreChange => parts.push("-b".to_string()),
+            Whitespace::IgnoreAll => parts.push("-w".to_string()),
+        }
+        if self.algorithm != default.algorithm {
+            parts.push(self.algorithm.label().to_string());
+        }
+        parts.join(" ")
+    }
+}
+
+/// Line ranges of a `@@ -a,b +c,d @@` header. Starts are 1-based and point
+/// at the first line of the range even when it is empty (git points at the
+/// line before).
+#[derive(Debug, Clone)]
+struct HunkRange {
+    old_start: u32,
+    old_len: u32,
+    new_start: u32,
+    new_len: u32,
+    /// Text after the closing `@@` (function context)
+    section: String,
+}
+
+impl HunkRange {
+    fn parse(header: &str) -> Option<Self> {
+        let rest = header.strip_prefix("@@ -")?;
+        let (ranges, section) = rest.split_once(" @@")?;
+        let (old, new) = ranges.split_once(" +")?;
+        let range = |s: &str| -> Option<(u32, u32)> {
+            let (start, len) = match s.split_once(',') {
+                Some((start, len)) => (start.parse().ok()?, len.parse().ok()?),
+                None => (s.parse().ok()?, 1),
+            };
+            Some((if len == 0 { start + 1 } else { start }, len))
+        };
+        let (old_start, old_len) = range(old)?;
+        let (new_start, new_len) = range(new)?;
+        Some(Self {
+            old_start,
+            old_len,
+            new_start,
+            new_len,
+            section: section.trim_end_matches('\n').to_string(),
+        })
+    }
+
+    fn old_end(&self) -> u32 {
+        self.old_start + self.old_len
+    }
+
+    fn new_end(&self) -> u32 {
+        self.new_start + self.new_len
+    }
+
+    fn header(&self) -> String {
+        let start = |start: u32, len: u32| if len == 0 { start - 1 } else { start };
+        format!(
+            "@@ -{},{} +{},{} @@{}\n",
+            start(self.old_start, self.old_len),
+            self.old_len,
+            start(self.new_start, self.new_len),
+            self.new_len,
+            self.section,
+        )
+    }
+}
+
+impl DiffHunk {
+    fn set_range(&mut self, range: &HunkRange) {
+        self.header = range.header();
+        if let Some(first) = self.lines.first_mut().filter(|l| l.kind == DiffLineKind::HunkHeader) {
+            first.content = self.header.clone();
+        }
+    }
+}
+
+const LOCKFILES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "Gemfile.lock",
+    "composer.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "uv.lock",
+    "go.sum",
+    "flake.lock",
+    "mix.lock",
+    "pubspec.lock",
+];
+
+/// Lockfiles and minified bundles, judged by name.
+pub fn is_generated_path(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    LOCKFILES.contains(&name)
+        || [".min.js", ".min.css", ".min.mjs", ".js.map", ".css.map"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+}
+
+/// Extensions whose single very long lines mean "minified".
+pub fn may_be_minified(path: &str) -> bool {
+    ["js", "mjs", "cjs", "css", "json", "svg"]
+        .iter()
+        .any(|ext| path.rsplit('.').next() == Some(ext))
 }
 
 impl FileDiff {
@@ -34,6 +281,91 @@ impl FileDiff {
     pub fn total_lines(&self) -> usize {
         self.hunks.iter().map(|h| h.lines.len()).sum()
     }
+
+    /// Grow hunk `index` by up to `above` and `below` context lines taken from
+    /// `new_side` (the new file's lines), merging it with neighbours it reaches.
+    /// Returns false when the hunk has no line ranges (summaries, commit headers).
+    pub fn expand_hunk(&mut self, index: usize, new_side: &[String], above: u32, below: u32) -> bool {
+        let Some(mut range) = self.hunks.get(index).and_then(|h| HunkRange::parse(&h.header)) else {
+            return false;
+        };
+        let ranges: Vec<Option<HunkRange>> = self.hunks.iter().map(|h| HunkRange::parse(&h.header)).collect();
+        let floor = index.checked_sub(1)
+            .and_then(|i| ranges[i].as_ref())
+            .map_or(1, |r| r.new_end());
+        let ceil = ranges.get(index + 1)
+            .and_then(|r| r.as_ref())
+            .map_or(new_side.len() as u32 + 1, |r| r.new_start);
+        let context = |new: u32, old: u32| DiffLine {
+            kind: DiffLineKind::Context,
+            content: format!("{}\n", new_side.get(new as usize - 1).map(String::as_str).unwrap_or("")),
+            old_lineno: Some(old),
+            new_lineno: Some(new),
+        };
+
+        let first = range.new_start.saturating_sub(above).max(floor).min(range.new_start);
+        let added_above: Vec<DiffLine> = (first..range.new_start)
+            .map(|n| context(n, n + range.old_start - range.new_start))
+            .collect();
+        let grown = added_above.len() as u32;
+        range.old_start -= grown;
+        range.new_start -= grown;
+        range.old_len += grown;
+        range.new_len += grown;
+
+        let last = range.new_end().saturating_add(below).min(ceil).max(range.new_end());
+        let added_below: Vec<DiffLine> = (range.new_end()..last)
+            .map(|n| context(n, n + range.old_end() - range.new_end()))
+            .collect();
+        range.old_len += added_below.len() as u32;
+        range.new_len += added_below.len() as u32;
+
+        let hunk = &mut self.hunks[index];
+        let at = usize::from(hunk.lines.first().is_some_and(|l| l.kind == DiffLineKind::HunkHeader));
+        hunk.lines.splice(at..at, added_above);
+        hunk.lines.extend(added_below);
+        hunk.set_range(&range);
+
+        // Merge with the next hunk first so `index` stays valid for the previous one
+        if range.new_end() == ceil && index + 1 < self.hunks.len() && ranges[index + 1].is_some() {
+            self.merge_hunks(index);
+        }
+        if range.new_start == floor && index > 0 && ranges[index - 1].is_some() {
+            self.merge_hunks(index - 1);
+        }
+        true
+    }
+
+    /// Append hunk `index + 1` to hunk `index`; both must have line ranges.
+    fn merge_hunks(&mut self, index: usize) {
+        let (Some(mut a), Some(b)) = (HunkRange::parse(&self.hunks[index].header), HunkRange::parse(&self.hunks[index + 1].header)) else {
+            return;
+        };
+        let next = self.hunks.remove(index + 1);
+        a.old_len = b.old_end() - a.old_start;
+        a.new_len = b.new_end() - a.new_start;
+        let hunk = &mut self.hunks[index];
+        hunk.lines.extend(next.lines.into_iter().filter(|l| l.kind != DiffLineKind::HunkHeader));
+        hunk.set_range(&a);
+    }
+
+    /// Index of the hunk containing diff line `line` (in `all_lines` order).
+    pub fn hunk_of_line(&self, line: usize) -> Option<usize> {
+        let mut end = 0;
+        self.hunks.iter().position(|h| {
+            end += h.lines.len();
+            line < end
+        })
+    }
+
+    /// Count (additions, deletions) across all hunks.
+    pub fn stats(&self) -> (usize, usize) {
+        self.all_lines().iter().fold((0, 0), |(a, d), l| match l.kind {
+            DiffLineKind::Addition => (a + 1, d),
+            DiffLineKind::Deletion => (a, d + 1),
+            _ => (a, d),
+        })
+    }
 }
 
 impl DiffLine {
@@ -0,0 +1,48 @@
+//! Executable bits and symlinks of worktree files. Only Unix has them;
+//! elsewhere files read as regular and symlinks are written as plain files
+//! holding their target, as git does with `core.symlinks=false`.
+
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+
+/// Whether git records the file as executable.
+#[cfg(unix)]
+pub fn is_executable(meta: &Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+pub fn is_executable(_meta: &Metadata) -> bool {
+    false
+}
+
+/// Set or clear the executable bits, keeping the rest of the file's
+/// permissions. Like git, execute is granted wherever read is, so the
+/// umask a new file was created with still applies.
+#[cfg(unix)]
+pub fn set_executable(path: &Path, executable: bool) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(path)?.permissions().mode();
+    let new_mode = if executable { mode | ((mode & 0o444) >> 2) } else { mode & !0o111 };
+    if new_mode != mode {
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(new_mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn set_executable(_path: &Path, _executable: bool) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn symlink(target: &str, path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(not(unix))]
+pub fn symlink(target: &str, path: &Path) -> io::Result<()> {
+    std::fs::write(path, target)
+}
@@ -1,4 +1,15 @@
 pub mod repo;
 pub mod status;
 pub mod diff;
+pub mod file_mode;
 pub mod operations;
+pub mod commit_draft;
+pub mod undo;
+pub mod blame;
+pub mod checkpoint;
+pub mod word_diff;
+pub mod compare;
+pub mod context_size;
+pub mod patch;
+pub mod tag;
+pub mod turn;
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
-use std::process::Command;
+use std::process::{Command, Stdio};
 
+#[derive(Clone)]
 pub struct GitOps {
     workdir: String,
 }
@@ -18,9 +19,29 @@ impl GitOps {
         cmd
     }
 
-    pub fn stage_file(&self, path: &str) -> Result<()> {
+    /// `git` for commands run behind the TUI: a credential or passphrase
+    /// prompt fails the command instead of blocking on (or drawing over)
+    /// the terminal. A configured ssh command is left alone.
+    fn git_background(&self) -> Command {
+        let mut cmd = self.git();
+        cmd.stdin(Stdio::null()).env("GIT_TERMINAL_PROMPT", "0");
+        let ssh_configured = std::env::var_os("GIT_SSH_COMMAND").is_some()
+            || std::env::var_os("GIT_SSH").is_some()
+            || self.git()
+                .args(["config", "--get", "core.sshCommand"])
+                .output()
+                .is_ok_and(|o| o.status.success());
+        if !ssh_configured {
+            cmd.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+        }
+        cmd
+    }
+
+    /// Stage `paths` with a single `git add`.
+    pub fn stage_files(&self, paths: &[&str]) -> Result<()> {
         let output = self.git()
-            .args(["add", "--", path])
+            .args(["add", "--"])
+            .args(paths)
             .output()
             .context("Failed to run git add")?;
         if !output.status.success() {
@@ -29,13 +50,15 @@ impl GitOps {
         Ok(())
     }
 
-    pub fn unstage_file(&self, path: &str) -> Result<()> {
+    /// Unstage `paths` with a single `git restore --staged`.
+    pub fn unstage_files(&self, paths: &[&str]) -> Result<()> {
         let output = self.git()
-            .args(["reset", "HEAD", "--", path])
+            .args(["restore", "--staged", "--"])
+            .args(paths)
             .output()
-            .context("Failed to run git reset")?;
+            .context("Failed to run git restore")?;
         if !output.status.success() {
-            anyhow::bail!("git reset failed: {}", String::from_utf8_lossy(&output.stderr));
+            anyhow::bail!("git restore failed: {}", String::from_utf8_lossy(&output.stderr));
         }
         Ok(())
     }
@@ -63,7 +86,7 @@ impl GitOps {
     }
 
     pub fn push(&self) -> Result<String> {
-        let output = self.git()
+        let output = self.git_background()
             .args(["push"])
             .output()
             .context("Failed to run git push")?;
@@ -78,6 +101,50 @@ impl GitOps {
         Ok(combined)
     }
 
+    /// Push and set the upstream for a branch that has none yet.
+    pub fn push_set_upstream(&self, remote: &str, branch: &str) -> Result<String> {
+        let output = self.git_background()
+            .args(["push", "--set-upstream", remote, branch])
+            .output()
+            .context("Failed to run git push --set-upstream")?;
+        let combined = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+        if !output.status.success() {
+            anyhow::bail!("git push failed: {}", combined.trim());
+        }
+        Ok(combined)
+    }
+
+    pub fn push_force_with_lease(&self) -> Result<String> {
+        let output = self.git_background()
+            .args(["push", "--force-with-lease"])
+            .output()
+            .context("Failed to run git push --force-with-lease")?;
+        let combined = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+        if !output.status.success() {
+            anyhow::bail!("git push --force-with-lease failed: {}", combined.trim());
+        }
+        Ok(combined)
+    }
+
+    pub fn fetch(&self) -> Result<String> {
+        let output = self.git_background()
+            .args(["fetch", "--all", "--prune"])
+            .output()
+            .context("Failed to run git fetch")?;
+        if !output.status.success() {
+            anyhow::bail!("git fetch failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+
     pub fn pull(&self) -> Result<String> {
         let output = self.git()
             .args(["pull"])
@@ -100,6 +167,32 @@ impl GitOps {
         Ok(())
     }
 
+    /// `git submodule update --init --recursive`, for one submodule or all.
+    pub fn submodule_update(&self, path: Option<&str>) -> Result<String> {
+        let mut cmd = self.git();
+        cmd.args(["submodule", "update", "--init", "--recursive"]);
+        if let Some(path) = path {
+            cmd.args(["--", path]);
+        }
+        let output = cmd.output().context("Failed to run git submodule update")?;
+        if !output.status.success() {
+            anyhow::bail!("git submodule update failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Delete an untracked file (the counterpart of `discard_file`).
+    pub fn clean_file(&self, path: &str) -> Result<()> {
+        let output = self.git()
+            .args(["clean", "-f", "--", path])
+            .output()
+            .context("Failed to run git clean")?;
+        if !output.status.success() {
+            anyhow::bail!("git clean failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
     pub fn stash(&self) -> Result<String> {
         let output = self.git()
             .args(["stash"])
@@ -122,6 +215,29 @@ impl GitOps {
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
     }
 
+    pub fn stash_drop(&self) -> Result<String> {
+        let output = self.git()
+            .args(["stash", "drop"])
+            .output()
+            .context("Failed to run git stash drop")?;
+        if !output.status.success() {
+            anyhow::bail!("git stash drop failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Re-add a dropped stash commit to the stash list.
+    pub fn stash_store(&self, oid: &str, message: &str) -> Result<()> {
+        let output = self.git()
+            .args(["stash", "store", "-m", message, oid])
+            .output()
+            .context("Failed to run git stash store")?;
+        if !output.status.success() {
+            anyhow::bail!("git stash store failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
     pub fn branch_list(&self) -> Result<Vec<String>> {
         let output = self.git()
             .args(["branch", "--format=%(refname:short)"])
@@ -155,4 +271,158 @@ impl GitOps {
         }
         Ok(())
     }
+
+    pub fn delete_branch(&self, name: &str) -> Result<()> {
+        let output = self.git()
+            .args(["branch", "-D", name])
+            .output()
+            .context("Failed to run git branch -D")?;
+        if !output.status.success() {
+            anyhow::bail!("git branch -D failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
+    /// Recreate a branch pointing at `oid`.
+    pub fn restore_branch(&self, name: &str, oid: &str) -> Result<()> {
+        let output = self.git()
+            .args(["branch", name, oid])
+            .output()
+            .context("Failed to run git branch")?;
+        if !output.status.success() {
+            anyhow::bail!("git branch failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
+    /// Unified diff of the staged changes, or of `range` (`A..B`) when given.
+    pub fn diff_patch(&self, range: Option<&str>) -> Result<String> {
+        let mut cmd = self.git();
+        cmd.args(["diff", "--binary"]);
+        match range {
+            Some(range) => cmd.arg(range),
+            None => cmd.arg("--cached"),
+        };
+        let output = cmd.output().context("Failed to run git diff")?;
+        if !output.status.success() {
+            anyhow::bail!("git diff failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Unified diff of `paths` against HEAD (staged and unstaged changes),
+    /// against the empty tree before the first commit. Untracked files under
+    /// `paths` are included as new files.
+    pub fn diff_files(&self, paths: &[String]) -> Result<String> {
+        let has_head = self.git()
+            .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+            .output()
+            .is_ok_and(|o| o.status.success());
+        let base = if has_head {
+            "HEAD".to_string()
+        } else {
+            let output = self.git()
+                .args(["hash-object", "-t", "tree", "/dev/null"])
+                .output()
+                .context("Failed to run git hash-object")?;
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        };
+        let output = self.git()
+            .args(["diff", &base, "--"])
+            .args(paths)
+            .output()
+            .context("Failed to run git diff")?;
+        if !output.status.success() {
+            anyhow::bail!("git diff failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        let mut diff = String::from_utf8_lossy(&output.stdout).to_string();
+
+        let untracked = self.git()
+            .args(["ls-files", "--others", "--exclude-standard", "-z", "--"])
+            .args(paths)
+            .output()
+            .context("Failed to run git ls-files")?;
+        for path in untracked.stdout.split(|&b| b == 0).filter(|p| !p.is_empty()) {
+            let path = String::from_utf8_lossy(path);
+            let output = self.git()
+                .args(["diff", "--no-index", "--", "/dev/null", path.as_ref()])
+                .output()
+                .context("Failed to run git diff --no-index")?;
+            // Exit status 1 just means the files differ
+            if output.status.code().is_some_and(|c| c > 1) {
+                anyhow::bail!("git diff failed: {}", String::from_utf8_lossy(&output.stderr));
+            }
+            diff.push_str(&String::from_utf8_lossy(&output.stdout));
+        }
+        Ok(diff)
+    }
+
+    /// `git apply` a patch file to the worktree, or the index when `cached`.
+    /// `reject` applies what it can and leaves `*.rej` files; `check` only tests.
+    pub fn apply_patch(&self, patch: &str, cached: bool, reject: bool, check: bool) -> Result<String> {
+        let mut cmd = self.git();
+        cmd.arg("apply");
+        if cached {
+            cmd.arg("--cached");
+        }
+        if reject {
+            cmd.arg("--reject");
+        }
+        if check {
+            cmd.arg("--check");
+        }
+        let output = cmd.args(["--verbose", "--", patch])
+            .output()
+            .context("Failed to run git apply")?;
+        if !output.status.success() {
+            anyhow::bail!("git apply failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(String::from_utf8_lossy(&output.stderr).to_string())
+    }
+
+    /// Create a tag on `target` (HEAD when None); annotated when a message is given.
+    pub fn create_tag(&self, name: &str, target: Option<&str>, message: Option<&str>) -> Result<()> {
+        let mut cmd = self.git();
+        cmd.arg("tag");
+        if let Some(message) = message {
+            cmd.args(["-a", "-m", message]);
+        }
+        cmd.arg(name);
+        if let Some(target) = target {
+            cmd.arg(target);
+        }
+        let output = cmd.output().context("Failed to run git tag")?;
+        if !output.status.success() {
+            anyhow::bail!("git tag failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
+    pub fn delete_tag(&self, name: &str) -> Result<()> {
+        let output = self.git()
+            .args(["tag", "-d", name])
+            .output()
+            .context("Failed to run git tag -d")?;
+        if !output.status.success() {
+            anyhow::bail!("git tag -d failed: {}", String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
+    /// Push all tags to `remote`.
+    pub fn push_tags(&self, remote: &str) -> Result<String> {
+        let output = self.git_background()
+            .args(["push", remote, "--tags"])
+            .output()
+            .context("Failed to run git push --tags")?;
+        let combined = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+        if !output.status.success() {
+            anyhow::bail!("git push --tags failed: {}", combined.trim());
+        }
+        Ok(combined)
+    }
 }
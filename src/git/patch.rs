@@ -0,0 +1,87 @@
+//! Serialising a `FileDiff` (or some of its hunks) as a unified patch that
+//! `git apply` accepts.
+
+use anyhow::Result;
+
+use super::diff::{DiffLineKind, FileDiff};
+
+/// libgit2 reports a missing final newline as an extra line with this content.
+const NO_NEWLINE_MARKER: &str = "\n\\ No newline at end of file";
+
+/// Mode of a regular file, for sides whose mode is unknown.
+const DEFAULT_MODE: u32 = 0o100644;
+
+/// Unified patch for `hunks` of `diff` (all hunks when None). `old_path`
+/// makes it a rename patch; `modes` are the (old, new) file modes, 0 for a
+/// missing side. Fails for summaries and views without line ranges (commit
+/// views, submodule summaries, hex dumps).
+pub fn format_patch(diff: &FileDiff, old_path: Option<&str>, hunks: Option<&[usize]>, modes: (u32, u32)) -> Result<String> {
+    if diff.collapsed.is_some() {
+        anyhow::bail!("Load the full diff (L) before exporting it");
+    }
+    let selected: Vec<usize> = match hunks {
+        Some(h) => h.to_vec(),
+        None => (0..diff.hunks.len()).collect(),
+    };
+    if selected.is_empty() {
+        anyhow::bail!("No changes to export");
+    }
+
+    let path = diff.path.as_str();
+    let old = old_path.unwrap_or(path);
+    let mut body = String::new();
+    let (mut created, mut deleted) = (false, false);
+    for &i in &selected {
+        let Some(hunk) = diff.hunks.get(i) else { continue };
+        if !hunk.header.starts_with("@@ -") {
+            anyhow::bail!("{} has no line ranges and cannot be exported as a patch", diff.path);
+        }
+        created |= hunk.header.starts_with("@@ -0,0 ");
+        deleted |= hunk.header.contains(" +0,0 @@");
+        body.push_str(&hunk.header);
+        if !hunk.header.ends_with('\n') {
+            body.push('\n');
+        }
+        for line in &hunk.lines {
+            let prefix = match line.kind {
+                DiffLineKind::HunkHeader => continue,
+                DiffLineKind::Addition => '+',
+                DiffLineKind::Deletion => '-',
+                DiffLineKind::Context => ' ',
+            };
+            if line.content.starts_with(NO_NEWLINE_MARKER) {
+                continue;
+            }
+            body.push(prefix);
+            body.push_str(&line.content);
+            if !line.content.ends_with('\n') {
+                body.push_str(NO_NEWLINE_MARKER);
+                body.push('\n');
+            }
+        }
+    }
+
+    let mode = |m: u32| if m == 0 { DEFAULT_MODE } else { m };
+    let (old_mode, new_mode) = (mode(modes.0), mode(modes.1));
+    let mut out = format!("diff --git a/{} b/{}\n", old, path);
+    if deleted {
+        out.push_str(&format!("deleted file mode {:o}\n", old_mode));
+    } else if !created && old_mode != new_mode {
+        out.push_str(&format!("old mode {:o}\nnew mode {:o}\n", old_mode, new_mode));
+    }
+    if created {
+        out.push_str(&format!("new file mode {:o}\n--- /dev/null\n", new_mode));
+    } else {
+        if old != path {
+            out.push_str(&format!("rename from {}\nrename to {}\n", old, path));
+        }
+        out.push_str(&format!("--- a/{}\n", old));
+    }
+    if deleted {
+        out.push_str("+++ /dev/null\n");
+    } else {
+        out.push_str(&format!("+++ b/{}\n", path));
+    }
+    out.push_str(&body);
+    Ok(out)
+}
@@ -1,18 +1,83 @@
 use anyhow::{Context, Result};
 use git2::{DiffOptions, Repository, StatusOptions};
 
-use super::diff::{DiffHunk, DiffLine, DiffLineKind, FileDiff};
-use super::status::{FileStatus, FileStatusKind, StageState};
+use super::blame::BlameLine;
+use super::checkpoint::{self, Checkpoint, CheckpointFile, CHECKPOINT_REF_PREFIX, MAX_CHECKPOINTS};
+use super::compare::CompareSpec;
+use super::context_size::{self, ContextSize, MAX_DIR_FILES};
+use super::file_mode;
+use super::tag::TagInfo;
+use super::turn::{self, TurnChange, TurnFile, TurnSnapshot};
+use super::diff::{self, CollapseReason, Collapsed, DiffHunk, DiffLimits, DiffLine, DiffLineKind, DiffSettings, FileDiff, Whitespace};
+use super::status::{BranchInfo, FileStatus, FileStatusKind, StageState, SubmoduleState};
+use super::undo::{self, UndoEntry, UndoKind, SNAPSHOT_ENTRY, UNDO_REF_PREFIX};
+
+/// Default similarity (percent) for rename detection, as in `git diff -M`.
+pub const DEFAULT_RENAME_THRESHOLD: u16 = 50;
+
+/// Bytes inspected for NUL (binary) and very long lines (minified), like git.
+const SNIFF_BYTES: usize = 8000;
+/// Bytes of each side shown in the hex view.
+const HEX_VIEW_BYTES: usize = 64 * 1024;
 
 pub struct GitRepo {
     repo: Repository,
+    rename_threshold: u16,
+    limits: DiffLimits,
+    settings: DiffSettings,
+}
+
+/// Result of `scan_worktree`.
+struct WorktreeScan {
+    /// The worktree as a tree, when asked for
+    tree: Option<git2::Oid>,
+    snapshot: TurnSnapshot,
+    /// Files over `checkpoint::MAX_FILE_BYTES`, kept out of the tree
+    left_out: Vec<String>,
+}
+
+/// One side of a file diff: a blob in the object database or a worktree file.
+enum FileSide {
+    Blob(git2::Oid),
+    Workdir(std::path::PathBuf),
 }
 
 impl GitRepo {
     pub fn open(path: &str) -> Result<Self> {
         let repo = Repository::discover(path)
             .context("Failed to find git repository")?;
-        Ok(Self { repo })
+        Ok(Self {
+            repo,
+            rename_threshold: DEFAULT_RENAME_THRESHOLD,
+            limits: DiffLimits::default(),
+            settings: DiffSettings::default(),
+        })
+    }
+
+    pub fn with_rename_threshold(mut self, threshold: u16) -> Self {
+        self.rename_threshold = threshold.min(100);
+        self
+    }
+
+    pub fn with_diff_limits(mut self, limits: DiffLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_diff_settings(mut self, settings: DiffSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    pub fn set_diff_settings(&mut self, settings: DiffSettings) {
+        self.settings = settings;
+    }
+
+    /// Fresh options carrying the context, whitespace and algorithm settings.
+    fn diff_options(&self) -> DiffOptions {
+        let mut opts = DiffOptions::new();
+        self.settings.apply(&mut opts);
+        opts
     }
 
     pub fn workdir(&self) -> Option<&std::path::Path> {
@@ -23,37 +88,71 @@ impl GitRepo {
         let mut opts = StatusOptions::new();
         opts.include_untracked(true)
             .recurse_untracked_dirs(true)
-            .include_unmodified(false);
+            .include_unmodified(false)
+            .renames_head_to_index(true)
+            .renames_index_to_workdir(true)
+            .rename_threshold(self.rename_threshold);
 
         let statuses = self.repo.statuses(Some(&mut opts))?;
         let mut result = Vec::new();
 
+        // Submodule path -> name, to look up their own status
+        let submodules: std::collections::HashMap<String, String> = self.repo.submodules()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|s| Some((s.path().to_string_lossy().to_string(), s.name()?.to_string())))
+            .collect();
+
         for entry in statuses.iter() {
-            let path = entry.path().unwrap_or("").to_string();
             let status = entry.status();
 
-            let index_status = if status.contains(git2::Status::INDEX_NEW) {
+            // For renames `entry.path()` is the old path; take both ends from the deltas
+            let delta_path = |d: Option<git2::DiffDelta>, new: bool| -> Option<String> {
+                let d = d?;
+                let file = if new { d.new_file() } else { d.old_file() };
+                file.path().map(|p| p.to_string_lossy().to_string())
+            };
+            let (path, old_path) = if status.intersects(git2::Status::INDEX_RENAMED | git2::Status::WT_RENAMED) {
+                let old = if status.contains(git2::Status::INDEX_RENAMED) {
+                    delta_path(entry.head_to_index(), false)
+                } else {
+                    delta_path(entry.index_to_workdir(), false)
+                };
+                let new = if status.contains(git2::Status::WT_RENAMED) {
+                    delta_path(entry.index_to_workdir(), true)
+                } else {
+                    delta_path(entry.head_to_index(), true)
+                };
+                let path = new.unwrap_or_else(|| entry.path().unwrap_or("").to_string());
+                (path.clone(), old.filter(|o| *o != path))
+            } else {
+                (entry.path().unwrap_or("").to_string(), None)
+            };
+            let submodule = submodules.get(&path).map(|name| self.submodule_state(name));
+
+            // Renames come first: an edited rename also carries the MODIFIED bit
+            let index_status = if status.contains(git2::Status::INDEX_RENAMED) {
+                Some(FileStatusKind::Renamed)
+            } else if status.contains(git2::Status::INDEX_NEW) {
                 Some(FileStatusKind::New)
             } else if status.contains(git2::Status::INDEX_MODIFIED) {
                 Some(FileStatusKind::Modified)
             } else if status.contains(git2::Status::INDEX_DELETED) {
                 Some(FileStatusKind::Deleted)
-            } else if status.contains(git2::Status::INDEX_RENAMED) {
-                Some(FileStatusKind::Renamed)
             } else if status.contains(git2::Status::INDEX_TYPECHANGE) {
                 Some(FileStatusKind::Typechange)
             } else {
                 None
             };
 
-            let worktree_status = if status.contains(git2::Status::WT_NEW) {
+            let worktree_status = if status.contains(git2::Status::WT_RENAMED) {
+                Some(FileStatusKind::Renamed)
+            } else if status.contains(git2::Status::WT_NEW) {
                 Some(FileStatusKind::Untracked)
             } else if status.contains(git2::Status::WT_MODIFIED) {
                 Some(FileStatusKind::Modified)
             } else if status.contains(git2::Status::WT_DELETED) {
                 Some(FileStatusKind::Deleted)
-            } else if status.contains(git2::Status::WT_RENAMED) {
-                Some(FileStatusKind::Renamed)
             } else if status.contains(git2::Status::WT_TYPECHANGE) {
                 Some(FileStatusKind::Typechange)
             } else {
@@ -73,6 +172,8 @@ impl GitRepo {
             if status.contains(git2::Status::CONFLICTED) {
                 result.push(FileStatus {
                     path,
+                    old_path,
+                    submodule,
                     kind: FileStatusKind::Conflicted,
                     stage_state: StageState::Unstaged,
                     index_status: None,
@@ -81,6 +182,8 @@ impl GitRepo {
             } else {
                 result.push(FileStatus {
                     path,
+                    old_path,
+                    submodule,
                     kind,
                     stage_state,
                     index_status,
@@ -92,77 +195,363 @@ impl GitRepo {
         Ok(result)
     }
 
-    pub fn diff_file(&self, path: &str, staged: bool) -> Result<FileDiff> {
-        let mut diff_opts = DiffOptions::new();
+    /// Diff one file: HEAD vs index when `staged`, otherwise index vs workdir.
+    /// With `old_path` both ends of a rename are included and paired up,
+    /// so the diff is against the original file instead of a whole new file.
+    fn path_diff(
+        &self,
+        path: &str,
+        old_path: Option<&str>,
+        staged: bool,
+        diff_opts: &mut DiffOptions,
+    ) -> Result<git2::Diff<'_>> {
         diff_opts.pathspec(path);
+        if let Some(old) = old_path {
+            diff_opts.pathspec(old)
+                .include_untracked(true)
+                .recurse_untracked_dirs(true)
+                .show_untracked_content(true);
+        }
 
-        let diff = if staged {
+        let mut diff = if staged {
             let head_tree = self.repo.head()
                 .ok()
                 .and_then(|h| h.peel_to_tree().ok());
             self.repo.diff_tree_to_index(
                 head_tree.as_ref(),
                 None,
-                Some(&mut diff_opts),
+                Some(diff_opts),
             )?
         } else {
-            self.repo.diff_index_to_workdir(None, Some(&mut diff_opts))?
+            self.repo.diff_index_to_workdir(None, Some(diff_opts))?
         };
 
-        let mut hunks = Vec::new();
-        let mut current_lines: Vec<DiffLine> = Vec::new();
-        let mut current_header = String::new();
+        if old_path.is_some() {
+            let mut find = git2::DiffFindOptions::new();
+            find.renames(true)
+                .copies(true)
+                .for_untracked(true)
+                .rename_threshold(self.rename_threshold)
+                .copy_threshold(self.rename_threshold);
+            diff.find_similar(Some(&mut find))?;
+        }
+        Ok(diff)
+    }
 
-        diff.print(git2::DiffFormat::Patch, |_delta, hunk, line| {
-            match line.origin() {
-                'H' | 'F' => {}
-                _ => {
-                    if let Some(hunk) = hunk {
-                        let header = String::from_utf8_lossy(hunk.header()).to_string();
-                        if header != current_header && !current_header.is_empty() {
-                            hunks.push(DiffHunk {
-                                header: current_header.clone(),
-                                lines: std::mem::take(&mut current_lines),
-                            });
-                        }
-                        if header != current_header {
-                            current_header = header.clone();
-                            current_lines.push(DiffLine {
-                                kind: DiffLineKind::HunkHeader,
-                                content: header,
-                                old_lineno: None,
-                                new_lineno: None,
-                            });
-                        }
-                    }
+    /// Diff of one file, or a summary when it is binary, generated or over the limits.
+    pub fn diff_file(&self, path: &str, old_path: Option<&str>, staged: bool) -> Result<FileDiff> {
+        self.limited(path, old_path, staged, false)
+    }
 
-                    let content = String::from_utf8_lossy(line.content()).to_string();
-                    let kind = match line.origin() {
-                        '+' | '>' => DiffLineKind::Addition,
-                        '-' | '<' => DiffLineKind::Deletion,
-                        _ => DiffLineKind::Context,
-                    };
+    /// Whole file with diff markers, or a summary like `diff_file`.
+    pub fn file_contents(&self, path: &str, old_path: Option<&str>, staged: bool) -> Result<FileDiff> {
+        self.limited(path, old_path, staged, true)
+    }
 
-                    current_lines.push(DiffLine {
-                        kind,
-                        content,
-                        old_lineno: line.old_lineno(),
-                        new_lineno: line.new_lineno(),
-                    });
+    /// Load what a collapsed summary stands for: a hex view for binary
+    /// files, otherwise the diff (or whole file) regardless of size.
+    pub fn load_full_diff(&self, path: &str, old_path: Option<&str>, staged: bool, collapsed: Collapsed) -> Result<FileDiff> {
+        match (collapsed.reason, collapsed.full_context) {
+            (CollapseReason::Binary, _) => self.hex_diff(path, old_path, staged),
+            (_, true) => self.file_contents_within(path, old_path, staged, usize::MAX),
+            (_, false) => self.diff_file_within(path, old_path, staged, usize::MAX),
+        }
+    }
+
+    fn limited(&self, path: &str, old_path: Option<&str>, staged: bool, full_context: bool) -> Result<FileDiff> {
+        let (old, new) = self.file_sides(path, old_path, staged);
+        let collapsed = |reason| Collapsed { reason, full_context };
+
+        if let Some(reason) = self.collapse_reason(path, old.as_ref(), new.as_ref()) {
+            return Ok(self.summary(path, collapsed(reason), old.as_ref(), new.as_ref(), None));
+        }
+        let diff = if full_context {
+            self.file_contents_within(path, old_path, staged, self.limits.max_lines)?
+        } else {
+            self.diff_file_within(path, old_path, staged, self.limits.max_lines)?
+        };
+        if diff.collapsed.is_none() {
+            return Ok(diff);
+        }
+        let stats = self.line_stats(path, old_path, staged);
+        Ok(self.summary(path, collapsed(CollapseReason::Large), old.as_ref(), new.as_ref(), stats))
+    }
+
+    /// (additions, deletions) of a file's diff, untracked files included,
+    /// without building its lines.
+    fn line_stats(&self, path: &str, old_path: Option<&str>, staged: bool) -> Option<(usize, usize)> {
+        let mut diff_opts = self.diff_options();
+        diff_opts.include_untracked(true).show_untracked_content(true);
+        let stats = self.path_diff(path, old_path, staged, &mut diff_opts).ok()?.stats().ok()?;
+        Some((stats.insertions(), stats.deletions()))
+    }
+
+    fn in_index(&self, path: &str) -> bool {
+        self.repo.index().ok()
+            .and_then(|idx| idx.get_path(std::path::Path::new(path), 0))
+            .is_some()
+    }
+
+    /// Lines of the file a diff's new side shows (index when staged, worktree
+    /// otherwise), for expanding hunks in place.
+    pub fn new_side_lines(&self, path: &str, staged: bool) -> Result<Vec<String>> {
+        let (_, new) = self.file_sides(path, None, staged);
+        let side = new.context("File has no new side")?;
+        Ok(split_lines(&self.side_bytes(&side, usize::MAX)))
+    }
+
+    /// Lines of `path` in the target revision of a comparison.
+    pub fn compare_new_side_lines(&self, spec: &CompareSpec, path: &str) -> Result<Vec<String>> {
+        let entry = self.repo.find_commit(spec.target)?
+            .tree()?
+            .get_path(std::path::Path::new(path))?;
+        let blob = self.repo.find_blob(entry.id())?;
+        Ok(split_lines(blob.content()))
+    }
+
+    /// What sending `references` (relative to the workdir) would add to the
+    /// agent's context, read from the worktree. Binary and generated files,
+    /// files over `warn_bytes` and big directories come with a warning.
+    pub fn context_size(&self, references: &[String], warn_bytes: u64) -> ContextSize {
+        let mut size = ContextSize::default();
+        let Some(workdir) = self.repo.workdir() else { return size };
+        for reference in references {
+            let (path, range) = context_size::parse_reference(reference);
+            let path = path.trim_end_matches('/');
+            if !workdir.join(path).is_dir() {
+                self.measure_reference(&mut size, path, range, warn_bytes);
+                continue;
+            }
+            let files = self.dir_files(path);
+            if files.len() > MAX_DIR_FILES {
+                size.warnings.push(format!("{}/: {} files, only the first {} counted", path, files.len(), MAX_DIR_FILES));
+            } else if files.len() > context_size::DIR_WARN_FILES {
+                size.warnings.push(format!("{}/: {} files", path, files.len()));
+            }
+            for file in files.iter().take(MAX_DIR_FILES) {
+                self.measure_reference(&mut size, file, None, warn_bytes);
+            }
+        }
+        size
+    }
+
+    /// Tracked and untracked (not ignored) files under `dir`.
+    fn dir_files(&self, dir: &str) -> Vec<String> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_unmodified(true)
+            .exclude_submodules(true)
+            .pathspec(format!("{}/", dir));
+        let Ok(statuses) = self.repo.statuses(Some(&mut opts)) else { return Vec::new() };
+        statuses.iter()
+            .filter(|e| !e.status().intersects(git2::Status::WT_DELETED | git2::Status::INDEX_DELETED))
+            .filter_map(|e| e.path().map(|p| p.to_string()))
+            .collect()
+    }
+
+    /// Only the first `SNIFF_BYTES` are read to spot binaries; whole files
+    /// are read for their line count only up to `warn_bytes`, and ranges only
+    /// up to their last line.
+    fn measure_reference(&self, size: &mut ContextSize, path: &str, range: Option<(usize, usize)>, warn_bytes: u64) {
+        use std::io::{BufReader, Read};
+        let Some(full_path) = self.repo.workdir().map(|w| w.join(path)) else { return };
+        let measured = std::fs::File::open(&full_path).and_then(|file| {
+            let len = file.metadata()?.len();
+            let mut reader = BufReader::new(file);
+            let mut head = Vec::new();
+            (&mut reader).take(SNIFF_BYTES as u64).read_to_end(&mut head)?;
+            if head.contains(&0) {
+                return Ok((len, None));
+            }
+            let measured = match range {
+                Some((start, end)) => context_size::measure_range(head.as_slice().chain(reader), start, end)?,
+                None if len <= warn_bytes => {
+                    reader.read_to_end(&mut head)?;
+                    context_size::measure_text(&head, None)
                 }
+                None => (len, context_size::estimate_lines(&head, len)),
+            };
+            Ok((len, Some(measured)))
+        });
+        let Ok((len, measured)) = measured else {
+            size.warnings.push(format!("missing: {}", path));
+            return;
+        };
+        let file_size = crate::ui::file_panel::format_size(len);
+        let Some((bytes, lines)) = measured else {
+            size.warnings.push(format!("binary: {} ({})", path, file_size));
+            return;
+        };
+        if diff::is_generated_path(path) {
+            size.warnings.push(format!("generated: {} ({})", path, file_size));
+        } else if bytes > warn_bytes {
+            size.warnings.push(format!("large: {} ({})", path, crate::ui::file_panel::format_size(bytes)));
+        }
+        size.files += 1;
+        size.bytes += bytes;
+        size.lines += lines;
+    }
+
+    /// Old and new side of a file: HEAD → index when staged, index → worktree otherwise.
+    fn file_sides(&self, path: &str, old_path: Option<&str>, staged: bool) -> (Option<FileSide>, Option<FileSide>) {
+        let old_path = old_path.unwrap_or(path);
+        let index_blob = |p: &str| self.repo.index().ok()
+            .and_then(|idx| idx.get_path(std::path::Path::new(p), 0))
+            .map(|e| FileSide::Blob(e.id));
+        if staged {
+            let head = self.repo.head().ok()
+                .and_then(|h| h.peel_to_tree().ok())
+                .and_then(|t| t.get_path(std::path::Path::new(old_path)).ok())
+                .map(|e| FileSide::Blob(e.id()));
+            (head, index_blob(path))
+        } else {
+            let work = self.repo.workdir()
+                .map(|w| w.join(path))
+                .filter(|p| p.is_file())
+                .map(FileSide::Workdir);
+            (index_blob(old_path), work)
+        }
+    }
+
+    fn side_size(&self, side: &FileSide) -> u64 {
+        match side {
+            FileSide::Blob(id) => self.repo.odb().ok()
+                .and_then(|odb| odb.read_header(*id).ok())
+                .map(|(size, _)| size as u64)
+                .unwrap_or(0),
+            FileSide::Workdir(p) => std::fs::metadata(p).map(|m| m.len()).unwrap_or(0),
+        }
+    }
+
+    fn side_id(&self, side: &FileSide) -> Option<git2::Oid> {
+        match side {
+            FileSide::Blob(id) => Some(*id),
+            FileSide::Workdir(p) => git2::Oid::hash_file(git2::ObjectType::Blob, p).ok(),
+        }
+    }
+
+    /// Up to `limit` leading bytes of a side.
+    fn side_bytes(&self, side: &FileSide, limit: usize) -> Vec<u8> {
+        match side {
+            FileSide::Blob(id) => self.repo.find_blob(*id)
+                .map(|b| b.content()[..b.content().len().min(limit)].to_vec())
+                .unwrap_or_default(),
+            FileSide::Workdir(p) => {
+                use std::io::Read;
+                let mut buf = Vec::new();
+                if let Ok(f) = std::fs::File::open(p) {
+                    let _ = f.take(limit as u64).read_to_end(&mut buf);
+                }
+                buf
             }
-            true
-        })?;
+        }
+    }
 
-        if !current_lines.is_empty() {
-            hunks.push(DiffHunk {
-                header: current_header,
-                lines: current_lines,
-            });
+    fn collapse_reason(&self, path: &str, old: Option<&FileSide>, new: Option<&FileSide>) -> Option<CollapseReason> {
+        let samples: Vec<Vec<u8>> = [old, new].into_iter()
+            .flatten()
+            .map(|s| self.side_bytes(s, SNIFF_BYTES))
+            .collect();
+        if samples.iter().any(|s| s.contains(&0)) {
+            return Some(CollapseReason::Binary);
+        }
+        let size = [old, new].into_iter().flatten().map(|s| self.side_size(s)).max().unwrap_or(0);
+        if size > self.limits.max_bytes {
+            return Some(CollapseReason::Large);
         }
+        let minified = || diff::may_be_minified(path)
+            && samples.iter().any(|s| s.split(|&b| b == b'\n').any(|l| l.len() >= 1000));
+        if self.limits.collapse_generated && (diff::is_generated_path(path) || minified()) {
+            return Some(CollapseReason::Generated);
+        }
+        None
+    }
+
+    /// Summary hunk for a collapsed diff: size and blob id, old → new.
+    fn summary(
+        &self,
+        path: &str,
+        collapsed: Collapsed,
+        old: Option<&FileSide>,
+        new: Option<&FileSide>,
+        stats: Option<(usize, usize)>,
+    ) -> FileDiff {
+        let size = |s: Option<&FileSide>| s
+            .map(|s| crate::ui::file_panel::format_size(self.side_size(s)))
+            .unwrap_or_else(|| "(none)".to_string());
+        let id = |s: Option<&FileSide>| s
+            .and_then(|s| self.side_id(s))
+            .map(|id| id.to_string()[..7].to_string())
+            .unwrap_or_else(|| "(none)".to_string());
+        let line = |content: String| DiffLine {
+            kind: DiffLineKind::Context,
+            content,
+            old_lineno: None,
+            new_lineno: None,
+        };
+
+        let header = format!("@@ {} @@\n", collapsed.reason.label());
+        let mut lines = vec![
+            DiffLine {
+                kind: DiffLineKind::HunkHeader,
+                content: header.clone(),
+                old_lineno: None,
+                new_lineno: None,
+            },
+            line(format!("size  {} \u{2192} {}", size(old), size(new))),
+            line(format!("blob  {} \u{2192} {}", id(old), id(new))),
+        ];
+        if let Some((adds, dels)) = stats {
+            lines.push(line(format!("lines +{} -{}", adds, dels)));
+        }
+        lines.push(line(String::new()));
+        lines.push(line(match collapsed.reason {
+            CollapseReason::Binary => "Press L for a hex view".to_string(),
+            _ => "Press L to load it anyway".to_string(),
+        }));
+
+        FileDiff {
+            path: path.to_string(),
+            hunks: vec![DiffHunk { header, lines }],
+            collapsed: Some(collapsed),
+        }
+    }
+
+    /// Diff of hex dumps of both sides (first `HEX_VIEW_BYTES` of each).
+    fn hex_diff(&self, path: &str, old_path: Option<&str>, staged: bool) -> Result<FileDiff> {
+        let (old, new) = self.file_sides(path, old_path, staged);
+        self.hex_sides(path, old.as_ref(), new.as_ref())
+    }
+
+    fn hex_sides(&self, path: &str, old: Option<&FileSide>, new: Option<&FileSide>) -> Result<FileDiff> {
+        let dump = |s: Option<&FileSide>| s
+            .map(|s| hex_dump(&self.side_bytes(s, HEX_VIEW_BYTES)))
+            .unwrap_or_default();
+        let (old_text, new_text) = (dump(old), dump(new));
+        let patch = git2::Patch::from_buffers(
+            old_text.as_bytes(),
+            None,
+            new_text.as_bytes(),
+            None,
+            Some(DiffOptions::new().force_text(true)),
+        )?;
+        Ok(FileDiff {
+            path: format!("{} (hex)", path),
+            hunks: patch_hunks(&patch)?,
+            collapsed: None,
+        })
+    }
+
+    /// Diff of one file; past `max_lines` lines it stops and comes back as `over_limit`.
+    fn diff_file_within(&self, path: &str, old_path: Option<&str>, staged: bool, max_lines: usize) -> Result<FileDiff> {
+        let mut diff_opts = self.diff_options();
+        let diff = self.path_diff(path, old_path, staged, &mut diff_opts)?;
+
+        let Some(mut hunks) = collect_hunks_within(&diff, false, max_lines)? else { return Ok(over_limit(path, false)) };
 
         // If no hunks found, the file might be untracked — read it directly
-        if hunks.is_empty() {
+        if hunks.is_empty() && !self.in_index(path) {
             if let Some(workdir) = self.repo.workdir() {
                 let full_path = workdir.join(path);
                 if let Ok(content) = std::fs::read(&full_path) {
@@ -173,6 +562,9 @@ impl GitRepo {
                         } else {
                             file_lines.len()
                         };
+                        if total + 1 > max_lines {
+                            return Ok(over_limit(path, false));
+                        }
                         let header = format!("@@ -0,0 +1,{} @@ new file\n", total);
                         let mut lines = vec![DiffLine {
                             kind: DiffLineKind::HunkHeader,
@@ -207,38 +599,38 @@ impl GitRepo {
                 }
             }
         }
+        if hunks.is_empty() && self.settings.whitespace != Whitespace::Show {
+            hunks.push(DiffHunk {
+                header: String::new(),
+                lines: vec![DiffLine {
+                    kind: DiffLineKind::Context,
+                    content: "(only whitespace changes, hidden)\n".to_string(),
+                    old_lineno: None,
+                    new_lineno: None,
+                }],
+            });
+        }
 
         Ok(FileDiff {
             path: path.to_string(),
             hunks,
+            collapsed: None,
         })
     }
 
     /// Show full file with diff markers (additions/deletions highlighted).
     /// Uses a diff with maximum context lines so the entire file is visible.
-    pub fn file_contents(&self, path: &str, staged: bool) -> Result<FileDiff> {
+    /// Past `max_lines` lines it stops and comes back as `over_limit`.
+    fn file_contents_within(&self, path: &str, old_path: Option<&str>, staged: bool, max_lines: usize) -> Result<FileDiff> {
         // Try diff with full context first
-        let mut diff_opts = DiffOptions::new();
-        diff_opts.pathspec(path);
+        let mut diff_opts = self.diff_options();
         diff_opts.context_lines(u32::MAX);
-
-        let diff = if staged {
-            let head_tree = self.repo.head()
-                .ok()
-                .and_then(|h| h.peel_to_tree().ok());
-            self.repo.diff_tree_to_index(
-                head_tree.as_ref(),
-                None,
-                Some(&mut diff_opts),
-            )?
-        } else {
-            self.repo.diff_index_to_workdir(None, Some(&mut diff_opts))?
-        };
+        let diff = self.path_diff(path, old_path, staged, &mut diff_opts)?;
 
         let mut hunks = Vec::new();
         let mut current_lines: Vec<DiffLine> = Vec::new();
 
-        diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+        let printed = diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
             match line.origin() {
                 'H' | 'F' => {}
                 _ => {
@@ -255,10 +647,18 @@ impl GitRepo {
                         old_lineno: line.old_lineno(),
                         new_lineno: line.new_lineno(),
                     });
+                    // One more line goes to the header
+                    if current_lines.len() + 1 > max_lines {
+                        return false;
+                    }
                 }
             }
             true
-        })?;
+        });
+        match printed {
+            Err(e) if e.code() == git2::ErrorCode::User => return Ok(over_limit(path, true)),
+            result => result?,
+        }
 
         if !current_lines.is_empty() {
             let (adds, dels) = current_lines.iter().fold((0, 0), |(a, d), l| match l.kind {
@@ -294,6 +694,9 @@ impl GitRepo {
                         } else {
                             file_lines.len()
                         };
+                        if total + 1 > max_lines {
+                            return Ok(over_limit(path, true));
+                        }
                         let header = format!("@@ file: {} ({} lines) @@\n", path, total);
                         let mut lines = vec![DiffLine {
                             kind: DiffLineKind::HunkHeader,
@@ -321,6 +724,405 @@ impl GitRepo {
         Ok(FileDiff {
             path: path.to_string(),
             hunks,
+            collapsed: None,
+        })
+    }
+
+    /// Blame the version of `path` a diff shows: the index when `staged`,
+    /// the worktree otherwise. Index `i` holds line `i + 1`; lines changed
+    /// since HEAD are reported as uncommitted.
+    pub fn blame_file(&self, path: &str, staged: bool) -> Result<Vec<BlameLine>> {
+        let base = self.repo.blame_file(std::path::Path::new(path), None)
+            .context("Failed to blame file (is it tracked?)")?;
+        let buffered;
+        let (_, shown) = self.file_sides(path, None, staged);
+        let blame = match shown.map(|side| self.side_bytes(&side, usize::MAX)) {
+            Some(buf) => {
+                buffered = base.blame_buffer(&buf)?;
+                &buffered
+            }
+            None => &base,
+        };
+
+        let mut commits: std::collections::HashMap<git2::Oid, (i64, String)> =
+            std::collections::HashMap::new();
+        let mut lines = Vec::new();
+        for hunk in blame.iter() {
+            let oid = hunk.final_commit_id();
+            let line = if oid.is_zero() {
+                BlameLine::uncommitted()
+            } else {
+                let (time, summary) = commits.entry(oid).or_insert_with(|| {
+                    self.repo.find_commit(oid)
+                        .map(|c| (c.time().seconds(), c.summary().unwrap_or("").to_string()))
+                        .unwrap_or_default()
+                }).clone();
+                BlameLine {
+                    commit: Some(oid.to_string()),
+                    author: hunk.final_signature().name().unwrap_or("?").to_string(),
+                    time,
+                    summary,
+                }
+            };
+            let start = hunk.final_start_line().saturating_sub(1);
+            if lines.len() < start {
+                lines.resize(start, BlameLine::uncommitted());
+            }
+            for _ in 0..hunk.lines_in_hunk() {
+                lines.push(line.clone());
+            }
+        }
+        Ok(lines)
+    }
+
+    /// A patch file parsed for preview, each file under its own header.
+    pub fn patch_preview(&self, bytes: &[u8], name: &str) -> Result<FileDiff> {
+        let diff = git2::Diff::from_buffer(bytes).context("Not a patch file")?;
+        Ok(FileDiff {
+            path: format!("patch {}", name),
+            hunks: collect_hunks(&diff, true)?,
+            collapsed: None,
+        })
+    }
+
+    /// Diff a commit against its first parent, all files in one `FileDiff`.
+    pub fn commit_diff(&self, sha: &str) -> Result<FileDiff> {
+        let commit = self.repo.find_commit(git2::Oid::from_str(sha)?)?;
+        let tree = commit.tree()?;
+        let parent_tree = commit.parent(0).ok().and_then(|p| p.tree().ok());
+        let diff = self.repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut self.diff_options()))?;
+
+        let short = &sha[..sha.len().min(7)];
+        let header = format!(
+            "@@ commit {} {} <{}> @@ {}\n",
+            short,
+            commit.author().name().unwrap_or("?"),
+            commit.author().email().unwrap_or(""),
+            commit.summary().unwrap_or(""),
+        );
+        let mut hunks = vec![DiffHunk {
+            header: header.clone(),
+            lines: vec![DiffLine {
+                kind: DiffLineKind::HunkHeader,
+                content: header,
+                old_lineno: None,
+                new_lineno: None,
+            }],
+        }];
+        hunks.extend(collect_hunks(&diff, true)?);
+
+        Ok(FileDiff {
+            path: format!("commit {}", short),
+            hunks,
+            collapsed: None,
+        })
+    }
+
+    fn submodule_state(&self, name: &str) -> SubmoduleState {
+        self.repo.submodule_status(name, git2::SubmoduleIgnore::None)
+            .map(SubmoduleState::from_git)
+            .unwrap_or_default()
+    }
+
+    /// Every submodule (path and state), including clean ones.
+    pub fn submodules(&self) -> Result<Vec<(String, SubmoduleState)>> {
+        Ok(self.repo.submodules()?
+            .iter()
+            .map(|s| {
+                let state = s.name().map(|n| self.submodule_state(n)).unwrap_or_default();
+                (s.path().to_string_lossy().to_string(), state)
+            })
+            .collect())
+    }
+
+    /// Open the repository checked out at a submodule path.
+    pub fn open_submodule(&self, path: &str) -> Result<GitRepo> {
+        let repo = self.repo.find_submodule(path)?
+            .open()
+            .with_context(|| format!("Submodule {} is not initialized", path))?;
+        Ok(GitRepo {
+            repo,
+            rename_threshold: self.rename_threshold,
+            limits: self.limits,
+            settings: self.settings,
+        })
+    }
+
+    /// Changed files inside a submodule, as paths relative to this repo.
+    pub fn submodule_changed_files(&self, path: &str) -> Result<Vec<String>> {
+        Ok(self.open_submodule(path)?
+            .status_list()?
+            .into_iter()
+            .map(|f| format!("{}/{}", path, f.path))
+            .collect())
+    }
+
+    /// Summary diff of a submodule: commits between the recorded and the
+    /// checked-out commit, then the files changed inside it.
+    pub fn submodule_diff(&self, path: &str) -> Result<FileDiff> {
+        let sub = self.repo.find_submodule(path)?;
+        let recorded = sub.index_id().or(sub.head_id());
+        let checked_out = sub.workdir_id();
+        let short = |oid: Option<git2::Oid>| oid
+            .map(|o| o.to_string()[..7].to_string())
+            .unwrap_or_else(|| "none".to_string());
+
+        let mut hunks = Vec::new();
+        let mut push_hunk = |header: String, lines: Vec<DiffLine>| {
+            let mut all = vec![DiffLine {
+                kind: DiffLineKind::HunkHeader,
+                content: header.clone(),
+                old_lineno: None,
+                new_lineno: None,
+            }];
+            all.extend(lines);
+            hunks.push(DiffHunk { header, lines: all });
+        };
+        let line = |kind: DiffLineKind, content: String| DiffLine {
+            kind,
+            content,
+            old_lineno: None,
+            new_lineno: None,
+        };
+
+        let Ok(sub_repo) = self.open_submodule(path) else {
+            push_hunk(
+                format!("@@ submodule {} @@\n", path),
+                vec![line(DiffLineKind::Context, "(not initialized, press U to update)".to_string())],
+            );
+            return Ok(FileDiff { path: path.to_string(), hunks, collapsed: None });
+        };
+
+        let mut commits = Vec::new();
+        if let (Some(old), Some(new)) = (recorded, checked_out) {
+            // Commits only on the checked-out side are new, the rest were rewound
+            for (from, to, kind) in [(new, old, DiffLineKind::Addition), (old, new, DiffLineKind::Deletion)] {
+                let mut walk = sub_repo.repo.revwalk()?;
+                walk.push(from)?;
+                walk.hide(to)?;
+                for oid in walk.flatten() {
+                    let summary = sub_repo.repo.find_commit(oid)
+                        .map(|c| c.summary().unwrap_or("").to_string())
+                        .unwrap_or_default();
+                    commits.push(line(kind.clone(), format!("{} {}", &oid.to_string()[..7], summary)));
+                }
+            }
+        }
+        push_hunk(
+            format!("@@ submodule {} {}..{} @@\n", path, short(recorded), short(checked_out)),
+            commits,
+        );
+
+        let changed = sub_repo.status_list()?;
+        if !changed.is_empty() {
+            push_hunk(
+                "@@ changed files @@\n".to_string(),
+                changed.iter()
+                    .map(|f| line(DiffLineKind::Context, format!("{} {}", f.kind_icon(), f.display_path())))
+                    .collect(),
+            );
+        }
+
+        Ok(FileDiff { path: path.to_string(), hunks, collapsed: None })
+    }
+
+    /// The branch PRs usually target: origin's HEAD, else main or master.
+    pub fn default_branch(&self) -> Option<String> {
+        if let Ok(r) = self.repo.find_reference("refs/remotes/origin/HEAD") {
+            if let Some(target) = r.symbolic_target() {
+                return Some(target.trim_start_matches("refs/remotes/").to_string());
+            }
+        }
+        ["main", "master", "origin/main", "origin/master"].iter()
+            .find(|name| self.repo.revparse_single(name).is_ok())
+            .map(|name| name.to_string())
+    }
+
+    fn commit_of(&self, rev: &str) -> Result<git2::Oid> {
+        let object = self.repo.revparse_single(rev)
+            .with_context(|| format!("Unknown revision {}", rev))?;
+        Ok(object.peel_to_commit()?.id())
+    }
+
+    /// HEAD against its merge-base with the default branch: everything done on this branch.
+    pub fn compare_merge_base(&self) -> Result<CompareSpec> {
+        let main = self.default_branch().context("No main or master branch found")?;
+        let head = self.commit_of("HEAD")?;
+        let base = self.repo.merge_base(head, self.commit_of(&main)?)?;
+        Ok(CompareSpec {
+            base,
+            target: head,
+            label: format!("merge-base({})..HEAD", main),
+        })
+    }
+
+    /// Two revisions; with `merge_base` the base is the merge-base of both (`A...B`).
+    pub fn compare_revs(&self, base: &str, target: &str, merge_base: bool) -> Result<CompareSpec> {
+        let target_oid = self.commit_of(target)?;
+        let mut base_oid = self.commit_of(base)?;
+        if merge_base {
+            base_oid = self.repo.merge_base(base_oid, target_oid)?;
+        }
+        let sep = if merge_base { "..." } else { ".." };
+        Ok(CompareSpec {
+            base: base_oid,
+            target: target_oid,
+            label: format!("{}{}{}", base, sep, target),
+        })
+    }
+
+    /// Stash entries as ("stash@{n}: message", commit), newest first.
+    pub fn stashes(&self) -> Vec<(String, git2::Oid)> {
+        let Ok(reflog) = self.repo.reflog("refs/stash") else { return Vec::new() };
+        reflog.iter()
+            .enumerate()
+            .map(|(i, entry)| (
+                format!("stash@{{{}}}: {}", i, entry.message().unwrap_or("")),
+                entry.id_new(),
+            ))
+            .collect()
+    }
+
+    /// What a stash would change on top of the current HEAD.
+    pub fn compare_stash(&self, stash: git2::Oid, name: &str) -> Result<CompareSpec> {
+        Ok(CompareSpec {
+            base: self.commit_of("HEAD")?,
+            target: stash,
+            label: format!("HEAD..{}", name),
+        })
+    }
+
+    /// What happened between two checkpoints; without `base`, since HEAD.
+    pub fn compare_checkpoints(&self, base: Option<&Checkpoint>, target: &Checkpoint) -> Result<CompareSpec> {
+        Ok(CompareSpec {
+            base: match base {
+                Some(base) => base.commit,
+                None => self.commit_of("HEAD")?,
+            },
+            target: target.commit,
+            label: match base {
+                Some(base) => format!("checkpoint #{}..#{}", base.number, target.number),
+                None => format!("HEAD..checkpoint #{}", target.number),
+            },
+        })
+    }
+
+    /// (old, new) modes of `path` in the diff shown for it, 0 for a missing side.
+    pub fn file_modes(&self, path: &str, old_path: Option<&str>, staged: bool, compare: Option<&CompareSpec>) -> (u32, u32) {
+        let mut opts = DiffOptions::new();
+        let diff = match compare {
+            Some(spec) => {
+                opts.pathspec(path);
+                if let Some(old) = old_path {
+                    opts.pathspec(old);
+                }
+                self.compare_tree_diff(spec, Some(&mut opts))
+            }
+            None => self.path_diff(path, old_path, staged, &mut opts),
+        };
+        let Ok(diff) = diff else { return (0, 0) };
+        diff.deltas()
+            .find(|d| d.new_file().path().or(d.old_file().path()) == Some(std::path::Path::new(path)))
+            .map(|d| (u32::from(d.old_file().mode()), u32::from(d.new_file().mode())))
+            .unwrap_or((0, 0))
+    }
+
+    fn compare_tree_diff(&self, spec: &CompareSpec, opts: Option<&mut DiffOptions>) -> Result<git2::Diff<'_>> {
+        let base = self.repo.find_commit(spec.base)?.tree()?;
+        let target = self.repo.find_commit(spec.target)?.tree()?;
+        let mut diff = self.repo.diff_tree_to_tree(Some(&base), Some(&target), opts)?;
+        let mut find = git2::DiffFindOptions::new();
+        find.renames(true).rename_threshold(self.rename_threshold);
+        diff.find_similar(Some(&mut find))?;
+        Ok(diff)
+    }
+
+    /// Files changed between the two revisions of `spec`, as status entries.
+    pub fn compare_files(&self, spec: &CompareSpec) -> Result<Vec<FileStatus>> {
+        let diff = self.compare_tree_diff(spec, None)?;
+        Ok(diff.deltas()
+            .map(|delta| {
+                let path_of = |f: git2::DiffFile| f.path().map(|p| p.to_string_lossy().to_string());
+                let path = path_of(delta.new_file()).or(path_of(delta.old_file())).unwrap_or_default();
+                let kind = match delta.status() {
+                    git2::Delta::Added | git2::Delta::Untracked => FileStatusKind::New,
+                    git2::Delta::Deleted => FileStatusKind::Deleted,
+                    git2::Delta::Renamed | git2::Delta::Copied => FileStatusKind::Renamed,
+                    git2::Delta::Typechange => FileStatusKind::Typechange,
+                    git2::Delta::Conflicted => FileStatusKind::Conflicted,
+                    _ => FileStatusKind::Modified,
+                };
+                let old_path = path_of(delta.old_file()).filter(|old| kind == FileStatusKind::Renamed && *old != path);
+                FileStatus {
+                    path,
+                    old_path,
+                    submodule: None,
+                    kind: kind.clone(),
+                    stage_state: StageState::Unstaged,
+                    index_status: None,
+                    worktree_status: Some(kind),
+                }
+            })
+            .collect())
+    }
+
+    /// Diff of one file between the two revisions (whole file with
+    /// `full_context`), or a summary under the same limits as `diff_file`.
+    pub fn compare_diff(&self, spec: &CompareSpec, path: &str, old_path: Option<&str>, full_context: bool) -> Result<FileDiff> {
+        let (old, new) = self.compare_sides(spec, path, old_path);
+        let collapsed = |reason| Collapsed { reason, full_context };
+
+        if let Some(reason) = self.collapse_reason(path, old.as_ref(), new.as_ref()) {
+            return Ok(self.summary(path, collapsed(reason), old.as_ref(), new.as_ref(), None));
+        }
+        let diff = self.compare_diff_within(spec, path, old_path, full_context, self.limits.max_lines)?;
+        if diff.collapsed.is_none() {
+            return Ok(diff);
+        }
+        let stats = self.compare_file_diff(spec, path, old_path, false).ok()
+            .and_then(|d| d.stats().ok())
+            .map(|s| (s.insertions(), s.deletions()));
+        Ok(self.summary(path, collapsed(CollapseReason::Large), old.as_ref(), new.as_ref(), stats))
+    }
+
+    /// `load_full_diff` for a file of a comparison.
+    pub fn load_full_compare_diff(&self, spec: &CompareSpec, path: &str, old_path: Option<&str>, collapsed: Collapsed) -> Result<FileDiff> {
+        if collapsed.reason == CollapseReason::Binary {
+            let (old, new) = self.compare_sides(spec, path, old_path);
+            return self.hex_sides(path, old.as_ref(), new.as_ref());
+        }
+        self.compare_diff_within(spec, path, old_path, collapsed.full_context, usize::MAX)
+    }
+
+    /// Blobs of a compared file in the base and target revisions.
+    fn compare_sides(&self, spec: &CompareSpec, path: &str, old_path: Option<&str>) -> (Option<FileSide>, Option<FileSide>) {
+        let blob = |commit: git2::Oid, path: &str| self.repo.find_commit(commit).ok()
+            .and_then(|c| c.tree().ok())
+            .and_then(|t| t.get_path(std::path::Path::new(path)).ok())
+            .map(|e| FileSide::Blob(e.id()));
+        (blob(spec.base, old_path.unwrap_or(path)), blob(spec.target, path))
+    }
+
+    fn compare_file_diff(&self, spec: &CompareSpec, path: &str, old_path: Option<&str>, full_context: bool) -> Result<git2::Diff<'_>> {
+        let mut opts = self.diff_options();
+        opts.pathspec(path);
+        if let Some(old) = old_path {
+            opts.pathspec(old);
+        }
+        if full_context {
+            opts.context_lines(u32::MAX);
+        }
+        self.compare_tree_diff(spec, Some(&mut opts))
+    }
+
+    /// Past `max_lines` lines it stops and comes back as `over_limit`.
+    fn compare_diff_within(&self, spec: &CompareSpec, path: &str, old_path: Option<&str>, full_context: bool, max_lines: usize) -> Result<FileDiff> {
+        let diff = self.compare_file_diff(spec, path, old_path, full_context)?;
+        let Some(hunks) = collect_hunks_within(&diff, false, max_lines)? else { return Ok(over_limit(path, full_context)) };
+        Ok(FileDiff {
+            path: path.to_string(),
+            hunks,
+            collapsed: None,
         })
     }
 
@@ -328,4 +1130,725 @@ impl GitRepo {
         let head = self.repo.head()?;
         Ok(head.shorthand().unwrap_or("HEAD").to_string())
     }
+
+    /// Branch name with upstream, ahead/behind counts (from the last fetch)
+    /// and the tag on HEAD.
+    pub fn branch_info(&self) -> Result<BranchInfo> {
+        let mut info = self.tracking_info()?;
+        info.tag = self.head_tag();
+        Ok(info)
+    }
+
+    fn tracking_info(&self) -> Result<BranchInfo> {
+        let name = self.branch_name()?;
+        let local = match self.repo.find_branch(&name, git2::BranchType::Local) {
+            Ok(b) => b,
+            Err(_) => return Ok(BranchInfo::without_upstream(&name)),
+        };
+        let upstream = match local.upstream() {
+            Ok(u) => u,
+            Err(_) => return Ok(BranchInfo::without_upstream(&name)),
+        };
+        let upstream_name = upstream.name().ok().flatten().map(|s| s.to_string());
+
+        let (ahead, behind) = match (local.get().target(), upstream.get().target()) {
+            (Some(l), Some(u)) => self.repo.graph_ahead_behind(l, u).unwrap_or((0, 0)),
+            _ => (0, 0),
+        };
+
+        Ok(BranchInfo {
+            name,
+            upstream: upstream_name,
+            ahead,
+            behind,
+            tag: None,
+        })
+    }
+
+    /// Newest tag pointing at the HEAD commit. Runs on every status refresh,
+    /// so only tags whose target is HEAD are looked at in any detail.
+    pub fn head_tag(&self) -> Option<String> {
+        let head = self.repo.head().ok()?.target()?;
+        let mut newest: Option<(i64, String)> = None;
+        for reference in self.repo.references_glob("refs/tags/*").ok()?.flatten() {
+            let Some(target) = reference.target() else { continue };
+            // Lightweight tags point at the commit; annotated ones at a tag object
+            let time = if target == head {
+                0
+            } else {
+                let Ok(tag) = self.repo.find_tag(target) else { continue };
+                if tag.target_id() != head {
+                    continue;
+                }
+                tag.tagger().map_or(0, |t| t.when().seconds())
+            };
+            let Some(name) = reference.shorthand() else { continue };
+            if newest.as_ref().is_none_or(|(t, _)| time > *t) {
+                newest = Some((time, name.to_string()));
+            }
+        }
+        newest.map(|(_, name)| name)
+    }
+
+    /// All tags, newest first.
+    pub fn tags(&self) -> Result<Vec<TagInfo>> {
+        let mut tags = Vec::new();
+        for reference in self.repo.references_glob("refs/tags/*")? {
+            let reference = reference?;
+            let Some(name) = reference.shorthand().map(|s| s.to_string()) else { continue };
+            let Ok(commit) = reference.peel_to_commit() else { continue };
+            let info = match reference.peel_to_tag() {
+                Ok(tag) => TagInfo {
+                    name,
+                    annotated: true,
+                    target: commit.id(),
+                    time: tag.tagger().map_or(commit.time().seconds(), |t| t.when().seconds()),
+                    summary: tag.message().and_then(|m| m.lines().next()).unwrap_or("").to_string(),
+                },
+                Err(_) => TagInfo {
+                    name,
+                    annotated: false,
+                    target: commit.id(),
+                    time: commit.time().seconds(),
+                    summary: commit.summary().unwrap_or("").to_string(),
+                },
+            };
+            tags.push(info);
+        }
+        tags.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.name.cmp(&b.name)));
+        Ok(tags)
+    }
+
+    /// Remote to push tags to: the upstream's remote, else origin, else the only one.
+    pub fn push_remote(&self, branch: &BranchInfo) -> Option<String> {
+        let remotes = self.remotes();
+        branch.upstream.as_deref()
+            .and_then(|u| u.split_once('/'))
+            .map(|(remote, _)| remote.to_string())
+            .filter(|r| remotes.contains(r))
+            .or_else(|| remotes.iter().find(|r| *r == "origin").cloned())
+            .or_else(|| remotes.first().cloned())
+    }
+
+    pub fn remotes(&self) -> Vec<String> {
+        self.repo.remotes()
+            .map(|r| r.iter().flatten().map(|s| s.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    fn signature(&self) -> Result<git2::Signature<'static>> {
+        self.repo.signature()
+            .or_else(|_| git2::Signature::now("rataclaude", "rataclaude@localhost"))
+            .context("Failed to create signature")
+    }
+
+    /// Write an undo-log commit and point a fresh ref under `UNDO_REF_PREFIX` at it.
+    fn write_undo(
+        &self,
+        kind: UndoKind,
+        target: &str,
+        tree: git2::Oid,
+        parent: Option<git2::Oid>,
+    ) -> Result<UndoEntry> {
+        let sig = self.signature()?;
+        let tree = self.repo.find_tree(tree)?;
+        let parent = parent.map(|p| self.repo.find_commit(p)).transpose()?;
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let oid = self.repo.commit(
+            None,
+            &sig,
+            &sig,
+            &undo::encode_message(kind, target),
+            &tree,
+            &parents,
+        )?;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let refname = format!("{}{}-{:09}", UNDO_REF_PREFIX, now.as_secs(), now.subsec_nanos());
+        self.repo.reference(&refname, oid, false, "rataclaude: undo snapshot")
+            .context("Failed to write undo ref")?;
+        Ok(UndoEntry {
+            refname,
+            kind,
+            target: target.to_string(),
+            time: now.as_secs() as i64,
+        })
+    }
+
+    /// Save the worktree content of `path` before it is discarded.
+    /// A missing file is recorded as an empty tree, so undo deletes it again.
+    /// Drop the returned entry if the discard then fails.
+    pub fn snapshot_discard(&self, path: &str) -> Result<UndoEntry> {
+        let workdir = self.repo.workdir().context("Repository has no workdir")?;
+        let full_path = workdir.join(path);
+        let mut builder = self.repo.treebuilder(None)?;
+        // Only a missing file snapshots as empty; anything else aborts the discard
+        let bytes = match std::fs::read(&full_path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path)),
+        };
+        if let Some(bytes) = bytes {
+            let executable = std::fs::metadata(&full_path).is_ok_and(|m| file_mode::is_executable(&m));
+            let blob = self.repo.blob(&bytes)?;
+            builder.insert(SNAPSHOT_ENTRY, blob, if executable { 0o100755 } else { 0o100644 })?;
+        }
+        let tree = builder.write()?;
+        let head = self.repo.head().ok().and_then(|h| h.target());
+        self.write_undo(UndoKind::Discard, path, tree, head)
+    }
+
+    /// Keep a commit that a stash drop, branch or tag deletion is about to orphan.
+    /// Drop the returned entry if the deletion then fails.
+    pub fn snapshot_commit(&self, kind: UndoKind, target: &str, commit: git2::Oid) -> Result<UndoEntry> {
+        let empty = self.repo.treebuilder(None)?.write()?;
+        self.write_undo(kind, target, empty, Some(commit))
+    }
+
+    /// Record every changed file before a prompt is sent, so `turn_changes`
+    /// can tell what the agent touched. Files unchanged since `previous`
+    /// are not read again.
+    pub fn snapshot_turn(&self, previous: Option<&TurnSnapshot>) -> Result<TurnSnapshot> {
+        Ok(self.scan_worktree(false, previous)?.snapshot)
+    }
+
+    /// Checkpoint the worktree and snapshot it for the turn in one pass.
+    /// Also returns the files too large to go into the checkpoint.
+    pub fn checkpoint_turn(&self, label: &str, previous: Option<&TurnSnapshot>) -> Result<(TurnSnapshot, Option<Checkpoint>, Vec<String>)> {
+        let scan = self.scan_worktree(true, previous)?;
+        let tree_id = scan.tree.context("Failed to write the worktree")?;
+        Ok((scan.snapshot, self.commit_checkpoint(tree_id, label, false)?, scan.left_out))
+    }
+
+    /// One pass over the changed files: the turn snapshot and, with
+    /// `build_tree`, the worktree as `git add -A` would stage it, built on
+    /// HEAD's tree without touching the index (submodules keep HEAD's
+    /// commit). A tree needs every file stored, up to
+    /// `checkpoint::MAX_FILE_BYTES`; bigger files keep HEAD's version in it
+    /// and are listed in `left_out`. Without a tree only files up to
+    /// `turn::MAX_STORED_BYTES` are stored. Files whose size and mtime match
+    /// `previous` reuse its blob instead of being read.
+    fn scan_worktree(&self, build_tree: bool, previous: Option<&TurnSnapshot>) -> Result<WorktreeScan> {
+        let workdir = self.repo.workdir().context("Repository has no workdir")?;
+        let mut update = git2::build::TreeUpdateBuilder::new();
+        let mut files = std::collections::HashMap::new();
+        let mut left_out = Vec::new();
+        for file in self.status_list()? {
+            let full_path = workdir.join(&file.path);
+            let meta = std::fs::symlink_metadata(&full_path).ok();
+            let too_big = build_tree && meta.as_ref().is_some_and(|m| m.is_file() && m.len() > checkpoint::MAX_FILE_BYTES);
+            let store = build_tree && !too_big;
+            let reusable = |meta: &std::fs::Metadata| previous
+                .and_then(|p| p.files.get(&file.path))
+                .and_then(|f| f.as_ref())
+                .filter(|f| f.matches(meta) && (f.stored || !store))
+                .cloned();
+            let turn_file = |meta: &std::fs::Metadata| match reusable(meta) {
+                Some(f) => Ok(f),
+                None => self.turn_file(&full_path, meta, store),
+            };
+            let entry = match meta {
+                Some(ref m) if m.is_file() => Some(turn_file(m)?),
+                // The turn follows symlinks, like `turn_changes`
+                Some(ref m) if m.file_type().is_symlink() => match std::fs::metadata(&full_path) {
+                    Ok(target) if target.is_file() => Some(self.turn_file(&full_path, &target, false)?),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if too_big {
+                left_out.push(file.path.clone());
+            } else if build_tree && file.submodule.is_none() {
+                if let Some(ref old) = file.old_path {
+                    update.remove(old.as_str());
+                }
+                match (&meta, &entry) {
+                    (Some(m), _) if m.file_type().is_symlink() => {
+                        let target = std::fs::read_link(&full_path)?;
+                        let blob = self.repo.blob(target.to_string_lossy().as_bytes())?;
+                        update.upsert(file.path.as_str(), blob, git2::FileMode::Link);
+                    }
+                    (Some(m), Some(entry)) => {
+                        let mode = if file_mode::is_executable(m) {
+                            git2::FileMode::BlobExecutable
+                        } else {
+                            git2::FileMode::Blob
+                        };
+                        update.upsert(file.path.as_str(), entry.id, mode);
+                    }
+                    _ => {
+                        update.remove(file.path.as_str());
+                    }
+                }
+            }
+            files.insert(file.path, entry);
+        }
+        let snapshot = TurnSnapshot { workdir: workdir.to_string_lossy().to_string(), files };
+        if !build_tree {
+            return Ok(WorktreeScan { tree: None, snapshot, left_out });
+        }
+        let base = match self.repo.head().ok().and_then(|h| h.peel_to_tree().ok()) {
+            Some(tree) => tree,
+            None => self.repo.find_tree(self.repo.treebuilder(None)?.write()?)?,
+        };
+        let tree = Some(update.create_updated(&self.repo, &base)?);
+        Ok(WorktreeScan { tree, snapshot, left_out })
+    }
+
+    /// Hash a file for a turn snapshot, storing it when `store` is set or it
+    /// is small enough.
+    fn turn_file(&self, full_path: &std::path::Path, meta: &std::fs::Metadata, store: bool) -> Result<TurnFile> {
+        let stored = store || meta.len() <= turn::MAX_STORED_BYTES;
+        let id = if stored {
+            self.repo.blob(&std::fs::read(full_path)?)?
+        } else {
+            git2::Oid::hash_file(git2::ObjectType::Blob, full_path)?
+        };
+        Ok(TurnFile { id, size: meta.len(), mtime: meta.modified().ok(), stored })
+    }
+
+    /// Paths among `files` whose worktree content differs from `snapshot`.
+    pub fn turn_changes(&self, snapshot: &TurnSnapshot, files: &[FileStatus]) -> std::collections::HashSet<String> {
+        let Some(workdir) = self.repo.workdir() else { return Default::default() };
+        files.iter()
+            .filter(|f| {
+                let Some(before) = snapshot.files.get(&f.path) else { return true };
+                let full_path = workdir.join(&f.path);
+                let meta = std::fs::metadata(&full_path).ok().filter(|m| m.is_file());
+                match (before, meta) {
+                    (None, None) => false,
+                    (Some(before), Some(meta)) if before.matches(&meta) => false,
+                    (Some(before), Some(_)) => {
+                        git2::Oid::hash_file(git2::ObjectType::Blob, &full_path).ok() != Some(before.id)
+                    }
+                    _ => true,
+                }
+            })
+            .map(|f| f.path.clone())
+            .collect()
+    }
+
+    /// How `path` changed since `snapshot`: the new-side ranges of its hunks.
+    pub fn turn_change(&self, snapshot: &TurnSnapshot, path: &str) -> Result<TurnChange> {
+        let workdir = self.repo.workdir().context("Repository has no workdir")?;
+        let Ok(new) = std::fs::read(workdir.join(path)) else { return Ok(TurnChange::Deleted) };
+        let old = match snapshot.files.get(path) {
+            Some(Some(file)) => Some(file.id),
+            Some(None) => None,
+            // Clean when the prompt was sent
+            None => self.repo.index()?.get_path(std::path::Path::new(path), 0).map(|e| e.id),
+        };
+        // Files too big to store when the prompt was sent count as a whole
+        let Some(old) = old.and_then(|id| self.repo.find_blob(id).ok()) else { return Ok(TurnChange::Whole) };
+        let mut opts = DiffOptions::new();
+        opts.context_lines(0);
+        let patch = git2::Patch::from_blob_and_buffer(
+            &old,
+            Some(std::path::Path::new(path)),
+            &new,
+            Some(std::path::Path::new(path)),
+            Some(&mut opts),
+        )?;
+        if old.is_binary() || patch.delta().flags().is_binary() {
+            return Ok(TurnChange::Whole);
+        }
+        let mut ranges = Vec::new();
+        for h in 0..patch.num_hunks() {
+            let (hunk, _) = patch.hunk(h)?;
+            // A pure deletion has no new lines; point at where it was
+            let start = hunk.new_start().max(1);
+            ranges.push((start, start + hunk.new_lines().saturating_sub(1)));
+        }
+        Ok(TurnChange::Ranges(turn::merge_ranges(ranges)))
+    }
+
+    /// Checkpoint the worktree, labelled with `label` (see `checkpoint::label`).
+    /// Unless `force`, returns None when nothing changed since the latest one.
+    pub fn create_checkpoint(&self, label: &str, force: bool) -> Result<(Option<Checkpoint>, Vec<String>)> {
+        let scan = self.scan_worktree(true, None)?;
+        let tree_id = scan.tree.context("Failed to write the worktree")?;
+        Ok((self.commit_checkpoint(tree_id, label, force)?, scan.left_out))
+    }
+
+    fn commit_checkpoint(&self, tree_id: git2::Oid, label: &str, force: bool) -> Result<Option<Checkpoint>> {
+        let existing = self.checkpoints()?;
+        if let Some(latest) = existing.first() {
+            let same = self.repo.find_commit(latest.commit)?.tree_id() == tree_id;
+            if same && !force {
+                return Ok(None);
+            }
+        }
+        let label = checkpoint::label(label);
+        let number = existing.first().map_or(1, |c| c.number + 1);
+        let sig = self.signature()?;
+        let tree = self.repo.find_tree(tree_id)?;
+        let commit = self.repo.commit(None, &sig, &sig, &label, &tree, &[])?;
+        let refname = format!("{}{}", CHECKPOINT_REF_PREFIX, number);
+        self.repo.reference(&refname, commit, false, "rataclaude: checkpoint")
+            .context("Failed to write checkpoint ref")?;
+        for old in existing.iter().skip(MAX_CHECKPOINTS - 1) {
+            self.drop_checkpoint(old)?;
+        }
+        Ok(Some(Checkpoint { refname, number, commit, label, time: sig.when().seconds() }))
+    }
+
+    /// All checkpoints, newest first.
+    pub fn checkpoints(&self) -> Result<Vec<Checkpoint>> {
+        let mut checkpoints = Vec::new();
+        for reference in self.repo.references_glob(&format!("{}*", CHECKPOINT_REF_PREFIX))? {
+            let reference = reference?;
+            let Some(refname) = reference.name().map(|s| s.to_string()) else { continue };
+            let Ok(number) = refname[CHECKPOINT_REF_PREFIX.len()..].parse() else { continue };
+            let commit = reference.peel_to_commit()?;
+            checkpoints.push(Checkpoint {
+                refname,
+                number,
+                commit: commit.id(),
+                label: commit.summary().unwrap_or("").to_string(),
+                time: commit.time().seconds(),
+            });
+        }
+        checkpoints.sort_by_key(|c| std::cmp::Reverse(c.number));
+        Ok(checkpoints)
+    }
+
+    pub fn drop_checkpoint(&self, checkpoint: &Checkpoint) -> Result<()> {
+        self.repo.find_reference(&checkpoint.refname)?.delete()?;
+        Ok(())
+    }
+
+    /// Files that differ between `checkpoint` and the worktree.
+    pub fn checkpoint_files(&self, checkpoint: &Checkpoint) -> Result<Vec<CheckpointFile>> {
+        let tree = self.repo.find_commit(checkpoint.commit)?.tree()?;
+        let mut opts = DiffOptions::new();
+        opts.include_untracked(true).recurse_untracked_dirs(true);
+        let diff = self.repo.diff_tree_to_workdir(Some(&tree), Some(&mut opts))?;
+        Ok(diff.deltas()
+            .filter(|d| d.new_file().mode() != git2::FileMode::Commit && d.old_file().mode() != git2::FileMode::Commit)
+            .filter_map(|d| {
+                let change = match d.status() {
+                    git2::Delta::Added | git2::Delta::Untracked => 'A',
+                    git2::Delta::Deleted => 'D',
+                    git2::Delta::Modified | git2::Delta::Typechange => 'M',
+                    _ => return None,
+                };
+                let path = d.new_file().path().or(d.old_file().path())?;
+                Some(CheckpointFile { path: path.to_string_lossy().to_string(), change })
+            })
+            .collect())
+    }
+
+    /// Put the worktree (or just `paths`) back the way it was at `checkpoint`.
+    /// The current state is checkpointed first, so a restore can be undone;
+    /// the index and HEAD are left alone. Returns that checkpoint and the
+    /// number of files written or removed.
+    pub fn restore_checkpoint(&self, checkpoint: &Checkpoint, paths: Option<&[String]>) -> Result<(Checkpoint, usize)> {
+        let workdir = self.repo.workdir().context("Repository has no workdir")?;
+        let before = self.create_checkpoint(&format!("before restoring #{}", checkpoint.number), true)?.0
+            .context("Failed to checkpoint the worktree")?;
+        let current = self.repo.find_commit(before.commit)?.tree()?;
+        let target = self.repo.find_commit(checkpoint.commit)?.tree()?;
+        let mut opts = DiffOptions::new();
+        if let Some(paths) = paths {
+            opts.disable_pathspec_match(true);
+            for path in paths {
+                opts.pathspec(path);
+            }
+        }
+        let diff = self.repo.diff_tree_to_tree(Some(&current), Some(&target), Some(&mut opts))?;
+        let mut count = 0;
+        for delta in diff.deltas() {
+            let (old, new) = (delta.old_file(), delta.new_file());
+            if old.mode() == git2::FileMode::Commit || new.mode() == git2::FileMode::Commit {
+                continue;
+            }
+            let Some(path) = new.path().or(old.path()) else { continue };
+            let full_path = workdir.join(path);
+            match delta.status() {
+                git2::Delta::Deleted => {
+                    remove_path(&full_path)?;
+                    remove_empty_parents(&full_path, workdir);
+                }
+                git2::Delta::Added | git2::Delta::Modified | git2::Delta::Typechange => {
+                    let blob = self.repo.find_blob(new.id())?;
+                    write_blob(&full_path, blob.content(), new.mode())
+                        .with_context(|| format!("Failed to restore {}", path.display()))?;
+                }
+                _ => continue,
+            }
+            count += 1;
+        }
+        Ok((before, count))
+    }
+
+    /// Top stash entry: (commit id, message).
+    pub fn stash_top(&self) -> Option<(git2::Oid, String)> {
+        let commit = self.repo.find_reference("refs/stash").ok()?.peel_to_commit().ok()?;
+        let msg = commit.summary().unwrap_or("stash").to_string();
+        Some((commit.id(), msg))
+    }
+
+    pub fn tag_target(&self, name: &str) -> Result<git2::Oid> {
+        let reference = self.repo.find_reference(&format!("refs/tags/{}", name))?;
+        Ok(reference.peel_to_commit()?.id())
+    }
+
+    pub fn branch_tip(&self, name: &str) -> Result<git2::Oid> {
+        let branch = self.repo.find_branch(name, git2::BranchType::Local)?;
+        branch.get().target().context("Branch has no target")
+    }
+
+    /// All undo entries, newest first.
+    pub fn undo_entries(&self) -> Result<Vec<UndoEntry>> {
+        let mut entries = Vec::new();
+        for reference in self.repo.references_glob(&format!("{}*", UNDO_REF_PREFIX))? {
+            let reference = reference?;
+            let Some(refname) = reference.name().map(|s| s.to_string()) else { continue };
+            let commit = reference.peel_to_commit()?;
+            if let Some((kind, target)) = undo::decode_message(commit.message().unwrap_or("")) {
+                entries.push(UndoEntry {
+                    refname,
+                    kind,
+                    target,
+                    time: commit.time().seconds(),
+                });
+            }
+        }
+        entries.sort_by(|a, b| b.refname.cmp(&a.refname));
+        Ok(entries)
+    }
+
+    /// The commit an undo entry keeps alive (stash or branch tip).
+    pub fn undo_parent(&self, entry: &UndoEntry) -> Result<git2::Oid> {
+        let commit = self.repo.find_reference(&entry.refname)?.peel_to_commit()?;
+        Ok(commit.parent_id(0)?)
+    }
+
+    /// Write a discarded file back into the worktree (or remove it if it
+    /// did not exist when discarded).
+    pub fn restore_discard(&self, entry: &UndoEntry) -> Result<()> {
+        let workdir = self.repo.workdir().context("Repository has no workdir")?;
+        let full_path = workdir.join(&entry.target);
+        let tree = self.repo.find_reference(&entry.refname)?.peel_to_tree()?;
+        match tree.get_name(SNAPSHOT_ENTRY) {
+            Some(item) => {
+                let blob = self.repo.find_blob(item.id())?;
+                if let Some(parent) = full_path.parent() {
+                    std::fs::create_dir_all(parent).context("create parent directories")?;
+                }
+                std::fs::write(&full_path, blob.content()).context("write restored file")?;
+                file_mode::set_executable(&full_path, item.filemode() == 0o100755)?;
+            }
+            None => {
+                if full_path.exists() {
+                    std::fs::remove_file(&full_path).context("remove restored file")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn drop_undo(&self, entry: &UndoEntry) -> Result<()> {
+        self.repo.find_reference(&entry.refname)?.delete()?;
+        Ok(())
+    }
+}
+
+fn remove_path(path: &std::path::Path) -> Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => std::fs::remove_dir_all(path)?,
+        Ok(_) => std::fs::remove_file(path)?,
+        Err(_) => {}
+    }
+    Ok(())
+}
+
+/// Remove directories left empty by a restore, up to the workdir.
+fn remove_empty_parents(path: &std::path::Path, workdir: &std::path::Path) {
+    let mut dir = path.parent();
+    while let Some(d) = dir.filter(|d| d.starts_with(workdir) && *d != workdir) {
+        if std::fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+}
+
+/// Write a checkpointed file back, as a symlink or with its executable bit.
+/// An existing file keeps its other permissions; a new one gets the umask's.
+fn write_blob(path: &std::path::Path, content: &[u8], mode: git2::FileMode) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink() || m.is_dir()) {
+        remove_path(path)?;
+    }
+    if mode == git2::FileMode::Link {
+        remove_path(path)?;
+        file_mode::symlink(&String::from_utf8_lossy(content), path)?;
+        return Ok(());
+    }
+    std::fs::write(path, content)?;
+    file_mode::set_executable(path, mode == git2::FileMode::BlobExecutable)?;
+    Ok(())
+}
+
+/// `xxd`-style dump: offset, 16 hex bytes, printable ASCII.
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk.iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        out.push_str(&format!("{:08x}  {:<47}  |{}|\n", i * 16, hex.join(" "), ascii));
+    }
+    out
+}
+
+/// Hunks of an in-memory patch (no file headers).
+fn patch_hunks(patch: &git2::Patch) -> Result<Vec<DiffHunk>> {
+    let mut hunks = Vec::new();
+    for h in 0..patch.num_hunks() {
+        let (hunk, count) = patch.hunk(h)?;
+        let header = String::from_utf8_lossy(hunk.header()).to_string();
+        let mut lines = vec![DiffLine {
+            kind: DiffLineKind::HunkHeader,
+            content: header.clone(),
+            old_lineno: None,
+            new_lineno: None,
+        }];
+        for l in 0..count {
+            let line = patch.line_in_hunk(h, l)?;
+            let kind = match line.origin() {
+                '+' | '>' => DiffLineKind::Addition,
+                '-' | '<' => DiffLineKind::Deletion,
+                _ => DiffLineKind::Context,
+            };
+            lines.push(DiffLine {
+                kind,
+                content: String::from_utf8_lossy(line.content()).to_string(),
+                old_lineno: line.old_lineno(),
+                new_lineno: line.new_lineno(),
+            });
+        }
+        hunks.push(DiffHunk { header, lines });
+    }
+    Ok(hunks)
+}
+
+/// Stand-in for a diff that ran over the line limit, which `limited` and
+/// `compare_diff` replace with a summary.
+fn over_limit(path: &str, full_context: bool) -> FileDiff {
+    FileDiff {
+        path: path.to_string(),
+        hunks: Vec::new(),
+        collapsed: Some(Collapsed { reason: CollapseReason::Large, full_context }),
+    }
+}
+
+fn split_lines(bytes: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(bytes).lines().map(str::to_string).collect()
+}
+
+/// Collect a git2 diff into hunks. With `file_headers`, a header line
+/// naming the file is emitted whenever a new file starts (multi-file diffs).
+fn collect_hunks(diff: &git2::Diff, file_headers: bool) -> Result<Vec<DiffHunk>> {
+    Ok(collect_hunks_within(diff, file_headers, usize::MAX)?.unwrap_or_default())
+}
+
+/// Like `collect_hunks`, but stops with `None` once the hunks run over
+/// `max_lines` lines (hunk headers included).
+fn collect_hunks_within(diff: &git2::Diff, file_headers: bool, max_lines: usize) -> Result<Option<Vec<DiffHunk>>> {
+    let mut collected = 0;
+    let mut hunks = Vec::new();
+    let mut current_lines: Vec<DiffLine> = Vec::new();
+    let mut current_header = String::new();
+    let mut current_file: Option<std::path::PathBuf> = None;
+
+    let printed = diff.print(git2::DiffFormat::Patch, |delta, hunk, line| {
+        match line.origin() {
+            'H' | 'F' => {}
+            _ => {
+                let file = delta.new_file().path().or(delta.old_file().path()).map(|p| p.to_path_buf());
+                if file_headers && file != current_file {
+                    if !current_lines.is_empty() {
+                        hunks.push(DiffHunk {
+                            header: current_header.clone(),
+                            lines: std::mem::take(&mut current_lines),
+                        });
+                    }
+                    let header = format!(
+                        "@@ file: {} @@\n",
+                        file.as_deref().map(|p| p.display().to_string()).unwrap_or_default(),
+                    );
+                    hunks.push(DiffHunk {
+                        header: header.clone(),
+                        lines: vec![DiffLine {
+                            kind: DiffLineKind::HunkHeader,
+                            content: header,
+                            old_lineno: None,
+                            new_lineno: None,
+                        }],
+                    });
+                    collected += 1;
+                    current_header.clear();
+                    current_file = file;
+                }
+
+                if let Some(hunk) = hunk {
+                    let header = String::from_utf8_lossy(hunk.header()).to_string();
+                    if header != current_header && !current_header.is_empty() {
+                        hunks.push(DiffHunk {
+                            header: current_header.clone(),
+                            lines: std::mem::take(&mut current_lines),
+                        });
+                    }
+                    if header != current_header {
+                        collected += 1;
+                        current_header = header.clone();
+                        current_lines.push(DiffLine {
+                            kind: DiffLineKind::HunkHeader,
+                            content: header,
+                            old_lineno: None,
+                            new_lineno: None,
+                        });
+                    }
+                }
+
+                let content = String::from_utf8_lossy(line.content()).to_string();
+                let kind = match line.origin() {
+                    '+' | '>' => DiffLineKind::Addition,
+                    '-' | '<' => DiffLineKind::Deletion,
+                    _ => DiffLineKind::Context,
+                };
+
+                current_lines.push(DiffLine {
+                    kind,
+                    content,
+                    old_lineno: line.old_lineno(),
+                    new_lineno: line.new_lineno(),
+                });
+                collected += 1;
+                if collected > max_lines {
+                    return false;
+                }
+            }
+        }
+        true
+    });
+    match printed {
+        Err(e) if e.code() == git2::ErrorCode::User => return Ok(None),
+        result => result?,
+    }
+
+    if !current_lines.is_empty() {
+        hunks.push(DiffHunk {
+            header: current_header,
+            lines: current_lines,
+        });
+    }
+
+    Ok(Some(hunks))
 }
@@ -19,6 +19,10 @@ pub enum StageState {
 #[derive(Debug, Clone)]
 pub struct FileStatus {
     pub path: String,
+    /// Original path when the file was renamed
+    pub old_path: Option<String>,
+    /// Set when the entry is a submodule
+    pub submodule: Option<SubmoduleState>,
     pub kind: FileStatusKind,
     pub stage_state: StageState,
     pub index_status: Option<FileStatusKind>,
@@ -26,6 +30,23 @@ pub struct FileStatus {
 }
 
 impl FileStatus {
+    /// "old → new" for renames, otherwise the path.
+    pub fn display_path(&self) -> String {
+        match self.old_path {
+            Some(ref old) => format!("{} \u{2192} {}", old, self.path),
+            None => self.path.clone(),
+        }
+    }
+
+    /// Every path git needs to touch to stage/unstage this entry.
+    pub fn paths(&self) -> Vec<&str> {
+        let mut paths = vec![self.path.as_str()];
+        if let Some(ref old) = self.old_path {
+            paths.push(old);
+        }
+        paths
+    }
+
     pub fn icon(&self) -> &str {
         match self.stage_state {
             StageState::Staged => "✓",
@@ -35,6 +56,9 @@ impl FileStatus {
     }
 
     pub fn kind_icon(&self) -> &str {
+        if self.submodule.is_some() {
+            return "S";
+        }
         let kind = self.worktree_status.as_ref()
             .or(self.index_status.as_ref())
             .unwrap_or(&self.kind);
@@ -51,6 +75,9 @@ impl FileStatus {
 
     pub fn kind_color(&self) -> ratatui::style::Color {
         use ratatui::style::Color;
+        if self.submodule.is_some() {
+            return Color::Blue;
+        }
         let kind = self.worktree_status.as_ref()
             .or(self.index_status.as_ref())
             .unwrap_or(&self.kind);
@@ -63,3 +90,65 @@ impl FileStatus {
         }
     }
 }
+
+/// Submodule state relative to the commit recorded in the superproject.
+#[derive(Debug, Clone, Default)]
+pub struct SubmoduleState {
+    pub initialized: bool,
+    /// Checked-out commit differs from the recorded one
+    pub new_commits: bool,
+    /// Staged or unstaged changes inside the submodule
+    pub dirty: bool,
+    pub untracked: bool,
+}
+
+impl SubmoduleState {
+    pub fn from_git(status: git2::SubmoduleStatus) -> Self {
+        use git2::SubmoduleStatus as S;
+        Self {
+            initialized: !status.contains(S::WD_UNINITIALIZED),
+            new_commits: status.contains(S::WD_MODIFIED),
+            dirty: status.intersects(S::WD_INDEX_MODIFIED | S::WD_WD_MODIFIED),
+            untracked: status.contains(S::WD_UNTRACKED),
+        }
+    }
+
+    /// Short description, e.g. "new commits, dirty". Empty when clean.
+    pub fn label(&self) -> String {
+        if !self.initialized {
+            return "not initialized".to_string();
+        }
+        let mut parts = Vec::new();
+        if self.new_commits {
+            parts.push("new commits");
+        }
+        if self.dirty {
+            parts.push("dirty");
+        }
+        if self.untracked {
+            parts.push("untracked");
+        }
+        parts.join(", ")
+    }
+}
+
+/// Current branch plus its upstream tracking state.
+#[derive(Debug, Clone, Default)]
+pub struct BranchInfo {
+    pub name: String,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Tag pointing at HEAD, if any
+    pub tag: Option<String>,
+}
+
+impl BranchInfo {
+    /// A branch (or detached HEAD, or placeholder) with no upstream to track.
+    pub fn without_upstream(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+}
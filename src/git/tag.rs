@@ -0,0 +1,30 @@
+//! Tags for the tag picker.
+
+use crate::ui::file_panel::format_age;
+
+#[derive(Debug, Clone)]
+pub struct TagInfo {
+    pub name: String,
+    /// Annotated tags carry their own tagger, date and message
+    pub annotated: bool,
+    /// Commit the tag points at (peeled)
+    pub target: git2::Oid,
+    /// Tagger date for annotated tags, commit date otherwise (unix seconds)
+    pub time: i64,
+    /// First line of the annotation, or the commit summary
+    pub summary: String,
+}
+
+impl TagInfo {
+    /// Picker line, e.g. "v1.2.0  a1b2c3d  3d  ◆ Release 1.2".
+    pub fn label(&self, now: i64) -> String {
+        format!(
+            "{}  {:.7}  {}  {}{}",
+            self.name,
+            self.target.to_string(),
+            format_age(now.saturating_sub(self.time).max(0) as u64),
+            if self.annotated { "\u{25c6} " } else { "" },
+            self.summary,
+        )
+    }
+}
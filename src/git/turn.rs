@@ -0,0 +1,91 @@
+//! Files changed since the last prompt was sent: every changed file is
+//! hashed when a prompt goes out (and small ones written as blobs, for
+//! their line ranges), so the agent's turn can be told apart from older
+//! uncommitted changes.
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Files up to this size are written to the object database so the ranges
+/// the agent changed can be found; bigger ones are only hashed.
+pub const MAX_STORED_BYTES: u64 = 512 * 1024;
+
+/// Worktree state when the last prompt was sent.
+#[derive(Debug, Clone)]
+pub struct TurnSnapshot {
+    /// Repository the paths are relative to
+    pub workdir: String,
+    /// Each changed file (None: not a file). Files not listed were clean,
+    /// i.e. matched the index.
+    pub files: HashMap<String, Option<TurnFile>>,
+}
+
+/// A changed file's content when the prompt was sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnFile {
+    /// Blob id of the content, in the object database only when `stored`
+    pub id: git2::Oid,
+    pub size: u64,
+    pub mtime: Option<SystemTime>,
+    pub stored: bool,
+}
+
+impl TurnFile {
+    /// Same size and mtime as `meta`, so the content is taken as unchanged.
+    pub fn matches(&self, meta: &std::fs::Metadata) -> bool {
+        self.size == meta.len() && self.mtime.is_some() && self.mtime == meta.modified().ok()
+    }
+}
+
+/// What the turn did to one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TurnChange {
+    /// New-side line ranges that changed
+    Ranges(Vec<(u32, u32)>),
+    /// New or binary file
+    Whole,
+    Deleted,
+}
+
+/// Hunks closer than this many lines are referenced as one range.
+const MERGE_GAP: u32 = 3;
+
+/// Merge sorted ranges that overlap or nearly touch.
+pub fn merge_ranges(ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    let mut merged: Vec<(u32, u32)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + MERGE_GAP + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Single-line request injected into the agent PTY to review its last turn,
+/// with an `@path:A-B` reference per changed range.
+pub fn review_request(changes: &[(String, TurnChange)]) -> String {
+    let mut refs = Vec::new();
+    let mut deleted = Vec::new();
+    for (path, change) in changes {
+        match change {
+            TurnChange::Ranges(ranges) if !ranges.is_empty() => {
+                refs.extend(ranges.iter().map(|&(start, end)| match start == end {
+                    true => format!("@{}:{}", path, start),
+                    false => format!("@{}:{}-{}", path, start, end),
+                }));
+            }
+            TurnChange::Deleted => deleted.push(path.as_str()),
+            _ => refs.push(format!("@{}", path)),
+        }
+    }
+    let mut request = format!(
+        "Review the changes you just made for bugs, edge cases and anything left unfinished: {}",
+        refs.join(" "),
+    );
+    if !deleted.is_empty() {
+        request.push_str(&format!(" (deleted: {})", deleted.join(", ")));
+    }
+    request.push('\n');
+    request
+}
@@ -0,0 +1,70 @@
+//! Undo log for destructive git actions.
+//!
+//! Each entry is a commit under `refs/rataclaude/undo/` whose message records
+//! what was destroyed. Discards keep the old file content in the commit tree;
+//! stash drops, branch and tag deletions keep the lost commit as the parent,
+//! so nothing is garbage-collected while the entry exists. A deleted tag's
+//! annotation is not kept; it comes back as a lightweight tag.
+
+pub const UNDO_REF_PREFIX: &str = "refs/rataclaude/undo/";
+
+/// Name of the single tree entry holding a discarded file's content.
+pub const SNAPSHOT_ENTRY: &str = "snapshot";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoKind {
+    Discard,
+    StashDrop,
+    BranchDelete,
+    TagDelete,
+}
+
+impl UndoKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UndoKind::Discard => "discard",
+            UndoKind::StashDrop => "stash-drop",
+            UndoKind::BranchDelete => "branch-delete",
+            UndoKind::TagDelete => "tag-delete",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "discard" => Some(UndoKind::Discard),
+            "stash-drop" => Some(UndoKind::StashDrop),
+            "branch-delete" => Some(UndoKind::BranchDelete),
+            "tag-delete" => Some(UndoKind::TagDelete),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    pub refname: String,
+    pub kind: UndoKind,
+    /// File path, stash message, branch or tag name
+    pub target: String,
+    /// Unix timestamp of the snapshot
+    pub time: i64,
+}
+
+impl UndoEntry {
+    pub fn label(&self, now: i64) -> String {
+        let age = crate::ui::file_panel::format_age(now.saturating_sub(self.time).max(0) as u64);
+        format!("{:<13} {}  ({} ago)", self.kind.as_str(), self.target, age)
+    }
+}
+
+/// Commit message encoding the entry: kind on the first line, target on the second.
+pub fn encode_message(kind: UndoKind, target: &str) -> String {
+    format!("{}\n{}\n", kind.as_str(), target)
+}
+
+pub fn decode_message(msg: &str) -> Option<(UndoKind, String)> {
+    let mut lines = msg.lines();
+    let kind = UndoKind::parse(lines.next()?.trim())?;
+    let target = lines.next()?.to_string();
+    Some((kind, target))
+}
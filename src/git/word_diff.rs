@@ -0,0 +1,184 @@
+//! Intra-line ("word") diffs: pairs deleted and added lines inside a hunk and
+//! finds the spans that actually changed, so the diff view can emphasise them.
+
+use super::diff::{DiffLineKind, FileDiff};
+
+/// Lines longer than this (in tokens) are not word-diffed; the LCS is quadratic.
+const MAX_TOKENS: usize = 400;
+
+/// Changed char ranges `[start, end)` within one line.
+pub type Ranges = Vec<(usize, usize)>;
+
+/// Granularity of intra-line highlighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum WordDiffMode {
+    Off,
+    Word,
+    Char,
+}
+
+impl WordDiffMode {
+    pub fn next(self) -> Self {
+        match self {
+            WordDiffMode::Off => WordDiffMode::Word,
+            WordDiffMode::Word => WordDiffMode::Char,
+            WordDiffMode::Char => WordDiffMode::Off,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WordDiffMode::Off => "off",
+            WordDiffMode::Word => "word",
+            WordDiffMode::Char => "char",
+        }
+    }
+}
+
+/// Changed ranges for every line of `diff`, in `all_lines` order.
+/// Context lines, headers and unpaired lines get no ranges.
+pub fn emphasis(diff: &FileDiff, mode: WordDiffMode) -> Vec<Ranges> {
+    let lines = diff.all_lines();
+    let mut out = vec![Vec::new(); lines.len()];
+    if mode == WordDiffMode::Off {
+        return out;
+    }
+
+    // A run of deletions directly followed by a run of additions is a change
+    // block; its lines are paired up in order.
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].kind != DiffLineKind::Deletion {
+            i += 1;
+            continue;
+        }
+        let del_start = i;
+        while i < lines.len() && lines[i].kind == DiffLineKind::Deletion {
+            i += 1;
+        }
+        let add_start = i;
+        while i < lines.len() && lines[i].kind == DiffLineKind::Addition {
+            i += 1;
+        }
+        let pairs = (add_start - del_start).min(i - add_start);
+        for k in 0..pairs {
+            let (d, a) = (del_start + k, add_start + k);
+            if let Some((old, new)) = changed_ranges(
+                lines[d].content.trim_end_matches('\n'),
+                lines[a].content.trim_end_matches('\n'),
+                mode,
+            ) {
+                out[d] = old;
+                out[a] = new;
+            }
+        }
+    }
+    out
+}
+
+/// Changed ranges of both lines, or None when they have nothing meaningful
+/// in common (emphasising a whole rewritten line is just noise).
+fn changed_ranges(old: &str, new: &str, mode: WordDiffMode) -> Option<(Ranges, Ranges)> {
+    let a = tokenize(old, mode);
+    let b = tokenize(new, mode);
+    if a.is_empty() || b.is_empty() || a.len() > MAX_TOKENS || b.len() > MAX_TOKENS {
+        return None;
+    }
+
+    // LCS table over tokens
+    let (n, m) = (a.len(), b.len());
+    let mut lcs = vec![vec![0u16; m + 1]; n + 1];
+    for x in (0..n).rev() {
+        for y in (0..m).rev() {
+            lcs[x][y] = if a[x].text == b[y].text {
+                lcs[x + 1][y + 1] + 1
+            } else {
+                lcs[x + 1][y].max(lcs[x][y + 1])
+            };
+        }
+    }
+
+    let mut keep_a = vec![false; n];
+    let mut keep_b = vec![false; m];
+    let (mut x, mut y) = (0, 0);
+    while x < n && y < m {
+        if a[x].text == b[y].text {
+            keep_a[x] = true;
+            keep_b[y] = true;
+            x += 1;
+            y += 1;
+        } else if lcs[x + 1][y] >= lcs[x][y + 1] {
+            x += 1;
+        } else {
+            y += 1;
+        }
+    }
+
+    let common = a.iter()
+        .zip(&keep_a)
+        .any(|(t, &kept)| kept && !t.text.trim().is_empty());
+    if !common {
+        return None;
+    }
+    Some((ranges(&a, &keep_a), ranges(&b, &keep_b)))
+}
+
+struct Token<'a> {
+    text: &'a str,
+    /// Byte offset of the token in its line
+    byte: usize,
+    /// Char offset and length, for rendering
+    start: usize,
+    len: usize,
+}
+
+/// Split a line into words (runs of alphanumerics/underscore), runs of
+/// whitespace and single punctuation chars; or into single chars.
+fn tokenize(line: &str, mode: WordDiffMode) -> Vec<Token<'_>> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+
+    let mut tokens: Vec<Token> = Vec::new();
+    for (char_idx, (byte_idx, c)) in line.char_indices().enumerate() {
+        let extend = mode == WordDiffMode::Word
+            && class(c) != 2
+            && tokens.last()
+                .and_then(|t| t.text.chars().next_back())
+                .is_some_and(|last| class(last) == class(c));
+        match tokens.last_mut() {
+            Some(t) if extend => {
+                t.text = &line[t.byte..byte_idx + c.len_utf8()];
+                t.len += 1;
+            }
+            _ => tokens.push(Token {
+                text: &line[byte_idx..byte_idx + c.len_utf8()],
+                byte: byte_idx,
+                start: char_idx,
+                len: 1,
+            }),
+        }
+    }
+    tokens
+}
+
+/// Merge the char ranges of tokens not in the LCS.
+fn ranges(tokens: &[Token], keep: &[bool]) -> Ranges {
+    let mut out = Ranges::new();
+    for (t, &kept) in tokens.iter().zip(keep) {
+        if kept {
+            continue;
+        }
+        match out.last_mut() {
+            Some(last) if last.1 == t.start => last.1 = t.start + t.len,
+            _ => out.push((t.start, t.start + t.len)),
+        }
+    }
+    out
+}
@@ -0,0 +1,122 @@
+//! Prompts sent from the send dialog, kept per repository as JSON lines in
+//! the state dir so they can be recalled (Up/Down) or searched (Ctrl-R).
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Entries kept in memory; older lines stay in the file.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub prompt: String,
+    pub files: Vec<String>,
+    /// Unix seconds
+    pub time: i64,
+}
+
+impl HistoryEntry {
+    /// Text searched by Ctrl-R.
+    fn haystack(&self) -> String {
+        let mut text = self.prompt.clone();
+        for f in &self.files {
+            text.push(' ');
+            text.push_str(f);
+        }
+        text
+    }
+}
+
+pub struct History {
+    /// Oldest first
+    pub entries: Vec<HistoryEntry>,
+    path: Option<PathBuf>,
+}
+
+impl History {
+    /// Read `history.jsonl` from `state_dir`, skipping lines that don't parse.
+    pub fn load(state_dir: Option<&Path>) -> Self {
+        let path = state_dir.map(|d| d.join("history.jsonl"));
+        let mut entries: Vec<HistoryEntry> = path.as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .map(|text| text.lines().filter_map(|l| serde_json::from_str(l).ok()).collect())
+            .unwrap_or_default();
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+        }
+        Self { entries, path }
+    }
+
+    /// Record a sent prompt, unless it repeats the previous one.
+    pub fn push(&mut self, prompt: &str, files: &[String]) -> Result<()> {
+        let entry = HistoryEntry {
+            prompt: prompt.to_string(),
+            files: files.to_vec(),
+            time: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0),
+        };
+        if self.entries.last().is_some_and(|e| e.prompt == entry.prompt && e.files == entry.files) {
+            return Ok(());
+        }
+        self.entries.push(entry.clone());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        let Some(ref path) = self.path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Indices of entries matching `query` fuzzily, best match first
+    /// (ties go to the most recent).
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let mut scored: Vec<(i64, usize)> = self.entries.iter()
+            .enumerate()
+            .filter_map(|(i, e)| fuzzy_score(query, &e.haystack()).map(|s| (s, i)))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        scored.into_iter().map(|(_, i)| i).collect()
+    }
+}
+
+/// Case-insensitive subsequence match. Consecutive characters and matches at
+/// word starts score higher; None when `query` is not a subsequence.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().flat_map(|c| c.to_lowercase()).collect();
+    let mut score = 0i64;
+    let mut pos = 0usize;
+    let mut prev: Option<usize> = None;
+    for q in query.chars().flat_map(|c| c.to_lowercase()) {
+        if q.is_whitespace() {
+            continue;
+        }
+        let found = text[pos..].iter().position(|&c| c == q)? + pos;
+        score += 1;
+        if prev.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        if let Some(p) = prev {
+            score -= (found - p - 1).min(10) as i64;
+        }
+        prev = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
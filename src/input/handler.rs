@@ -7,3 +7,7 @@ use crate::input::keymap;
 pub fn handle_key(key: KeyEvent, focus: Focus, active_tab: ActiveTab) -> Option<Action> {
     keymap::map_key(key, focus, active_tab)
 }
+
+pub fn handle_selection_key(key: KeyEvent) -> Option<Action> {
+    keymap::map_selection_key(key)
+}
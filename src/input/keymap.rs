@@ -40,7 +40,7 @@ fn map_claude_code_key(key: KeyEvent, focus: Focus) -> Option<Action> {
         }
         Focus::GitStatus => map_git_status_key(key),
         Focus::DiffView => map_diff_view_key(key),
-        Focus::PromptDialog => None, // handled directly in app
+        Focus::PromptDialog | Focus::Picker | Focus::Basket => None, // handled directly in app
         Focus::FileBrowserLeft | Focus::FileBrowserRight => None,
     }
 }
@@ -65,6 +65,12 @@ fn map_file_browser_key(key: KeyEvent) -> Option<Action> {
         (KeyModifiers::NONE, KeyCode::Char('n')) => Some(Action::FBMkdir),
         (KeyModifiers::NONE, KeyCode::Char('.')) => Some(Action::FBToggleHidden),
         (KeyModifiers::CONTROL, KeyCode::Char('r')) => Some(Action::FBRefresh),
+        (KeyModifiers::NONE, KeyCode::Char('a')) => Some(Action::FBApplyPatch),
+        (KeyModifiers::NONE, KeyCode::Char('i')) => Some(Action::BasketAdd),
+        (KeyModifiers::SHIFT, KeyCode::Char('I')) | (KeyModifiers::SHIFT, KeyCode::Char('i')) => Some(Action::BasketOpen),
+        (KeyModifiers::NONE, KeyCode::Char(' ')) => Some(Action::FBToggleMark),
+        (KeyModifiers::NONE, KeyCode::Char('s')) => Some(Action::FBSendToClaude),
+        (KeyModifiers::SHIFT, KeyCode::Char('S')) | (KeyModifiers::SHIFT, KeyCode::Char('s')) => Some(Action::FBSendToClaudeWithPrompt),
         _ => None,
     }
 }
@@ -89,11 +95,31 @@ fn map_git_status_key(key: KeyEvent) -> Option<Action> {
         (KeyModifiers::SHIFT, KeyCode::Char('C')) | (KeyModifiers::SHIFT, KeyCode::Char('c')) => Some(Action::CommitAndPush),
         (KeyModifiers::NONE, KeyCode::Char('p')) => Some(Action::Push),
         (KeyModifiers::SHIFT, KeyCode::Char('P')) | (KeyModifiers::SHIFT, KeyCode::Char('p')) => Some(Action::Pull),
+        (KeyModifiers::NONE, KeyCode::Char('f')) => Some(Action::Fetch),
+        (KeyModifiers::SHIFT, KeyCode::Char('F')) | (KeyModifiers::SHIFT, KeyCode::Char('f')) => Some(Action::ForcePush),
         (KeyModifiers::NONE, KeyCode::Char('b')) => Some(Action::BranchList),
         (KeyModifiers::SHIFT, KeyCode::Char('B')) | (KeyModifiers::SHIFT, KeyCode::Char('b')) => Some(Action::CreateBranch),
         (KeyModifiers::NONE, KeyCode::Char('z')) => Some(Action::Stash),
         (KeyModifiers::SHIFT, KeyCode::Char('Z')) | (KeyModifiers::SHIFT, KeyCode::Char('z')) => Some(Action::StashPop),
+        (KeyModifiers::NONE, KeyCode::Char('x')) => Some(Action::StashDrop),
+        (KeyModifiers::NONE, KeyCode::Char('u')) => Some(Action::UndoList),
+        (KeyModifiers::NONE, KeyCode::Char('w')) => Some(Action::Checkpoint),
+        (KeyModifiers::SHIFT, KeyCode::Char('W')) | (KeyModifiers::SHIFT, KeyCode::Char('w')) => Some(Action::CheckpointList),
+        (KeyModifiers::SHIFT, KeyCode::Char('D')) | (KeyModifiers::SHIFT, KeyCode::Char('d')) => Some(Action::CheckpointRollback),
         (KeyModifiers::NONE, KeyCode::Char('v')) => Some(Action::ToggleMultiSelect),
+        (KeyModifiers::NONE, KeyCode::Char('t')) => Some(Action::ToggleTreeView),
+        (KeyModifiers::NONE, KeyCode::Char('l')) => Some(Action::ToggleTurnFilter),
+        (KeyModifiers::NONE, KeyCode::Char('/')) => Some(Action::StartFilter),
+        (KeyModifiers::NONE, KeyCode::Char('m')) => Some(Action::SubmoduleList),
+        (KeyModifiers::SHIFT, KeyCode::Char('U')) | (KeyModifiers::SHIFT, KeyCode::Char('u')) => Some(Action::SubmoduleUpdate),
+        (KeyModifiers::NONE, KeyCode::Backspace) => Some(Action::SubmoduleLeave),
+        (KeyModifiers::SHIFT, KeyCode::Char('R')) | (KeyModifiers::SHIFT, KeyCode::Char('r')) => Some(Action::CompareMenu),
+        (KeyModifiers::NONE, KeyCode::Char('r')) => Some(Action::CompareReview),
+        (KeyModifiers::SHIFT, KeyCode::Char('E')) | (KeyModifiers::SHIFT, KeyCode::Char('e')) => Some(Action::GitExportPatch { clipboard: false }),
+        (KeyModifiers::SHIFT, KeyCode::Char('Y')) | (KeyModifiers::SHIFT, KeyCode::Char('y')) => Some(Action::GitExportPatch { clipboard: true }),
+        (KeyModifiers::SHIFT, KeyCode::Char('T')) | (KeyModifiers::SHIFT, KeyCode::Char('t')) => Some(Action::TagList),
+        (KeyModifiers::NONE, KeyCode::Char('i')) => Some(Action::BasketAdd),
+        (KeyModifiers::SHIFT, KeyCode::Char('I')) | (KeyModifiers::SHIFT, KeyCode::Char('i')) => Some(Action::BasketOpen),
         _ => None,
     }
 }
@@ -119,22 +145,52 @@ fn map_diff_view_key(key: KeyEvent) -> Option<Action> {
             Some(Action::DiffClose)
         }
         (KeyModifiers::NONE, KeyCode::Char('s')) => Some(Action::DiffSendLines),
+        (KeyModifiers::NONE, KeyCode::Char('b')) => Some(Action::DiffToggleBlame),
+        (KeyModifiers::NONE, KeyCode::Char('w')) => Some(Action::DiffCycleWordDiff),
+        (KeyModifiers::NONE, KeyCode::Char('v')) => Some(Action::DiffToggleSplit),
+        (KeyModifiers::SHIFT, KeyCode::Char('L')) | (KeyModifiers::SHIFT, KeyCode::Char('l')) => Some(Action::DiffLoadFull),
+        (KeyModifiers::NONE, KeyCode::Char('/')) => Some(Action::DiffSearch),
+        (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char(':')) => Some(Action::DiffGotoLine),
+        (KeyModifiers::NONE, KeyCode::Char('n')) => Some(Action::DiffNextMatch),
+        (KeyModifiers::SHIFT, KeyCode::Char('N')) | (KeyModifiers::SHIFT, KeyCode::Char('n')) => Some(Action::DiffPrevMatch),
+        (KeyModifiers::NONE, KeyCode::Char('g')) => Some(Action::DiffGoPrefix),
+        (KeyModifiers::SHIFT, KeyCode::Char('G')) | (KeyModifiers::SHIFT, KeyCode::Char('g')) => Some(Action::DiffBottom),
+        (KeyModifiers::CONTROL, KeyCode::Char('d')) => Some(Action::DiffHalfPageDown),
+        (KeyModifiers::CONTROL, KeyCode::Char('u')) => Some(Action::DiffHalfPageUp),
+        (KeyModifiers::NONE | KeyModifiers::SHIFT, KeyCode::Char('+') | KeyCode::Char('=')) => Some(Action::DiffContext(1)),
+        (KeyModifiers::NONE, KeyCode::Char('-')) => Some(Action::DiffContext(-1)),
+        (KeyModifiers::SHIFT, KeyCode::Char('W')) | (KeyModifiers::SHIFT, KeyCode::Char('w')) => Some(Action::DiffCycleWhitespace),
+        (KeyModifiers::SHIFT, KeyCode::Char('A')) | (KeyModifiers::SHIFT, KeyCode::Char('a')) => Some(Action::DiffCycleAlgorithm),
+        (KeyModifiers::NONE, KeyCode::Char('[')) => Some(Action::DiffExpandHunk { above: true }),
+        (KeyModifiers::NONE, KeyCode::Char(']')) => Some(Action::DiffExpandHunk { above: false }),
+        (KeyModifiers::SHIFT, KeyCode::Char('E')) | (KeyModifiers::SHIFT, KeyCode::Char('e')) => Some(Action::DiffExportPatch { clipboard: false }),
+        (KeyModifiers::SHIFT, KeyCode::Char('Y')) | (KeyModifiers::SHIFT, KeyCode::Char('y')) => Some(Action::DiffExportPatch { clipboard: true }),
+        (KeyModifiers::SHIFT, KeyCode::Char('T')) | (KeyModifiers::SHIFT, KeyCode::Char('t')) => Some(Action::DiffTagCommit),
+        (KeyModifiers::NONE, KeyCode::Enter) => Some(Action::DiffShowCommit),
+        (KeyModifiers::NONE, KeyCode::Char('i')) => Some(Action::BasketAdd),
+        (KeyModifiers::SHIFT, KeyCode::Char('I')) | (KeyModifiers::SHIFT, KeyCode::Char('i')) => Some(Action::BasketOpen),
         (KeyModifiers::SHIFT, KeyCode::Char('S')) | (KeyModifiers::SHIFT, KeyCode::Char('s')) => Some(Action::SendToClaudeWithPrompt),
         _ => None,
     }
 }
 
+/// Keys that act on an active PTY selection instead of being typed into it.
+pub fn map_selection_key(key: KeyEvent) -> Option<Action> {
+    match (key.modifiers, key.code) {
+        (KeyModifiers::ALT, KeyCode::Char('q')) => Some(Action::SelectionQuote),
+        (KeyModifiers::ALT, KeyCode::Char('o')) => Some(Action::SelectionOpenRefs),
+        _ => None,
+    }
+}
+
 /// Convert a crossterm KeyEvent to the bytes that should be sent to a PTY
 pub fn key_to_bytes(key: KeyEvent) -> Vec<u8> {
     let mut bytes = Vec::new();
 
     if key.modifiers.contains(KeyModifiers::CONTROL) {
-        match key.code {
-            KeyCode::Char(c) => {
-                let ctrl_byte = (c as u8).wrapping_sub(b'a').wrapping_add(1);
-                bytes.push(ctrl_byte);
-            }
-            _ => {}
+        if let KeyCode::Char(c) = key.code {
+            let ctrl_byte = (c as u8).wrapping_sub(b'a').wrapping_add(1);
+            bytes.push(ctrl_byte);
         }
         return bytes;
     }
@@ -2,11 +2,17 @@
 
 mod action;
 mod app;
+mod basket;
+mod config;
 mod event;
+mod file_refs;
 mod filebrowser;
 mod git;
+mod history;
 mod input;
+mod prompts;
 mod pty;
+mod state;
 mod tui;
 mod ui;
 
@@ -18,18 +24,25 @@ use tokio::sync::mpsc;
 
 use action::ActiveTab;
 use app::{App, Focus};
+use clap::Parser;
+use config::Config;
 use event::AppEvent;
 use pty::manager::PtyManager;
+use ui::basket_panel::BasketPanel;
 use ui::command_bar::CommandBar;
 use ui::file_browser_pane::FileBrowserPane;
 use ui::git_pane::GitPane;
 use ui::layout::AppLayout;
+use ui::picker::Picker;
 use ui::prompt_dialog::PromptDialog;
 use ui::pty_pane::PtyPane;
 use ui::tab_bar::TabBar;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Parse args before touching the terminal so --help prints normally
+    let config = Config::parse();
+
     // Set panic hook to restore terminal before printing panic
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
@@ -37,7 +50,7 @@ async fn main() -> Result<()> {
         original_hook(info);
     }));
 
-    let result = run().await;
+    let result = run(config).await;
 
     // Always restore terminal
     tui::restore()?;
@@ -45,7 +58,7 @@ async fn main() -> Result<()> {
     result
 }
 
-async fn run() -> Result<()> {
+async fn run(config: Config) -> Result<()> {
     let mut terminal = tui::init().context("terminal init")?;
     let size = terminal.size().context("get terminal size")?;
 
@@ -59,7 +72,7 @@ async fn run() -> Result<()> {
 
     let (pty_manager, pty_reader) =
         PtyManager::spawn(pty_cols, pty_rows).context("spawn PTY")?;
-    let mut app = App::new(pty_manager, pty_cols, pty_rows);
+    let mut app = App::new(pty_manager, pty_cols, pty_rows, config);
 
     // Event channel
     let (tx, mut rx) = mpsc::unbounded_channel::<AppEvent>();
@@ -144,7 +157,8 @@ async fn run() -> Result<()> {
                         app.resize_pty(pty_area);
 
                         // Render PTY pane
-                        let pty_pane = PtyPane::new(&app.emulator, app.focus == Focus::Pty, &app.pty_selection);
+                        let links = app.underlined_links();
+                        let pty_pane = PtyPane::new(&app.emulator, app.focus == Focus::Pty, &app.pty_selection, &links);
                         pty_pane.render(pty_area, frame.buffer_mut());
 
                         // Render Git pane
@@ -152,6 +166,8 @@ async fn run() -> Result<()> {
                             files: &app.files,
                             diff: app.current_diff.as_ref(),
                             branch: &app.branch,
+                            submodule: app.submodule_stack.last().map(|(_, p)| p.as_str()),
+                            compare: app.compare.as_ref().map(|c| c.label.as_str()),
                             focus: app.focus,
                             status_state: &mut app.status_state,
                             diff_state: &app.diff_state,
@@ -185,11 +201,21 @@ async fn run() -> Result<()> {
                 );
                 cmd_bar.render(cmd_area, frame.buffer_mut());
 
+                // Context basket (overlay, below the dialogs it opens)
+                if app.basket_open {
+                    BasketPanel::new(&app.basket).render(content_area, frame.buffer_mut());
+                }
+
                 // Prompt dialog (modal overlay)
                 if app.prompt_state.visible {
                     let dialog = PromptDialog::new(&app.prompt_state);
                     dialog.render(content_area, frame.buffer_mut());
                 }
+
+                // Picker (modal overlay)
+                if app.picker_state.visible {
+                    Picker::new(&mut app.picker_state).render(content_area, frame.buffer_mut());
+                }
             })?;
         }
     }
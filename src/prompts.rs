@@ -0,0 +1,122 @@
+//! Prompt templates for the send dialog. Each file in the user's
+//! `rataclaude/prompts/` config dir or the project's `.rataclaude/prompts/`
+//! is one template, named after the file stem; project templates win.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::{Captures, Regex};
+
+/// Used when neither directory defines a template of the same name.
+const BUILTIN: &[(&str, &str)] = &[
+    ("explain", "Explain what {files} does and how it fits into the rest of the codebase."),
+    ("review", "Review these changes to {files} for bugs, edge cases and missing error handling:\n\n```diff\n{diff}\n```"),
+    ("tests", "Write tests for {files}, following the layout of the existing tests."),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemplateSource {
+    Builtin,
+    User,
+    Project,
+}
+
+#[derive(Debug, Clone)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub body: String,
+    pub source: TemplateSource,
+}
+
+impl PromptTemplate {
+    /// Picker line: name, origin and the start of the body.
+    pub fn label(&self) -> String {
+        let source = match self.source {
+            TemplateSource::Builtin => "built-in",
+            TemplateSource::User => "user",
+            TemplateSource::Project => "project",
+        };
+        let first = self.body.lines().next().unwrap_or("");
+        let preview: String = first.chars().take(50).collect();
+        let ellipsis = if first.chars().count() > 50 || self.body.lines().count() > 1 { "…" } else { "" };
+        format!("{:<12} {:<8} {}{}", self.name, source, preview, ellipsis)
+    }
+
+    pub fn uses(&self, var: &str) -> bool {
+        self.body.contains(&format!("{{{}}}", var))
+    }
+}
+
+/// Values substituted into a template. Missing values become "".
+#[derive(Debug, Default)]
+pub struct TemplateContext {
+    pub files: Vec<String>,
+    pub selection: Option<String>,
+    pub branch: String,
+    pub diff: Option<String>,
+    /// "path:A-B" of the diff view cursor or selection
+    pub line_range: Option<String>,
+}
+
+/// Built-in, user and project templates, sorted by name.
+pub fn load(workdir: &str) -> Vec<PromptTemplate> {
+    let mut templates: Vec<PromptTemplate> = BUILTIN.iter()
+        .map(|(name, body)| PromptTemplate {
+            name: name.to_string(),
+            body: body.to_string(),
+            source: TemplateSource::Builtin,
+        })
+        .collect();
+    if let Some(dir) = dirs::config_dir() {
+        merge_dir(&mut templates, &dir.join("rataclaude").join("prompts"), TemplateSource::User);
+    }
+    merge_dir(&mut templates, &Path::new(workdir).join(".rataclaude").join("prompts"), TemplateSource::Project);
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+fn merge_dir(templates: &mut Vec<PromptTemplate>, dir: &Path, source: TemplateSource) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else { continue };
+        if name.starts_with('.') {
+            continue;
+        }
+        let Ok(body) = std::fs::read_to_string(&path) else { continue };
+        let template = PromptTemplate { name, body: body.trim_end().to_string(), source };
+        match templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => templates.push(template),
+        }
+    }
+}
+
+/// Substitute `{files}`, `{selection}`, `{branch}`, `{diff}` and
+/// `{line_range}`. Other braces are left alone.
+pub fn render(body: &str, ctx: &TemplateContext) -> String {
+    let files = ctx.files.iter()
+        .map(|f| format!("@{}", f))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let line_range = ctx.line_range.as_deref()
+        .map(|r| format!("@{}", r))
+        .unwrap_or_default();
+    // One pass, so braces inside substituted text (a diff, say) stay as they are
+    var_regex().replace_all(body, |caps: &Captures| match &caps[1] {
+        "files" => files.clone(),
+        "selection" => ctx.selection.as_deref().unwrap_or("").trim_end().to_string(),
+        "branch" => ctx.branch.clone(),
+        "diff" => ctx.diff.as_deref().unwrap_or("").trim_end().to_string(),
+        _ => line_range.clone(),
+    })
+    .to_string()
+}
+
+fn var_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{(files|selection|branch|diff|line_range)\}").expect("valid regex"))
+}
@@ -0,0 +1,206 @@
+//! Clickable links on the visible PTY screen: URLs, OSC 8 hyperlinks and
+//! paths (optionally `path:line`) that exist in the workdir.
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::file_refs::{self, FileRef};
+
+/// OSC 8 hyperlinks remembered for matching against the screen.
+const MAX_HYPERLINKS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    Url(String),
+    /// Line 1 when the text had no line number
+    File(FileRef),
+}
+
+/// A link on screen row `row`, covering columns `start..end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub row: u16,
+    pub start: u16,
+    pub end: u16,
+    pub target: LinkTarget,
+}
+
+impl Link {
+    pub fn contains(&self, col: u16, row: u16) -> bool {
+        self.row == row && col >= self.start && col < self.end
+    }
+}
+
+#[derive(Debug, Default)]
+enum OscState {
+    #[default]
+    Ground,
+    Escape,
+    Csi,
+    Osc(Vec<u8>),
+    OscEscape(Vec<u8>),
+}
+
+/// Follows OSC 8 (`ESC ] 8 ; params ; uri ST text ESC ] 8 ; ; ST`) in the
+/// raw PTY output, which vt100 drops, and records each link's text and URI.
+#[derive(Debug, Default)]
+pub struct HyperlinkTracker {
+    state: OscState,
+    open: Option<(String, Vec<u8>)>,
+    links: VecDeque<(String, String)>,
+}
+
+impl HyperlinkTracker {
+    pub fn process(&mut self, data: &[u8]) {
+        for &b in data {
+            self.state = match std::mem::take(&mut self.state) {
+                OscState::Ground if b == 0x1b => OscState::Escape,
+                OscState::Ground => {
+                    if let Some((_, ref mut text)) = self.open {
+                        if b >= 0x20 && b != 0x7f {
+                            text.push(b);
+                        }
+                    }
+                    OscState::Ground
+                }
+                OscState::Escape => match b {
+                    b']' => OscState::Osc(Vec::new()),
+                    b'[' => OscState::Csi,
+                    _ => OscState::Ground,
+                },
+                OscState::Csi if (0x40..=0x7e).contains(&b) => OscState::Ground,
+                OscState::Csi => OscState::Csi,
+                OscState::Osc(buf) if b == 0x07 => {
+                    self.finish_osc(&buf);
+                    OscState::Ground
+                }
+                OscState::Osc(buf) if b == 0x1b => OscState::OscEscape(buf),
+                OscState::Osc(mut buf) => {
+                    if buf.len() < 4096 {
+                        buf.push(b);
+                    }
+                    OscState::Osc(buf)
+                }
+                OscState::OscEscape(buf) => {
+                    if b == b'\\' {
+                        self.finish_osc(&buf);
+                    }
+                    OscState::Ground
+                }
+            };
+        }
+    }
+
+    fn finish_osc(&mut self, buf: &[u8]) {
+        let Some(rest) = buf.strip_prefix(b"8;") else { return };
+        let rest = String::from_utf8_lossy(rest);
+        let uri = rest.split_once(';').map(|(_, uri)| uri).unwrap_or("");
+        // Any OSC 8 ends the open link; a non-empty URI starts the next one
+        if let Some((uri, text)) = self.open.take() {
+            let text = String::from_utf8_lossy(&text).trim().to_string();
+            if !text.is_empty() {
+                if self.links.len() == MAX_HYPERLINKS {
+                    self.links.pop_front();
+                }
+                self.links.push_back((text, uri));
+            }
+        }
+        if !uri.is_empty() {
+            self.open = Some((uri.to_string(), Vec::new()));
+        }
+    }
+
+    /// (text, uri) pairs, oldest first.
+    pub fn links(&self) -> &VecDeque<(String, String)> {
+        &self.links
+    }
+}
+
+fn url_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"https?://[^\s<>"'`]+"#).expect("valid regex"))
+}
+
+fn path_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[\w.~/@+-]*[/.][\w.~/@+-]*(?::(\d+))?(?::\d+)?").expect("valid regex"))
+}
+
+/// Links on visible row `row` of `screen`. Paths are resolved against `workdir`.
+pub fn detect_row(screen: &vt100::Screen, row: u16, workdir: &Path, hyperlinks: &VecDeque<(String, String)>) -> Vec<Link> {
+    let (_, cols) = screen.size();
+    // Row text plus the column each byte came from
+    let mut text = String::new();
+    let mut byte_col: Vec<u16> = Vec::new();
+    for col in 0..cols {
+        let Some(cell) = screen.cell(row, col) else { continue };
+        if cell.is_wide_continuation() {
+            continue;
+        }
+        let contents = cell.contents();
+        let contents = if contents.is_empty() { " ".to_string() } else { contents };
+        byte_col.extend(std::iter::repeat_n(col, contents.len()));
+        text.push_str(&contents);
+    }
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    let span = |start: usize, end: usize| {
+        let first = byte_col[start];
+        let last = byte_col[end - 1];
+        (first, last + 1)
+    };
+    let mut row_links: Vec<Link> = Vec::new();
+    let overlaps = |links: &[Link], start: u16, end: u16| {
+        links.iter().any(|l| start < l.end && l.start < end)
+    };
+
+    for (label, uri) in hyperlinks.iter().rev() {
+        for (pos, _) in text.match_indices(label.as_str()) {
+            let (start, end) = span(pos, pos + label.len());
+            if overlaps(&row_links, start, end) {
+                continue;
+            }
+            let target = match uri.strip_prefix("file://") {
+                Some(path) => {
+                    // file://host/path: drop the host
+                    let path = path.find('/').map(|i| &path[i..]).unwrap_or(path);
+                    match file_refs::resolve(path, workdir) {
+                        Some(path) => LinkTarget::File(FileRef { path, line: 1, column: None }),
+                        None => continue,
+                    }
+                }
+                None => LinkTarget::Url(uri.clone()),
+            };
+            row_links.push(Link { row, start, end, target });
+        }
+    }
+
+    for m in url_regex().find_iter(&text) {
+        let url = m.as_str().trim_end_matches(['.', ',', ';', ':', ')', ']', '}', '!', '?']);
+        let (start, end) = span(m.start(), m.start() + url.len());
+        if !overlaps(&row_links, start, end) {
+            row_links.push(Link { row, start, end, target: LinkTarget::Url(url.to_string()) });
+        }
+    }
+
+    for caps in path_regex().captures_iter(&text) {
+        let m = caps.get(0).expect("whole match");
+        let full = m.as_str();
+        let path_part = full.split(':').next().unwrap_or(full);
+        let Some(path) = file_refs::resolve(path_part, workdir) else { continue };
+        let line = caps.get(1).and_then(|l| l.as_str().parse().ok());
+        // Underline the path and the line number, not trailing punctuation
+        let len = if line.is_some() { full.len() } else { path_part.trim_end_matches(['.', ',']).len() };
+        let (start, end) = span(m.start(), m.start() + len);
+        if !overlaps(&row_links, start, end) {
+            let file_ref = FileRef { path, line: line.unwrap_or(1), column: None };
+            row_links.push(Link { row, start, end, target: LinkTarget::File(file_ref) });
+        }
+    }
+
+    row_links
+}
@@ -163,6 +163,12 @@ impl PtyManager {
         Ok(())
     }
 
+    /// Write `text` as a bracketed paste so embedded newlines don't submit.
+    pub async fn paste(&self, text: &str) -> Result<()> {
+        self.writer.write_all(format!("\x1b[200~{}\x1b[201~", text).as_bytes()).await?;
+        Ok(())
+    }
+
     pub fn resize(&self, cols: u16, rows: u16) -> Result<()> {
         let ws = libc::winsize {
             ws_row: rows,
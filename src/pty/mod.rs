@@ -1,2 +1,3 @@
+pub mod links;
 pub mod manager;
 pub mod terminal_emulator;
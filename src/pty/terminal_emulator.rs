@@ -5,10 +5,12 @@ use ratatui::{
 };
 
 use crate::app::PtySelection;
+use crate::pty::links::{HyperlinkTracker, Link};
 
 pub struct TerminalEmulator {
     parser: vt100::Parser,
     scroll_offset: usize,
+    hyperlinks: HyperlinkTracker,
 }
 
 impl TerminalEmulator {
@@ -16,11 +18,18 @@ impl TerminalEmulator {
         Self {
             parser: vt100::Parser::new(rows, cols, 1000),
             scroll_offset: 0,
+            hyperlinks: HyperlinkTracker::default(),
         }
     }
 
     pub fn process(&mut self, data: &[u8]) {
         self.parser.process(data);
+        self.hyperlinks.process(data);
+    }
+
+    /// OSC 8 hyperlinks seen so far, as (text, uri).
+    pub fn hyperlinks(&self) -> &std::collections::VecDeque<(String, String)> {
+        self.hyperlinks.links()
     }
 
     pub fn set_size(&mut self, rows: u16, cols: u16) {
@@ -63,7 +72,7 @@ impl TerminalEmulator {
         self.scroll_offset > 0
     }
 
-    pub fn render(&self, area: Rect, buf: &mut Buffer, focused: bool, selection: &PtySelection) {
+    pub fn render(&self, area: Rect, buf: &mut Buffer, focused: bool, selection: &PtySelection, links: &[Link]) {
         let screen = self.parser.screen();
         let selection_bg = Color::Rgb(60, 80, 140);
 
@@ -92,7 +101,7 @@ impl TerminalEmulator {
                         if cell.italic() {
                             modifiers |= Modifier::ITALIC;
                         }
-                        if cell.underline() {
+                        if cell.underline() || links.iter().any(|l| l.contains(col, row)) {
                             modifiers |= Modifier::UNDERLINED;
                         }
                         if cell.inverse() && !is_selected {
@@ -0,0 +1,11 @@
+//! Per-repository state kept outside the repository (saved baskets, ...).
+
+use std::path::PathBuf;
+
+/// `$XDG_STATE_HOME/rataclaude/<workdir>` (the local data dir on macOS),
+/// with the workdir flattened into a single directory name.
+pub fn repo_state_dir(workdir: &str) -> Option<PathBuf> {
+    let base = dirs::state_dir().or_else(dirs::data_local_dir)?;
+    let key = workdir.trim_end_matches('/').replace(['/', '\\', ':'], "-");
+    Some(base.join("rataclaude").join(key))
+}
@@ -0,0 +1,85 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+use crate::basket::{Basket, BasketItem};
+
+/// Overlay listing the context basket, in the order it will be sent.
+pub struct BasketPanel<'a> {
+    basket: &'a Basket,
+}
+
+impl<'a> BasketPanel<'a> {
+    pub fn new(basket: &'a Basket) -> Self {
+        Self { basket }
+    }
+}
+
+impl Widget for BasketPanel<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let width = area.width.min(70);
+        let height = (self.basket.items.len().max(1) as u16 + 2).max(8).min(area.height);
+        let x = (area.width - width) / 2 + area.x;
+        let y = (area.height - height) / 2 + area.y;
+        let dialog_area = Rect::new(x, y, width, height);
+
+        Clear.render(dialog_area, buf);
+
+        let title = match self.basket.name {
+            Some(ref name) => format!(" Context basket: {} ({}) ", name, self.basket.items.len()),
+            None => format!(" Context basket ({}) ", self.basket.items.len()),
+        };
+        let mut hints = Vec::new();
+        for (key, desc) in [
+            ("Enter", "send"), ("J/K", "move"), ("d", "remove"), ("p", "add selection"),
+            ("c", "clear"), ("w/o", "save/open"), ("Esc", "close"),
+        ] {
+            hints.push(Span::styled(format!(" {}", key), Style::default().fg(Color::Yellow)));
+            hints.push(Span::raw(format!(" {} ", desc)));
+        }
+
+        let block = Block::default()
+            .title(title)
+            .title_bottom(Line::from(hints))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+
+        if self.basket.items.is_empty() {
+            let inner = block.inner(dialog_area);
+            block.render(dialog_area, buf);
+            let empty = Line::from(Span::styled(
+                "Empty: press i on a file, diff selection or file browser entry",
+                Style::default().fg(Color::DarkGray),
+            ));
+            buf.set_line(inner.x + 1, inner.y, &empty, inner.width.saturating_sub(1));
+            return;
+        }
+
+        let items: Vec<ListItem> = self.basket.items.iter()
+            .map(|item| {
+                let color = match item {
+                    BasketItem::File { .. } => Color::Green,
+                    BasketItem::Lines { .. } => Color::Cyan,
+                    BasketItem::Text { .. } => Color::Gray,
+                };
+                ListItem::new(Line::from(Span::styled(item.label(), Style::default().fg(color))))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▶ ");
+
+        let mut state = ListState::default().with_selected(Some(self.basket.cursor));
+        StatefulWidget::render(list, dialog_area, buf, &mut state);
+    }
+}
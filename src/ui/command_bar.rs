@@ -53,28 +53,66 @@ impl Widget for CommandBar {
                 match self.focus {
                     Focus::Pty => {
                         spans.extend(Self::key_hint("C-\\", "resize"));
+                        spans.extend(Self::key_hint("drag", "select"));
+                        spans.extend(Self::key_hint("click", "open link"));
+                        spans.extend(Self::key_hint("M-q/M-o", "quote/open selection"));
                     }
                     Focus::GitStatus => {
                         spans.extend(Self::key_hint("j/k", "nav"));
                         spans.extend(Self::key_hint("Spc", "stage"));
                         spans.extend(Self::key_hint("e", "expand"));
+                        spans.extend(Self::key_hint("t", "tree"));
+                        spans.extend(Self::key_hint("/", "filter"));
+                        spans.extend(Self::key_hint("l", "last turn"));
                         spans.extend(Self::key_hint("s/S", "send"));
+                        spans.extend(Self::key_hint("i/I", "basket"));
                         spans.extend(Self::key_hint("c", "commit"));
                         spans.extend(Self::key_hint("C", "commit+push"));
                         spans.extend(Self::key_hint("p/P", "push/pull"));
+                        spans.extend(Self::key_hint("f/F", "fetch/force"));
                         spans.extend(Self::key_hint("b/B", "branch/new"));
-                        spans.extend(Self::key_hint("z/Z", "stash/pop"));
+                        spans.extend(Self::key_hint("z/Z/x", "stash/pop/drop"));
+                        spans.extend(Self::key_hint("d", "discard"));
+                        spans.extend(Self::key_hint("m/U", "submodules/update"));
+                        spans.extend(Self::key_hint("R/r", "compare/review"));
+                        spans.extend(Self::key_hint("T", "tags"));
+                        spans.extend(Self::key_hint("E/Y", "patch"));
+                        spans.extend(Self::key_hint("u", "undo"));
+                        spans.extend(Self::key_hint("w/W/D", "checkpoint/list/rollback"));
                     }
                     Focus::DiffView => {
                         spans.extend(Self::key_hint("j/k", "scroll"));
                         spans.extend(Self::key_hint("J/K", "hunk"));
                         spans.extend(Self::key_hint("Esc", "back"));
                         spans.extend(Self::key_hint("s", "send"));
+                        spans.extend(Self::key_hint("i/I", "basket"));
+                        spans.extend(Self::key_hint("b", "blame"));
+                        spans.extend(Self::key_hint("w", "word diff"));
+                        spans.extend(Self::key_hint("v", "split"));
+                        spans.extend(Self::key_hint("/ n/N", "search"));
+                        spans.extend(Self::key_hint(":", "line"));
+                        spans.extend(Self::key_hint("[/]", "expand"));
+                        spans.extend(Self::key_hint("+/-", "context"));
+                        spans.extend(Self::key_hint("W/A", "ws/algo"));
+                        spans.extend(Self::key_hint("E/Y", "patch"));
+                        spans.extend(Self::key_hint("Enter", "commit"));
                     }
                     Focus::PromptDialog => {
                         spans.extend(Self::key_hint("Enter", "confirm"));
                         spans.extend(Self::key_hint("Esc", "cancel"));
                     }
+                    Focus::Picker => {
+                        spans.extend(Self::key_hint("j/k", "nav"));
+                        spans.extend(Self::key_hint("Enter", "select"));
+                        spans.extend(Self::key_hint("Esc", "cancel"));
+                    }
+                    Focus::Basket => {
+                        spans.extend(Self::key_hint("j/k", "nav"));
+                        spans.extend(Self::key_hint("J/K", "reorder"));
+                        spans.extend(Self::key_hint("p", "add PTY selection"));
+                        spans.extend(Self::key_hint("Enter", "send"));
+                        spans.extend(Self::key_hint("Esc", "close"));
+                    }
                     Focus::FileBrowserLeft | Focus::FileBrowserRight => {}
                 }
             }
@@ -89,6 +127,10 @@ impl Widget for CommandBar {
                 spans.extend(Self::key_hint("d", "delete"));
                 spans.extend(Self::key_hint("r", "rename"));
                 spans.extend(Self::key_hint("n", "mkdir"));
+                spans.extend(Self::key_hint("Spc", "mark"));
+                spans.extend(Self::key_hint("s/S", "send"));
+                spans.extend(Self::key_hint("a", "apply patch"));
+                spans.extend(Self::key_hint("i/I", "basket"));
                 spans.extend(Self::key_hint(".", "hidden"));
             }
         }
@@ -6,7 +6,9 @@ use ratatui::{
     widgets::{Block, Borders, Widget},
 };
 
-use crate::git::diff::{DiffLineKind, FileDiff};
+use crate::git::blame::BlameLine;
+use crate::git::diff::{DiffLineKind, DiffSettings, FileDiff};
+use crate::git::word_diff::{self, WordDiffMode};
 use crate::ui::syntax::{self, HighlightSpan};
 
 // ── True-color palette (looks great on Ghostty) ─────────────────────
@@ -18,6 +20,10 @@ const DEL_BG: Color = Color::Rgb(50, 22, 22);
 const DEL_FG: Color = Color::Rgb(235, 100, 95);
 const DEL_GUTTER_FG: Color = Color::Rgb(170, 70, 65);
 
+// Intra-line emphasis for the spans that actually changed
+const ADD_EMPH_BG: Color = Color::Rgb(36, 84, 48);
+const DEL_EMPH_BG: Color = Color::Rgb(100, 34, 34);
+
 const CTX_FG: Color = Color::Rgb(140, 140, 140);
 const CTX_GUTTER_FG: Color = Color::Rgb(80, 80, 80);
 
@@ -35,9 +41,40 @@ const BORDER_UNFOCUSED: Color = Color::Rgb(55, 55, 65);
 const SCROLLBAR_TRACK: Color = Color::Rgb(35, 35, 40);
 const SCROLLBAR_THUMB: Color = Color::Rgb(90, 90, 110);
 
+const FILLER_BG: Color = Color::Rgb(22, 22, 26);
+const FILLER_FG: Color = Color::Rgb(40, 40, 48);
+
+const SEARCH_BG: Color = Color::Rgb(110, 95, 30);
+const SEARCH_FG: Color = Color::Rgb(240, 210, 100);
+
 const CURSOR_BG: Color = Color::Rgb(45, 50, 65);
 const SELECT_BG: Color = Color::Rgb(40, 55, 80);
 
+// Blame gutter: newer commits are brighter
+const BLAME_BG: Color = Color::Rgb(28, 28, 34);
+const BLAME_DAY: Color = Color::Rgb(240, 200, 110);
+const BLAME_WEEK: Color = Color::Rgb(200, 170, 110);
+const BLAME_MONTH: Color = Color::Rgb(160, 145, 115);
+const BLAME_YEAR: Color = Color::Rgb(120, 115, 105);
+const BLAME_OLD: Color = Color::Rgb(85, 85, 85);
+const BLAME_UNCOMMITTED: Color = Color::Rgb(110, 150, 220);
+const BLAME_W: u16 = 26;
+
+/// One row of the side-by-side view, as indices into `FileDiff::all_lines`.
+#[derive(Debug, Clone)]
+pub enum SplitRow {
+    Header(usize),
+    /// Old line on the left, new line on the right; None is a filler cell
+    Pair { left: Option<usize>, right: Option<usize> },
+}
+
+/// Line input at the bottom of the diff view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffPrompt {
+    Search,
+    GotoLine,
+}
+
 pub struct DiffViewState {
     pub scroll: u16,
     pub h_scroll: u16,
@@ -47,6 +84,32 @@ pub struct DiffViewState {
     /// Cached syntax-highlighted spans for each line in the diff.
     /// Recomputed only when the diff changes (set_file / update_highlight_cache).
     pub highlight_cache: Vec<Vec<HighlightSpan>>,
+    /// Blame for the working-tree file, indexed by new line number - 1.
+    /// Some(..) while blame mode is on.
+    pub blame: Option<Vec<BlameLine>>,
+    /// Full SHA when the view shows a commit instead of a working-tree file
+    pub commit_view: Option<String>,
+    /// Granularity of intra-line change highlighting
+    pub word_diff: WordDiffMode,
+    /// Changed char ranges per line (see `word_diff::emphasis`), cached like highlights.
+    pub emphasis: Vec<word_diff::Ranges>,
+    /// Side-by-side view. Cursor, scroll and selection then index `split_rows`.
+    pub split: bool,
+    pub split_rows: Vec<SplitRow>,
+    /// `/` or `:` input being typed
+    pub prompt: Option<DiffPrompt>,
+    pub prompt_input: String,
+    /// Active search pattern (smart case) and its match ranges per diff line
+    pub search: Option<regex::Regex>,
+    pub search_hits: Vec<word_diff::Ranges>,
+    /// The search input does not compile
+    pub search_error: bool,
+    /// Cursor when the prompt opened, restored on Esc
+    pub prompt_origin: usize,
+    /// First `g` of `gg` was pressed
+    pub pending_g: bool,
+    /// Context, whitespace and algorithm the diffs are computed with
+    pub settings: DiffSettings,
 }
 
 impl DiffViewState {
@@ -58,6 +121,20 @@ impl DiffViewState {
             select_anchor: None,
             file_path: None,
             highlight_cache: Vec::new(),
+            blame: None,
+            commit_view: None,
+            word_diff: WordDiffMode::Word,
+            emphasis: Vec::new(),
+            split: false,
+            split_rows: Vec::new(),
+            prompt: None,
+            prompt_input: String::new(),
+            search: None,
+            search_hits: Vec::new(),
+            search_error: false,
+            prompt_origin: 0,
+            pending_g: false,
+            settings: DiffSettings::default(),
         }
     }
 
@@ -122,6 +199,156 @@ impl DiffViewState {
         self.select_anchor = None;
     }
 
+    /// Move the cursor to `row` and scroll it into view.
+    pub fn jump_to(&mut self, row: usize, viewport_h: u16) {
+        self.cursor = row;
+        self.ensure_visible(viewport_h);
+    }
+
+    pub fn top(&mut self) {
+        self.cursor = 0;
+        self.scroll = 0;
+    }
+
+    pub fn bottom(&mut self, diff: &FileDiff, viewport_h: u16) {
+        self.jump_to(self.row_count(diff).saturating_sub(1), viewport_h);
+    }
+
+    /// Move half a page; `down` false moves up. The view scrolls along.
+    pub fn half_page(&mut self, diff: &FileDiff, down: bool, viewport_h: u16) {
+        let step = (viewport_h / 2).max(1);
+        let max = self.row_count(diff).saturating_sub(1);
+        if down {
+            self.cursor = (self.cursor + step as usize).min(max);
+            self.scroll = self.scroll.saturating_add(step).min(max as u16);
+        } else {
+            self.cursor = self.cursor.saturating_sub(step as usize);
+            self.scroll = self.scroll.saturating_sub(step);
+        }
+        self.ensure_visible(viewport_h);
+    }
+
+    pub fn open_prompt(&mut self, prompt: DiffPrompt) {
+        self.prompt = Some(prompt);
+        self.prompt_input.clear();
+        self.prompt_origin = self.cursor;
+        if prompt == DiffPrompt::Search {
+            self.search_error = false;
+        }
+    }
+
+    /// Esc: drop the input and, for a search, go back to where it started.
+    pub fn cancel_prompt(&mut self, viewport_h: u16) {
+        if self.prompt.take() == Some(DiffPrompt::Search) {
+            self.search = None;
+            self.search_hits.clear();
+            self.search_error = false;
+            self.jump_to(self.prompt_origin, viewport_h);
+        }
+        self.prompt_input.clear();
+    }
+
+    /// Recompile the search from `prompt_input` and jump to the first match
+    /// at or after the origin (incremental search).
+    pub fn update_search(&mut self, diff: &FileDiff, viewport_h: u16) {
+        let query = &self.prompt_input;
+        // Smart case: case-insensitive unless the query has an uppercase letter
+        let pattern = if query.chars().any(|c| c.is_uppercase()) {
+            query.clone()
+        } else {
+            format!("(?i){}", query)
+        };
+        self.search = None;
+        self.search_hits.clear();
+        self.search_error = false;
+        if query.is_empty() {
+            self.jump_to(self.prompt_origin, viewport_h);
+            return;
+        }
+        match regex::Regex::new(&pattern) {
+            Ok(re) => {
+                self.search = Some(re);
+                self.update_search_hits(diff);
+                if let Some(row) = self.find_match(self.prompt_origin, true) {
+                    self.jump_to(row, viewport_h);
+                }
+            }
+            Err(_) => self.search_error = true,
+        }
+    }
+
+    /// Match ranges of the active search in each line of `diff`.
+    fn update_search_hits(&mut self, diff: &FileDiff) {
+        let Some(ref re) = self.search else { return };
+        self.search_hits = diff.all_lines().iter()
+            .map(|line| {
+                if line.kind == DiffLineKind::HunkHeader {
+                    return Vec::new();
+                }
+                let content = line.content.trim_end_matches('\n');
+                re.find_iter(content)
+                    .filter(|m| !m.is_empty())
+                    .map(|m| {
+                        let start = content[..m.start()].chars().count();
+                        (start, start + m.as_str().chars().count())
+                    })
+                    .collect()
+            })
+            .collect();
+    }
+
+    fn row_has_match(&self, row: usize) -> bool {
+        self.row_lines(row).iter()
+            .any(|&i| self.search_hits.get(i).is_some_and(|h| !h.is_empty()))
+    }
+
+    /// First row with a match starting at `from` (inclusive), wrapping around.
+    fn find_match(&self, from: usize, forward: bool) -> Option<usize> {
+        let total = if self.split { self.split_rows.len() } else { self.search_hits.len() };
+        (0..total)
+            .map(|k| if forward { (from + k) % total } else { (from + total - k) % total })
+            .find(|&row| self.row_has_match(row))
+    }
+
+    /// `n` / `N`: next or previous row with a match.
+    pub fn next_match(&mut self, forward: bool, viewport_h: u16) {
+        if self.search.is_none() {
+            return;
+        }
+        let total = if self.split { self.split_rows.len() } else { self.search_hits.len() };
+        if total == 0 {
+            return;
+        }
+        let from = if forward { (self.cursor + 1) % total } else { (self.cursor + total - 1) % total };
+        if let Some(row) = self.find_match(from, forward) {
+            self.jump_to(row, viewport_h);
+        }
+    }
+
+    /// (index of the match under/after the cursor, total matches)
+    pub fn match_position(&self) -> (usize, usize) {
+        let count = |lines: Vec<usize>| lines.iter()
+            .map(|&i| self.search_hits.get(i).map_or(0, |h| h.len()))
+            .sum::<usize>();
+        let total = self.search_hits.iter().map(|h| h.len()).sum();
+        let before: usize = (0..self.cursor).map(|row| count(self.row_lines(row))).sum();
+        ((before + 1).min(total), total)
+    }
+
+    /// `:<n>`: first row showing new-file line `n` (or the next line after it).
+    pub fn goto_line(&mut self, diff: &FileDiff, n: u32, viewport_h: u16) -> bool {
+        let lines = diff.all_lines();
+        let rows = self.row_count(diff);
+        let found = (0..rows).find(|&row| {
+            self.row_lines(row).iter()
+                .any(|&i| lines.get(i).and_then(|l| l.new_lineno).is_some_and(|ln| ln >= n))
+        });
+        if let Some(row) = found {
+            self.jump_to(row, viewport_h);
+        }
+        found.is_some()
+    }
+
     pub fn reset(&mut self) {
         self.scroll = 0;
         self.h_scroll = 0;
@@ -129,6 +356,18 @@ impl DiffViewState {
         self.select_anchor = None;
         self.file_path = None;
         self.highlight_cache.clear();
+        self.emphasis.clear();
+        self.blame = None;
+        self.commit_view = None;
+        self.clear_search();
+    }
+
+    fn clear_search(&mut self) {
+        self.prompt = None;
+        self.prompt_input.clear();
+        self.search = None;
+        self.search_hits.clear();
+        self.search_error = false;
     }
 
     pub fn set_file(&mut self, path: &str) {
@@ -139,9 +378,19 @@ impl DiffViewState {
             self.cursor = 0;
             self.select_anchor = None;
             self.highlight_cache.clear();
+            self.emphasis.clear();
+            self.blame = None;
+            self.commit_view = None;
+            self.clear_search();
         }
     }
 
+    /// Blame info for a diff line with a new-file line number.
+    pub fn blame_for(&self, new_lineno: Option<u32>) -> Option<&BlameLine> {
+        let n = new_lineno? as usize;
+        self.blame.as_ref()?.get(n.checked_sub(1)?)
+    }
+
     /// Pre-compute syntax highlighting for all lines in a diff.
     /// Call this when the diff content changes.
     pub fn update_highlight_cache(&mut self, diff: &FileDiff) {
@@ -155,6 +404,83 @@ impl DiffViewState {
             })
             .collect();
         self.highlight_cache = syntax::highlight_diff_lines(&diff.path, &lines_for_highlight);
+        self.update_emphasis(diff);
+        self.split_rows = split_rows(diff);
+        self.update_search_hits(diff);
+    }
+
+    /// Number of cursor positions: diff lines, or split rows in side-by-side view.
+    pub fn row_count(&self, diff: &FileDiff) -> usize {
+        if self.split {
+            self.split_rows.len()
+        } else {
+            diff.total_lines()
+        }
+    }
+
+    /// Diff line indices shown on a row, new side first.
+    pub fn row_lines(&self, row: usize) -> Vec<usize> {
+        if !self.split {
+            return vec![row];
+        }
+        match self.split_rows.get(row) {
+            Some(SplitRow::Header(i)) => vec![*i],
+            Some(SplitRow::Pair { left, right }) => {
+                right.iter().chain(left.iter().filter(|l| Some(**l) != *right)).copied().collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Diff line indices under the selection, or under the cursor.
+    /// A split row contributes its new-side line only (old side for fillers).
+    pub fn selected_lines(&self) -> Vec<usize> {
+        let (start, end) = self.selection_range().unwrap_or((self.cursor, self.cursor));
+        (start..=end).filter_map(|row| self.row_lines(row).first().copied()).collect()
+    }
+
+    pub fn is_header_row(&self, diff: &FileDiff, row: usize) -> bool {
+        self.row_lines(row).first()
+            .and_then(|&i| diff.all_lines().get(i).map(|l| l.kind == DiffLineKind::HunkHeader))
+            .unwrap_or(false)
+    }
+
+    /// Split row showing diff line `line`.
+    fn split_row_of(&self, line: usize) -> Option<usize> {
+        self.split_rows.iter().position(|r| match r {
+            SplitRow::Header(i) => *i == line,
+            SplitRow::Pair { left, right } => *left == Some(line) || *right == Some(line),
+        })
+    }
+
+    /// Put the cursor on diff line `line` after the diff changed shape.
+    pub fn move_to_line(&mut self, line: usize, viewport_h: u16) {
+        let row = if self.split { self.split_row_of(line).unwrap_or(0) } else { line };
+        self.select_anchor = None;
+        self.jump_to(row, viewport_h);
+    }
+
+    /// Switch between unified and side-by-side, keeping the cursor on the same line.
+    pub fn toggle_split(&mut self) {
+        let map = |pos: usize, state: &Self| -> usize {
+            if state.split {
+                state.row_lines(pos).first().copied().unwrap_or(0)
+            } else {
+                state.split_row_of(pos).unwrap_or(0)
+            }
+        };
+        let cursor = map(self.cursor, self);
+        let anchor = self.select_anchor.map(|a| map(a, self));
+        let top = map(self.scroll as usize, self);
+        self.split = !self.split;
+        self.cursor = cursor;
+        self.select_anchor = anchor;
+        self.scroll = top as u16;
+    }
+
+    /// Recompute intra-line emphasis, e.g. after `word_diff` changes.
+    pub fn update_emphasis(&mut self, diff: &FileDiff) {
+        self.emphasis = word_diff::emphasis(diff, self.word_diff);
     }
 }
 
@@ -191,13 +517,22 @@ pub fn render_diff(
 
     let (title, stats) = match &diff {
         Some(d) => {
-            let (adds, dels) = count_changes(d);
+            let (adds, dels) = d.stats();
             let stats_str = if adds > 0 || dels > 0 {
                 format!(" +{} -{} ", adds, dels)
             } else {
                 String::new()
             };
-            (format!(" {} ", d.path), stats_str)
+            let mode = match (state.split, state.blame.is_some()) {
+                (true, _) => " \u{00b7} split",
+                (false, true) => " \u{00b7} blame",
+                (false, false) => "",
+            };
+            let settings = match state.settings.label() {
+                l if l.is_empty() => l,
+                l => format!(" \u{00b7} {}", l),
+            };
+            (format!(" {}{}{} ", d.path, mode, settings), stats_str)
         }
         None => (" diff ".to_string(), String::new()),
     };
@@ -213,6 +548,10 @@ pub fn render_diff(
         ]))
         .borders(Borders::ALL)
         .border_style(border_style);
+    let block = match search_footer(state) {
+        Some(footer) => block.title_bottom(footer),
+        None => block,
+    };
 
     let inner = block.inner(area);
     block.render(area, buf);
@@ -222,11 +561,36 @@ pub fn render_diff(
     }
 
     match diff {
+        Some(diff) if state.split => render_split_lines(diff, state, focused, inner, buf),
         Some(diff) => render_diff_lines(diff, state, focused, inner, buf),
         None => render_empty(inner, buf),
     }
 }
 
+/// "/query_" or ":123_" while typing, "/query [3/17]" while a search is active.
+fn search_footer(state: &DiffViewState) -> Option<Line<'static>> {
+    let (sigil, cursor) = match state.prompt {
+        Some(DiffPrompt::Search) => ("/", "_"),
+        Some(DiffPrompt::GotoLine) => (":", "_"),
+        None if state.search.is_some() => ("/", ""),
+        None => return None,
+    };
+    let mut spans = vec![
+        Span::styled(format!(" {}", sigil), Style::default().fg(SEARCH_FG)),
+        Span::raw(format!("{}{} ", state.prompt_input, cursor)),
+    ];
+    if state.search_error && state.prompt == Some(DiffPrompt::Search) {
+        spans.push(Span::styled("invalid regex ", Style::default().fg(DEL_FG)));
+    } else if state.search.is_some() && state.prompt != Some(DiffPrompt::GotoLine) {
+        let (current, total) = state.match_position();
+        spans.push(Span::styled(
+            format!("[{}/{}] ", current, total),
+            Style::default().fg(if total == 0 { DEL_FG } else { CTX_FG }),
+        ));
+    }
+    Some(Line::from(spans))
+}
+
 fn render_empty(area: Rect, buf: &mut Buffer) {
     let messages = [
         "",
@@ -249,9 +613,15 @@ fn render_diff_lines(diff: &FileDiff, state: &DiffViewState, focused: bool, area
     let total = all_lines.len();
     let scroll = state.scroll as usize;
 
-    // Gutter width: "NNNN │ NNNN │ " = ~13 chars.  Adapt to max line numbers.
+    // Optional blame column, then "NNNN │ NNNN │ " = ~13 chars.
+    let blame_w: u16 = if state.blame.is_some() { BLAME_W } else { 0 };
+    let gx = area.x + blame_w;
     let gutter_w: u16 = 13;
-    let content_x = area.x + gutter_w;
+    let content_x = gx + gutter_w;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
     let _content_w = area.width.saturating_sub(gutter_w + 1); // 1 for scrollbar
     let scrollbar_x = area.right().saturating_sub(1);
 
@@ -283,13 +653,27 @@ fn render_diff_lines(diff: &FileDiff, state: &DiffViewState, focused: bool, area
                     _ => (Color::Reset, CTX_FG, CTX_GUTTER_FG, ' '),
                 };
 
+                // ── Blame column ──
+                if blame_w > 0 {
+                    let (text, fg) = match state.blame_for(line.new_lineno) {
+                        Some(b) => blame_cell(b, now),
+                        None => (String::new(), BLAME_OLD),
+                    };
+                    buf.set_string(
+                        area.x,
+                        y,
+                        format!("{:<w$}", text, w = blame_w as usize),
+                        Style::default().fg(fg).bg(BLAME_BG),
+                    );
+                }
+
                 // ── Gutter: old lineno ──
                 let old_str = match line.old_lineno {
                     Some(n) => format!("{:>4}", n),
                     None => "    ".to_string(),
                 };
                 buf.set_string(
-                    area.x,
+                    gx,
                     y,
                     &old_str,
                     Style::default().fg(gutter_fg).bg(GUTTER_BG),
@@ -297,7 +681,7 @@ fn render_diff_lines(diff: &FileDiff, state: &DiffViewState, focused: bool, area
 
                 // Separator
                 buf.set_string(
-                    area.x + 4,
+                    gx + 4,
                     y,
                     " \u{2502} ",
                     Style::default().fg(GUTTER_SEP).bg(GUTTER_BG),
@@ -309,7 +693,7 @@ fn render_diff_lines(diff: &FileDiff, state: &DiffViewState, focused: bool, area
                     None => "    ".to_string(),
                 };
                 buf.set_string(
-                    area.x + 7,
+                    gx + 7,
                     y,
                     &new_str,
                     Style::default().fg(gutter_fg).bg(GUTTER_BG),
@@ -317,7 +701,7 @@ fn render_diff_lines(diff: &FileDiff, state: &DiffViewState, focused: bool, area
 
                 // Separator
                 buf.set_string(
-                    area.x + 11,
+                    gx + 11,
                     y,
                     " \u{2502}",
                     Style::default().fg(GUTTER_SEP).bg(GUTTER_BG),
@@ -328,79 +712,10 @@ fn render_diff_lines(diff: &FileDiff, state: &DiffViewState, focused: bool, area
                     .fg(line_fg)
                     .bg(line_bg)
                     .add_modifier(Modifier::BOLD);
-                buf.set_string(content_x, y, &prefix_char.to_string(), prefix_style);
+                buf.set_string(content_x, y, prefix_char.to_string(), prefix_style);
 
                 // ── Content with syntax highlighting + horizontal scroll ──
-                let content = line.content.trim_end_matches('\n');
-                let h_off = state.h_scroll as usize;
-                let content_w = (scrollbar_x.saturating_sub(content_x + 1)) as usize;
-                // Use cached highlights if available, otherwise empty
-                let empty_spans = Vec::new();
-                let spans = if line_idx < state.highlight_cache.len() {
-                    &state.highlight_cache[line_idx]
-                } else {
-                    &empty_spans
-                };
-
-                let mut cx = content_x + 1;
-                if spans.is_empty() {
-                    // Fallback: render plain with h_scroll (char-aware)
-                    let visible: String = content.chars()
-                        .skip(h_off)
-                        .take(content_w)
-                        .collect();
-                    buf.set_string(
-                        cx,
-                        y,
-                        &visible,
-                        Style::default().fg(line_fg).bg(line_bg),
-                    );
-                    cx += visible.chars().count() as u16;
-                } else {
-                    // Walk through spans using char counts, not byte counts
-                    let mut char_pos: usize = 0;
-                    for span in spans {
-                        let span_chars: usize = span.text.chars().count();
-                        let span_end = char_pos + span_chars;
-
-                        if span_end <= h_off {
-                            char_pos = span_end;
-                            continue;
-                        }
-
-                        let skip = if h_off > char_pos { h_off - char_pos } else { 0 };
-                        let rendered = (cx - (content_x + 1)) as usize;
-                        let remaining_w = content_w.saturating_sub(rendered);
-                        if remaining_w == 0 {
-                            break;
-                        }
-                        let visible: String = span.text.chars()
-                            .skip(skip)
-                            .take(remaining_w)
-                            .collect();
-
-                        let mut style = Style::default().fg(span.fg).bg(line_bg);
-                        if span.bold {
-                            style = style.add_modifier(Modifier::BOLD);
-                        }
-                        if span.italic {
-                            style = style.add_modifier(Modifier::ITALIC);
-                        }
-                        let vis_chars = visible.chars().count() as u16;
-                        buf.set_string(cx, y, &visible, style);
-                        cx += vis_chars;
-
-                        char_pos = span_end;
-                    }
-                }
-
-                // Fill remaining width with background color
-                for x in cx..scrollbar_x {
-                    if let Some(cell) = buf.cell_mut((x, y)) {
-                        cell.set_char(' ');
-                        cell.set_style(Style::default().bg(line_bg));
-                    }
-                }
+                render_content(line, line_idx, state, content_x + 1, scrollbar_x, y, line_bg, line_fg, buf);
             }
         }
 
@@ -426,6 +741,245 @@ fn render_diff_lines(diff: &FileDiff, state: &DiffViewState, focused: bool, area
     }
 }
 
+/// Pair up a diff for side-by-side display: context lines sit on both sides,
+/// a block of deletions is matched row by row with the additions after it.
+fn split_rows(diff: &FileDiff) -> Vec<SplitRow> {
+    let mut rows = Vec::new();
+    let mut dels: Vec<usize> = Vec::new();
+    let mut adds: Vec<usize> = Vec::new();
+    let flush = |rows: &mut Vec<SplitRow>, dels: &mut Vec<usize>, adds: &mut Vec<usize>| {
+        for k in 0..dels.len().max(adds.len()) {
+            rows.push(SplitRow::Pair {
+                left: dels.get(k).copied(),
+                right: adds.get(k).copied(),
+            });
+        }
+        dels.clear();
+        adds.clear();
+    };
+
+    for (i, line) in diff.all_lines().iter().enumerate() {
+        match line.kind {
+            DiffLineKind::Deletion => {
+                if !adds.is_empty() {
+                    flush(&mut rows, &mut dels, &mut adds);
+                }
+                dels.push(i);
+            }
+            DiffLineKind::Addition => adds.push(i),
+            DiffLineKind::HunkHeader => {
+                flush(&mut rows, &mut dels, &mut adds);
+                rows.push(SplitRow::Header(i));
+            }
+            DiffLineKind::Context => {
+                flush(&mut rows, &mut dels, &mut adds);
+                rows.push(SplitRow::Pair { left: Some(i), right: Some(i) });
+            }
+        }
+    }
+    flush(&mut rows, &mut dels, &mut adds);
+    rows
+}
+
+/// Side-by-side rendering: "NNNN │-content" on each half, old left, new right.
+fn render_split_lines(diff: &FileDiff, state: &DiffViewState, focused: bool, area: Rect, buf: &mut Buffer) {
+    let all_lines = diff.all_lines();
+    let total = state.split_rows.len();
+    let scroll = state.scroll as usize;
+    let scrollbar_x = area.right().saturating_sub(1);
+    let half = (scrollbar_x - area.x) / 2;
+    let left_x = area.x;
+    let right_x = area.x + half;
+
+    for row in 0..area.height {
+        let row_idx = scroll + row as usize;
+        let y = area.y + row;
+
+        match state.split_rows.get(row_idx) {
+            None => {
+                for x in area.x..area.right() {
+                    if let Some(cell) = buf.cell_mut((x, y)) {
+                        cell.set_char(' ');
+                        cell.set_style(Style::default().bg(GUTTER_BG));
+                    }
+                }
+                continue;
+            }
+            Some(SplitRow::Header(i)) => {
+                render_hunk_header(all_lines[*i], area.x, y, area.width.saturating_sub(1), buf);
+            }
+            Some(SplitRow::Pair { left, right }) => {
+                let left_line = left.map(|i| (i, all_lines[i]));
+                let right_line = right.map(|i| (i, all_lines[i]));
+                render_split_side(left_line, true, state, left_x, right_x, y, buf);
+                render_split_side(right_line, false, state, right_x, scrollbar_x, y, buf);
+            }
+        }
+
+        render_scrollbar_cell(scrollbar_x, y, row, area.height, scroll, total, buf);
+
+        let is_cursor = row_idx == state.cursor && focused;
+        let is_selected = state.selection_range()
+            .map(|(s, e)| row_idx >= s && row_idx <= e)
+            .unwrap_or(false);
+        if is_cursor || is_selected {
+            let overlay_bg = if is_cursor { CURSOR_BG } else { SELECT_BG };
+            for x in area.x..scrollbar_x {
+                if let Some(cell) = buf.cell_mut((x, y)) {
+                    cell.set_style(cell.style().bg(overlay_bg));
+                }
+            }
+        }
+    }
+}
+
+/// One half of a split row: line number gutter, +/- prefix and content,
+/// or a hatched filler when the other side has no counterpart.
+fn render_split_side(
+    line: Option<(usize, &crate::git::diff::DiffLine)>,
+    old_side: bool,
+    state: &DiffViewState,
+    x: u16,
+    right: u16,
+    y: u16,
+    buf: &mut Buffer,
+) {
+    let Some((line_idx, line)) = line else {
+        for fx in x..right {
+            if let Some(cell) = buf.cell_mut((fx, y)) {
+                cell.set_char('\u{2571}');
+                cell.set_style(Style::default().fg(FILLER_FG).bg(FILLER_BG));
+            }
+        }
+        return;
+    };
+
+    let (line_bg, line_fg, gutter_fg, prefix_char) = match line.kind {
+        DiffLineKind::Addition => (ADD_BG, ADD_FG, ADD_GUTTER_FG, '+'),
+        DiffLineKind::Deletion => (DEL_BG, DEL_FG, DEL_GUTTER_FG, '-'),
+        _ => (Color::Reset, CTX_FG, CTX_GUTTER_FG, ' '),
+    };
+    let lineno = if old_side { line.old_lineno } else { line.new_lineno };
+    let num = match lineno {
+        Some(n) => format!("{:>4}", n),
+        None => "    ".to_string(),
+    };
+    buf.set_string(x, y, &num, Style::default().fg(gutter_fg).bg(GUTTER_BG));
+    buf.set_string(x + 4, y, " \u{2502}", Style::default().fg(GUTTER_SEP).bg(GUTTER_BG));
+    buf.set_string(
+        x + 6,
+        y,
+        prefix_char.to_string(),
+        Style::default().fg(line_fg).bg(line_bg).add_modifier(Modifier::BOLD),
+    );
+    render_content(line, line_idx, state, x + 7, right, y, line_bg, line_fg, buf);
+}
+
+/// Render a line's text from `x` up to `right`: syntax colours, horizontal
+/// scroll, background fill and intra-line emphasis.
+#[allow(clippy::too_many_arguments)]
+fn render_content(
+    line: &crate::git::diff::DiffLine,
+    line_idx: usize,
+    state: &DiffViewState,
+    x: u16,
+    right: u16,
+    y: u16,
+    line_bg: Color,
+    line_fg: Color,
+    buf: &mut Buffer,
+) {
+    let content = line.content.trim_end_matches('\n');
+    let h_off = state.h_scroll as usize;
+    let content_w = right.saturating_sub(x) as usize;
+    // Use cached highlights if available, otherwise empty
+    let empty_spans = Vec::new();
+    let spans = if line_idx < state.highlight_cache.len() {
+        &state.highlight_cache[line_idx]
+    } else {
+        &empty_spans
+    };
+
+    let mut cx = x;
+    if spans.is_empty() {
+        // Fallback: render plain with h_scroll (char-aware)
+        let visible: String = content.chars()
+            .skip(h_off)
+            .take(content_w)
+            .collect();
+        buf.set_string(
+            cx,
+            y,
+            &visible,
+            Style::default().fg(line_fg).bg(line_bg),
+        );
+        cx += visible.chars().count() as u16;
+    } else {
+        // Walk through spans using char counts, not byte counts
+        let mut char_pos: usize = 0;
+        for span in spans {
+            let span_chars: usize = span.text.chars().count();
+            let span_end = char_pos + span_chars;
+
+            if span_end <= h_off {
+                char_pos = span_end;
+                continue;
+            }
+
+            let skip = h_off.saturating_sub(char_pos);
+            let rendered = (cx - x) as usize;
+            let remaining_w = content_w.saturating_sub(rendered);
+            if remaining_w == 0 {
+                break;
+            }
+            let visible: String = span.text.chars()
+                .skip(skip)
+                .take(remaining_w)
+                .collect();
+
+            let mut style = Style::default().fg(span.fg).bg(line_bg);
+            if span.bold {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            if span.italic {
+                style = style.add_modifier(Modifier::ITALIC);
+            }
+            let vis_chars = visible.chars().count() as u16;
+            buf.set_string(cx, y, &visible, style);
+            cx += vis_chars;
+
+            char_pos = span_end;
+        }
+    }
+
+    // Fill remaining width with background color
+    for fx in cx..right {
+        if let Some(cell) = buf.cell_mut((fx, y)) {
+            cell.set_char(' ');
+            cell.set_style(Style::default().bg(line_bg));
+        }
+    }
+
+    // Stronger background on the changed spans, keeping syntax colours
+    let emph_bg = if line.kind == DiffLineKind::Addition { ADD_EMPH_BG } else { DEL_EMPH_BG };
+    for &(start, end) in state.emphasis.get(line_idx).map(|r| r.as_slice()).unwrap_or(&[]) {
+        for c in start.max(h_off)..end.min(h_off + content_w) {
+            if let Some(cell) = buf.cell_mut((x + (c - h_off) as u16, y)) {
+                cell.set_style(cell.style().bg(emph_bg));
+            }
+        }
+    }
+
+    // Search matches on top of everything
+    for &(start, end) in state.search_hits.get(line_idx).map(|r| r.as_slice()).unwrap_or(&[]) {
+        for c in start.max(h_off)..end.min(h_off + content_w) {
+            if let Some(cell) = buf.cell_mut((x + (c - h_off) as u16, y)) {
+                cell.set_style(cell.style().bg(SEARCH_BG).add_modifier(Modifier::BOLD));
+            }
+        }
+    }
+}
+
 fn render_hunk_header(
     line: &crate::git::diff::DiffLine,
     x: u16,
@@ -536,15 +1090,25 @@ fn render_scrollbar_cell(
     }
 }
 
-fn count_changes(diff: &FileDiff) -> (usize, usize) {
-    let mut adds = 0;
-    let mut dels = 0;
-    for line in diff.all_lines() {
-        match line.kind {
-            DiffLineKind::Addition => adds += 1,
-            DiffLineKind::Deletion => dels += 1,
-            _ => {}
-        }
+/// Blame column text ("sha author age") and its age colour.
+fn blame_cell(blame: &BlameLine, now: i64) -> (String, Color) {
+    if blame.commit.is_none() {
+        return (format!("{} {:<12} {:>4}", blame.short_sha(), "uncommitted", ""), BLAME_UNCOMMITTED);
     }
-    (adds, dels)
+    let age = now.saturating_sub(blame.time).max(0) as u64;
+    let fg = match age {
+        a if a < 86400 => BLAME_DAY,
+        a if a < 86400 * 7 => BLAME_WEEK,
+        a if a < 86400 * 30 => BLAME_MONTH,
+        a if a < 86400 * 365 => BLAME_YEAR,
+        _ => BLAME_OLD,
+    };
+    let author: String = blame.author.chars().take(12).collect();
+    let text = format!(
+        "{} {:<12} {:>4}",
+        blame.short_sha(),
+        author,
+        crate::ui::file_panel::format_age(age),
+    );
+    (text, fg)
 }
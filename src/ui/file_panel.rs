@@ -18,34 +18,38 @@ impl<'a> FilePanelWidget<'a> {
         Self { state, focused }
     }
 
-    fn format_size(size: u64) -> String {
-        if size < 1024 {
-            format!("{}B", size)
-        } else if size < 1024 * 1024 {
-            format!("{:.1}K", size as f64 / 1024.0)
-        } else if size < 1024 * 1024 * 1024 {
-            format!("{:.1}M", size as f64 / (1024.0 * 1024.0))
-        } else {
-            format!("{:.1}G", size as f64 / (1024.0 * 1024.0 * 1024.0))
-        }
+    fn format_time(time: &std::time::SystemTime) -> String {
+        format_age(time.elapsed().unwrap_or_default().as_secs())
     }
+}
 
-    fn format_time(time: &std::time::SystemTime) -> String {
-        let duration = time.elapsed().unwrap_or_default();
-        let secs = duration.as_secs();
-        if secs < 60 {
-            "now".to_string()
-        } else if secs < 3600 {
-            format!("{}m", secs / 60)
-        } else if secs < 86400 {
-            format!("{}h", secs / 3600)
-        } else if secs < 86400 * 30 {
-            format!("{}d", secs / 86400)
-        } else if secs < 86400 * 365 {
-            format!("{}mo", secs / (86400 * 30))
-        } else {
-            format!("{}y", secs / (86400 * 365))
-        }
+/// Compact size like "512B", "1.5K", "3.2M".
+pub fn format_size(size: u64) -> String {
+    if size < 1024 {
+        format!("{}B", size)
+    } else if size < 1024 * 1024 {
+        format!("{:.1}K", size as f64 / 1024.0)
+    } else if size < 1024 * 1024 * 1024 {
+        format!("{:.1}M", size as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1}G", size as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
+/// Compact age like "5m", "3d", "2y".
+pub fn format_age(secs: u64) -> String {
+    if secs < 60 {
+        "now".to_string()
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else if secs < 86400 {
+        format!("{}h", secs / 3600)
+    } else if secs < 86400 * 30 {
+        format!("{}d", secs / 86400)
+    } else if secs < 86400 * 365 {
+        format!("{}mo", secs / (86400 * 30))
+    } else {
+        format!("{}y", secs / (86400 * 365))
     }
 }
 
@@ -102,11 +106,14 @@ impl Widget for FilePanelWidget<'_> {
 
             let entry = &self.state.entries[idx];
             let is_cursor = idx == self.state.cursor;
+            let is_marked = self.state.is_marked(&entry.path);
 
             // Icon + name
             let icon = if entry.is_dir { "/" } else { " " };
 
-            let name_color = if entry.is_dir {
+            let name_color = if is_marked {
+                Color::Yellow
+            } else if entry.is_dir {
                 Color::Cyan
             } else {
                 Color::White
@@ -122,7 +129,7 @@ impl Widget for FilePanelWidget<'_> {
             let size_str = if entry.is_dir {
                 "<DIR>".to_string()
             } else {
-                Self::format_size(entry.size)
+                format_size(entry.size)
             };
 
             let time_str = entry
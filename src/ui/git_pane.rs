@@ -5,7 +5,7 @@ use ratatui::{
 };
 
 use crate::git::diff::FileDiff;
-use crate::git::status::FileStatus;
+use crate::git::status::{BranchInfo, FileStatus};
 use crate::ui::diff_view::{self, DiffViewState};
 use crate::ui::layout::AppLayout;
 use crate::ui::status_list::{StatusListState, StatusListWidget};
@@ -14,26 +14,32 @@ use crate::app::Focus;
 pub struct GitPane<'a> {
     pub files: &'a [FileStatus],
     pub diff: Option<&'a FileDiff>,
-    pub branch: &'a str,
+    pub branch: &'a BranchInfo,
+    /// Submodule path when drilled into one
+    pub submodule: Option<&'a str>,
+    /// Compare-mode label while the list shows a revision range
+    pub compare: Option<&'a str>,
     pub focus: Focus,
     pub status_state: &'a mut StatusListState,
     pub diff_state: &'a DiffViewState,
 }
 
 impl Widget for GitPane<'_> {
-    fn render(mut self, area: Rect, buf: &mut Buffer) {
+    fn render(self, area: Rect, buf: &mut Buffer) {
         let (status_area, diff_area) = AppLayout::split_right(area);
 
         let status_widget = StatusListWidget::new(
             self.files,
             self.focus == Focus::GitStatus,
             self.branch,
-        );
+            self.submodule,
+        )
+        .compare(self.compare);
         ratatui::widgets::StatefulWidget::render(
             status_widget,
             status_area,
             buf,
-            &mut self.status_state,
+            self.status_state,
         );
 
         diff_view::render_diff(
@@ -5,7 +5,10 @@ pub mod status_list;
 pub mod diff_view;
 pub mod command_bar;
 pub mod prompt_dialog;
+pub mod picker;
 pub mod syntax;
 pub mod tab_bar;
 pub mod file_panel;
 pub mod file_browser_pane;
+pub mod basket_panel;
+pub mod text_input;
@@ -0,0 +1,156 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, StatefulWidget, Widget},
+};
+
+/// What a confirmed picker selection should do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PickerKind {
+    PushRemote,
+    Branch,
+    Undo,
+    Submodule,
+    Compare,
+    ApplyPatch,
+    Tag,
+    Basket,
+    PromptTemplate,
+    FileRef,
+    Checkpoint,
+    CheckpointFile,
+}
+
+impl PickerKind {
+    /// Extra key accepted by this picker besides Enter/Esc.
+    pub fn extra_hint(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            PickerKind::Branch => Some(("d", "delete")),
+            PickerKind::Submodule => Some(("u", "update")),
+            PickerKind::Tag => Some(("n/d/p", "new/delete/push")),
+            PickerKind::Basket => Some(("d", "delete")),
+            PickerKind::FileRef => Some(("b", "file browser")),
+            PickerKind::Checkpoint => Some(("Spc/r/f/d", "mark/restore/files/delete")),
+            _ => None,
+        }
+    }
+}
+
+/// Modal single-choice list (remotes, branches, ...).
+pub struct PickerState {
+    pub visible: bool,
+    pub kind: PickerKind,
+    pub title: String,
+    pub items: Vec<String>,
+    pub list_state: ListState,
+}
+
+impl PickerState {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            kind: PickerKind::PushRemote,
+            title: String::new(),
+            items: Vec::new(),
+            list_state: ListState::default(),
+        }
+    }
+
+    pub fn open(&mut self, kind: PickerKind, title: &str, items: Vec<String>) {
+        self.visible = true;
+        self.kind = kind;
+        self.title = title.to_string();
+        self.items = items;
+        self.list_state.select(Some(0));
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+        self.items.clear();
+        self.list_state.select(None);
+    }
+
+    pub fn move_up(&mut self) {
+        let i = self.list_state.selected().unwrap_or(0);
+        self.list_state.select(Some(i.saturating_sub(1)));
+    }
+
+    pub fn move_down(&mut self) {
+        let i = self.list_state.selected().unwrap_or(0);
+        if i + 1 < self.items.len() {
+            self.list_state.select(Some(i + 1));
+        }
+    }
+
+    pub fn selected_index(&self) -> Option<usize> {
+        self.list_state.selected().filter(|&i| i < self.items.len())
+    }
+
+    pub fn selected(&self) -> Option<&str> {
+        self.list_state.selected()
+            .and_then(|i| self.items.get(i))
+            .map(|s| s.as_str())
+    }
+}
+
+pub struct Picker<'a> {
+    state: &'a mut PickerState,
+}
+
+impl<'a> Picker<'a> {
+    pub fn new(state: &'a mut PickerState) -> Self {
+        Self { state }
+    }
+}
+
+impl Widget for Picker<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if !self.state.visible {
+            return;
+        }
+
+        // Center the picker, sized to its contents
+        let longest = self.state.items.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+        let width = (longest as u16 + 8).max(self.state.title.len() as u16 + 4).max(30).min(area.width);
+        let height = (self.state.items.len().max(1) as u16 + 2).min(area.height);
+        let x = (area.width - width) / 2 + area.x;
+        let y = (area.height - height) / 2 + area.y;
+        let dialog_area = Rect::new(x, y, width, height);
+
+        Clear.render(dialog_area, buf);
+
+        let mut hints = vec![
+            Span::styled(" Enter", Style::default().fg(Color::Yellow)),
+            Span::raw(" select  "),
+        ];
+        if let Some((key, desc)) = self.state.kind.extra_hint() {
+            hints.push(Span::styled(key, Style::default().fg(Color::Yellow)));
+            hints.push(Span::raw(format!(" {}  ", desc)));
+        }
+        hints.push(Span::styled("Esc", Style::default().fg(Color::Yellow)));
+        hints.push(Span::raw(" cancel "));
+
+        let block = Block::default()
+            .title(format!(" {} ", self.state.title))
+            .title_bottom(Line::from(hints))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+
+        let items: Vec<ListItem> = self.state.items.iter()
+            .map(|s| ListItem::new(Line::from(s.as_str())))
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▶ ");
+
+        StatefulWidget::render(list, dialog_area, buf, &mut self.state.list_state);
+    }
+}
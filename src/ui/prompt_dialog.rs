@@ -1,167 +1,387 @@
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Color, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph, Widget},
 };
 
 use std::path::PathBuf;
 
+use crate::git::context_size::ContextSize;
+use crate::history::History;
+
+use super::text_input::TextInput;
+
+/// Input rows shown before the input scrolls.
+const MAX_INPUT_ROWS: usize = 10;
+
+/// Context-size warnings listed before the rest are summarised.
+const MAX_WARNING_ROWS: usize = 3;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PromptMode {
     SendToClaude,
     Commit,
     CommitAndPush,
     CreateBranch,
+    CompareRevs,
+    ExportPatch,
+    ConfirmForcePush,
+    ConfirmDiscard,
+    ConfirmStashDrop,
+    ConfirmDeleteBranch,
+    CreateTag,
+    ConfirmDeleteTag,
+    SaveBasket,
     FBRename,
     FBMkdir,
     FBConfirmDelete,
 }
 
+/// Ctrl-R search through the prompt history.
+pub struct HistorySearch {
+    pub query: String,
+    /// Matching history indices, best first
+    pub results: Vec<usize>,
+    /// Result currently shown in the input
+    pub shown: usize,
+}
+
 pub struct PromptDialogState {
     pub visible: bool,
-    pub input: String,
-    pub cursor_pos: usize,
+    pub input: TextInput,
     pub files: Vec<String>,
     pub mode: PromptMode,
+    /// Waiting for Claude to write a drafted commit message
+    pub drafting: bool,
     // File browser operation paths
     pub fb_delete_path: Option<PathBuf>,
     pub fb_rename_path: Option<PathBuf>,
     pub fb_mkdir_parent: Option<PathBuf>,
+    /// Patch text waiting for a file name
+    pub export_patch: Option<String>,
+    /// Commit a new tag goes on (HEAD when None)
+    pub tag_target: Option<String>,
+    /// Text quoted below the prompt and file references
+    pub quotes: Vec<String>,
+    /// Sending the context basket, which is emptied once sent
+    pub basket: bool,
+    /// History entry shown while recalling with Up/Down
+    pub history_pos: Option<usize>,
+    /// Input and files from before history was recalled
+    history_draft: Option<(String, Vec<String>)>,
+    pub history_search: Option<HistorySearch>,
+    /// Size of the files being sent; None until measured for the current files
+    pub context: Option<ContextSize>,
 }
 
 impl PromptDialogState {
     pub fn new() -> Self {
         Self {
             visible: false,
-            input: String::new(),
-            cursor_pos: 0,
+            input: TextInput::new(),
             files: Vec::new(),
             mode: PromptMode::SendToClaude,
+            drafting: false,
             fb_delete_path: None,
             fb_rename_path: None,
             fb_mkdir_parent: None,
+            export_patch: None,
+            tag_target: None,
+            quotes: Vec::new(),
+            basket: false,
+            history_pos: None,
+            history_draft: None,
+            history_search: None,
+            context: None,
         }
     }
 
     pub fn open_send(&mut self, files: Vec<String>) {
         self.visible = true;
-        self.input.clear();
-        self.cursor_pos = 0;
+        self.input.reset("");
         self.files = files;
         self.mode = PromptMode::SendToClaude;
+        self.context = None;
+    }
+
+    /// Send dialog for the context basket's references and quoted selections.
+    pub fn open_send_basket(&mut self, files: Vec<String>, quotes: Vec<String>) {
+        self.open_send(files);
+        self.set_quotes(quotes);
+        self.basket = true;
+    }
+
+    pub fn open_save_basket(&mut self, name: Option<&str>) {
+        self.visible = true;
+        self.input.reset(name.unwrap_or_default());
+        self.files.clear();
+        self.mode = PromptMode::SaveBasket;
     }
 
     pub fn open_commit(&mut self) {
         self.visible = true;
-        self.input.clear();
-        self.cursor_pos = 0;
+        self.input.reset("");
         self.files.clear();
         self.mode = PromptMode::Commit;
     }
 
     pub fn open_commit_and_push(&mut self) {
         self.visible = true;
-        self.input.clear();
-        self.cursor_pos = 0;
+        self.input.reset("");
         self.files.clear();
         self.mode = PromptMode::CommitAndPush;
     }
 
     pub fn open_create_branch(&mut self) {
         self.visible = true;
-        self.input.clear();
-        self.cursor_pos = 0;
+        self.input.reset("");
         self.files.clear();
         self.mode = PromptMode::CreateBranch;
     }
 
+    /// Ask where to write `patch`, suggesting `default_name`.
+    pub fn open_export_patch(&mut self, patch: String, default_name: String) {
+        self.visible = true;
+        self.input.reset(default_name);
+        self.files.clear();
+        self.export_patch = Some(patch);
+        self.mode = PromptMode::ExportPatch;
+    }
+
+    /// New tag on `target` (a commit SHA), or on HEAD.
+    pub fn open_create_tag(&mut self, target: Option<String>) {
+        self.visible = true;
+        self.input.reset("");
+        self.files.clear();
+        self.tag_target = target;
+        self.mode = PromptMode::CreateTag;
+    }
+
+    pub fn open_compare_revs(&mut self) {
+        self.visible = true;
+        self.input.reset("");
+        self.files.clear();
+        self.mode = PromptMode::CompareRevs;
+    }
+
+    /// Open a "type 'yes'" confirmation for `mode` about `subject`.
+    pub fn open_confirm(&mut self, mode: PromptMode, subject: String) {
+        self.visible = true;
+        self.input.reset("");
+        self.files = vec![subject];
+        self.mode = mode;
+    }
+
+    pub fn is_confirm(&self) -> bool {
+        matches!(self.mode,
+            PromptMode::ConfirmForcePush
+                | PromptMode::ConfirmDiscard
+                | PromptMode::ConfirmStashDrop
+                | PromptMode::ConfirmDeleteBranch
+                | PromptMode::ConfirmDeleteTag
+                | PromptMode::FBConfirmDelete
+        )
+    }
+
+    /// Whether the typed confirmation accepts the action.
+    pub fn confirmed(&self) -> bool {
+        self.input.text().to_lowercase() == "yes"
+    }
+
     pub fn open_fb_confirm_delete(&mut self, name: String) {
         self.visible = true;
-        self.input.clear();
-        self.cursor_pos = 0;
+        self.input.reset("");
         self.files = vec![name];
         self.mode = PromptMode::FBConfirmDelete;
     }
 
     pub fn open_fb_rename(&mut self, current_name: String) {
         self.visible = true;
-        self.input = current_name.clone();
-        self.cursor_pos = current_name.len();
+        self.input.reset(current_name);
         self.files.clear();
         self.mode = PromptMode::FBRename;
     }
 
     pub fn open_fb_mkdir(&mut self) {
         self.visible = true;
-        self.input.clear();
-        self.cursor_pos = 0;
+        self.input.reset("");
         self.files.clear();
         self.mode = PromptMode::FBMkdir;
     }
 
     pub fn close(&mut self) {
         self.visible = false;
-        self.input.clear();
-        self.cursor_pos = 0;
+        self.input.reset("");
         self.files.clear();
+        self.drafting = false;
         self.fb_delete_path = None;
         self.fb_rename_path = None;
         self.fb_mkdir_parent = None;
+        self.export_patch = None;
+        self.tag_target = None;
+        self.quotes.clear();
+        self.basket = false;
+        self.history_pos = None;
+        self.history_draft = None;
+        self.history_search = None;
+    }
+
+    fn show_history(&mut self, history: &History, index: usize) {
+        if self.history_draft.is_none() {
+            self.history_draft = Some((self.input.text().to_string(), self.files.clone()));
+        }
+        if let Some(entry) = history.entries.get(index) {
+            self.set_input(entry.prompt.clone());
+            self.set_files(entry.files.clone());
+        }
     }
 
-    pub fn insert_char(&mut self, c: char) {
-        self.input.insert(self.cursor_pos, c);
-        self.cursor_pos += c.len_utf8();
+    fn set_files(&mut self, files: Vec<String>) {
+        if files != self.files {
+            self.files = files;
+            self.context = None;
+        }
     }
 
-    pub fn delete_char(&mut self) {
-        if self.cursor_pos > 0 {
-            let prev = self.input[..self.cursor_pos]
-                .chars()
-                .last()
-                .map(|c| c.len_utf8())
-                .unwrap_or(0);
-            self.cursor_pos -= prev;
-            self.input.remove(self.cursor_pos);
+    /// Replace the quoted selections, re-measuring the context when they change.
+    pub fn set_quotes(&mut self, quotes: Vec<String>) {
+        if quotes != self.quotes {
+            self.quotes = quotes;
+            self.context = None;
         }
     }
 
-    pub fn move_cursor_left(&mut self) {
-        if self.cursor_pos > 0 {
-            let prev = self.input[..self.cursor_pos]
-                .chars()
-                .last()
-                .map(|c| c.len_utf8())
-                .unwrap_or(0);
-            self.cursor_pos -= prev;
+    fn restore_draft(&mut self) {
+        if let Some((input, files)) = self.history_draft.take() {
+            self.set_input(input);
+            self.set_files(files);
         }
+        self.history_pos = None;
+    }
+
+    /// Up: the previous (older) prompt and its files.
+    pub fn history_prev(&mut self, history: &History) {
+        let index = match self.history_pos {
+            None if history.entries.is_empty() => return,
+            None => history.entries.len() - 1,
+            Some(0) => return,
+            Some(i) => i - 1,
+        };
+        self.show_history(history, index);
+        self.history_pos = Some(index);
     }
 
-    pub fn move_cursor_right(&mut self) {
-        if self.cursor_pos < self.input.len() {
-            let next = self.input[self.cursor_pos..]
-                .chars()
-                .next()
-                .map(|c| c.len_utf8())
-                .unwrap_or(0);
-            self.cursor_pos += next;
+    /// Down: the next (newer) prompt, then back to what was being typed.
+    pub fn history_next(&mut self, history: &History) {
+        match self.history_pos {
+            None => {}
+            Some(i) if i + 1 < history.entries.len() => {
+                self.show_history(history, i + 1);
+                self.history_pos = Some(i + 1);
+            }
+            Some(_) => self.restore_draft(),
         }
     }
 
+    pub fn start_search(&mut self, history: &History) {
+        self.history_search = Some(HistorySearch { query: String::new(), results: Vec::new(), shown: 0 });
+        self.update_search(history);
+    }
+
+    /// Re-run the search after the query changed and show the best match.
+    pub fn update_search(&mut self, history: &History) {
+        let Some(ref mut search) = self.history_search else { return };
+        search.results = history.search(&search.query);
+        search.shown = 0;
+        self.show_search_result(history);
+    }
+
+    /// Ctrl-R again: the next match.
+    pub fn next_search_result(&mut self, history: &History) {
+        let Some(ref mut search) = self.history_search else { return };
+        if !search.results.is_empty() {
+            search.shown = (search.shown + 1) % search.results.len();
+        }
+        self.show_search_result(history);
+    }
+
+    fn show_search_result(&mut self, history: &History) {
+        let result = self.history_search.as_ref()
+            .and_then(|s| s.results.get(s.shown).copied());
+        match result {
+            Some(index) => {
+                self.show_history(history, index);
+                self.history_pos = Some(index);
+            }
+            None => self.restore_draft(),
+        }
+    }
+
+    /// Enter: keep the match for editing.
+    pub fn accept_search(&mut self) {
+        self.history_search = None;
+    }
+
+    /// Esc: back to what was being typed.
+    pub fn cancel_search(&mut self) {
+        self.history_search = None;
+        self.restore_draft();
+    }
+
+    /// Replace the input (undoably), leaving the cursor at the end.
+    pub fn set_input(&mut self, text: String) {
+        self.input.replace(text);
+    }
+
+    /// Replace the input with a message drafted by Claude.
+    pub fn fill_draft(&mut self, text: String) {
+        self.input.replace(text);
+        self.drafting = false;
+    }
+
+    /// Modes whose input may span lines (Shift+Enter inserts a newline).
+    pub fn is_multiline(&self) -> bool {
+        matches!(self.mode,
+            PromptMode::SendToClaude | PromptMode::Commit | PromptMode::CommitAndPush | PromptMode::CreateTag
+        )
+    }
+
+    pub fn is_commit(&self) -> bool {
+        matches!(self.mode, PromptMode::Commit | PromptMode::CommitAndPush)
+    }
+
+    /// Prompt followed by the file references it doesn't already contain
+    /// (a template may have placed them with `{files}`).
     pub fn build_command(&self) -> String {
+        let input = self.input.text();
+        // References a template already put in the prompt are not repeated
+        let typed: Vec<&str> = input.split_whitespace().collect();
         let file_refs: Vec<String> = self.files.iter()
             .map(|f| format!("@{}", f))
+            .filter(|r| !typed.contains(&r.as_str()))
             .collect();
         let files_str = file_refs.join(" ");
 
-        if self.input.is_empty() {
+        if input.is_empty() {
             format!("{}\n", files_str)
+        } else if files_str.is_empty() {
+            format!("{}\n", input)
         } else {
-            format!("{} {}\n", self.input, files_str)
+            format!("{} {}\n", input, files_str)
         }
     }
+
+    /// Multi-line message for prompts with quotes: the prompt and references,
+    /// then each quote in its own fenced block. Meant to be pasted.
+    pub fn build_message(&self) -> String {
+        let mut msg = self.build_command().trim_end().to_string();
+        for quote in &self.quotes {
+            msg.push_str(&format!("\n\n```\n{}\n```", quote.trim_end()));
+        }
+        msg.trim_start().to_string()
+    }
 }
 
 pub struct PromptDialog<'a> {
@@ -180,25 +400,49 @@ impl Widget for PromptDialog<'_> {
             return;
         }
 
-        let (title, action_label) = match self.state.mode {
+        let (title, action_label): (&str, &str) = match self.state.mode {
             PromptMode::SendToClaude => (" Send to Claude ", "send"),
             PromptMode::Commit => (" Commit ", "commit"),
             PromptMode::CommitAndPush => (" Commit & Push ", "commit+push"),
             PromptMode::CreateBranch => (" New Branch ", "create"),
+            PromptMode::CompareRevs => (" Compare Revisions ", "compare"),
+            PromptMode::ExportPatch => (" Export Patch ", "write"),
+            PromptMode::ConfirmForcePush => (" Confirm Force Push ", "force-push"),
+            PromptMode::ConfirmDiscard => (" Confirm Discard ", "discard"),
+            PromptMode::ConfirmStashDrop => (" Confirm Stash Drop ", "drop"),
+            PromptMode::ConfirmDeleteBranch => (" Confirm Branch Delete ", "delete"),
+            PromptMode::CreateTag => (" New Tag ", "tag"),
+            PromptMode::ConfirmDeleteTag => (" Confirm Tag Delete ", "delete"),
+            PromptMode::SaveBasket => (" Save Basket ", "save"),
             PromptMode::FBRename => (" Rename ", "rename"),
             PromptMode::FBMkdir => (" New Directory ", "create"),
             PromptMode::FBConfirmDelete => (" Confirm Delete ", "delete"),
         };
 
-        // Center the dialog
-        let dialog_width = area.width.min(60);
-        let dialog_height = 8u16.min(area.height);
+        let has_files = !self.state.files.is_empty() || !self.state.quotes.is_empty();
+        let context_lines = match self.state.context {
+            Some(ref context) if has_files && self.state.mode == PromptMode::SendToClaude => context_lines(context),
+            _ => Vec::new(),
+        };
+        let label_rows: u16 = if has_files { 2 + context_lines.len() as u16 } else { 1 };
+        let prompt_prefix = if self.state.is_confirm() { "Type 'yes': " } else { "> " };
+
+        // Center the dialog, growing it with the input
+        let dialog_width = area.width.min(if self.state.is_multiline() { 80 } else { 60 });
+        let text_width = (dialog_width as usize).saturating_sub(2 + prompt_prefix.len());
+        let (input_lines, cursor_row) = self.state.input.render_lines(text_width);
+        let input_rows = input_lines.len().clamp(1, MAX_INPUT_ROWS);
+        let dialog_height = (label_rows + input_rows as u16 + 3).max(8).min(area.height);
         let x = (area.width - dialog_width) / 2 + area.x;
         let y = (area.height - dialog_height) / 2 + area.y;
         let dialog_area = Rect::new(x, y, dialog_width, dialog_height);
 
         Clear.render(dialog_area, buf);
 
+        let title = match (self.state.history_pos, &self.state.history_search) {
+            (Some(i), None) => format!("{}(history {}) ", title, i + 1),
+            _ => title.to_string(),
+        };
         let block = Block::default()
             .title(title)
             .borders(Borders::ALL)
@@ -207,27 +451,36 @@ impl Widget for PromptDialog<'_> {
         let inner = block.inner(dialog_area);
         block.render(dialog_area, buf);
 
-        let has_files = !self.state.files.is_empty();
-        let constraints = if has_files {
-            vec![
-                Constraint::Length(2),
-                Constraint::Length(1),
-                Constraint::Min(1),
-            ]
-        } else {
-            vec![
-                Constraint::Length(1),
-                Constraint::Length(1),
-                Constraint::Min(1),
-            ]
-        };
+        let constraints = vec![
+            Constraint::Length(label_rows),
+            Constraint::Length(input_rows as u16),
+            Constraint::Min(1),
+        ];
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(constraints)
             .split(inner);
 
-        if has_files {
+        if let Some(ref search) = self.state.history_search {
+            let status = if search.results.is_empty() {
+                "  (no match)".to_string()
+            } else {
+                format!("  ({}/{})", search.shown + 1, search.results.len())
+            };
+            let mut spans = vec![
+                Span::styled("History search: ", Style::default().fg(Color::DarkGray)),
+                Span::styled(search.query.clone(), Style::default().fg(Color::Yellow)),
+                Span::styled(status, Style::default().fg(Color::DarkGray)),
+            ];
+            if !self.state.files.is_empty() {
+                spans.push(Span::styled(
+                    format!("  @{}", self.state.files.join(" @")),
+                    Style::default().fg(Color::Green),
+                ));
+            }
+            Paragraph::new(Line::from(spans)).render(chunks[0], buf);
+        } else if has_files {
             let label = match self.state.mode {
                 PromptMode::FBConfirmDelete => {
                     let files_text = self.state.files.join(", ");
@@ -236,22 +489,55 @@ impl Widget for PromptDialog<'_> {
                         Span::styled(files_text, Style::default().fg(Color::Yellow)),
                     ])
                 }
+                PromptMode::ConfirmForcePush
+                | PromptMode::ConfirmDiscard
+                | PromptMode::ConfirmStashDrop
+                | PromptMode::ConfirmDeleteBranch
+                | PromptMode::ConfirmDeleteTag => {
+                    let verb = match self.state.mode {
+                        PromptMode::ConfirmForcePush => "Force-push (with lease): ",
+                        PromptMode::ConfirmDiscard => "Discard changes (undo with u): ",
+                        PromptMode::ConfirmStashDrop => "Drop stash (undo with u): ",
+                        PromptMode::ConfirmDeleteTag => "Delete tag (undo with u): ",
+                        _ => "Delete branch (undo with u): ",
+                    };
+                    Line::from(vec![
+                        Span::styled(verb, Style::default().fg(Color::Red)),
+                        Span::styled(self.state.files.join(", "), Style::default().fg(Color::Yellow)),
+                    ])
+                }
                 _ => {
                     let files_text = self.state.files.iter()
                         .map(|f| format!("@{}", f))
                         .collect::<Vec<_>>()
                         .join(" ");
-                    Line::from(vec![
+                    let mut spans = vec![
                         Span::styled("Files: ", Style::default().fg(Color::DarkGray)),
                         Span::styled(files_text, Style::default().fg(Color::Green)),
-                    ])
+                    ];
+                    if !self.state.quotes.is_empty() {
+                        spans.push(Span::styled(
+                            format!(" + {} quoted selection(s)", self.state.quotes.len()),
+                            Style::default().fg(Color::Gray),
+                        ));
+                    }
+                    Line::from(spans)
                 }
             };
-            Paragraph::new(label).render(chunks[0], buf);
+            let mut lines = vec![label];
+            lines.extend(context_lines);
+            Paragraph::new(lines).render(chunks[0], buf);
         } else {
             let placeholder = match self.state.mode {
+                PromptMode::Commit | PromptMode::CommitAndPush if self.state.drafting => {
+                    "Waiting for Claude to draft a message..."
+                }
                 PromptMode::Commit | PromptMode::CommitAndPush => "Enter commit message:",
                 PromptMode::CreateBranch => "Enter branch name:",
+                PromptMode::CompareRevs => "Revisions (A..B, or A...B from their merge-base):",
+                PromptMode::ExportPatch => "Write patch to (relative to the repository):",
+                PromptMode::CreateTag => "Tag name, then an optional message (makes it annotated):",
+                PromptMode::SaveBasket => "Save the basket as:",
                 PromptMode::FBRename => "Enter new name:",
                 PromptMode::FBMkdir => "Enter directory name:",
                 PromptMode::FBConfirmDelete => "Type 'yes' to confirm:",
@@ -261,28 +547,80 @@ impl Widget for PromptDialog<'_> {
             Paragraph::new(label).render(chunks[0], buf);
         }
 
-        // Input prompt
-        let prompt_prefix = match self.state.mode {
-            PromptMode::FBConfirmDelete => "Type 'yes': ",
-            _ => "> ",
-        };
-
-        let input_line = Line::from(vec![
-            Span::styled(prompt_prefix, Style::default().fg(Color::Cyan)),
-            Span::raw(&self.state.input),
-            Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
-        ]);
-        Paragraph::new(input_line).render(chunks[1], buf);
+        // Input, scrolled to keep the cursor row visible
+        let scroll = (cursor_row + 1).saturating_sub(input_rows);
+        let indent = " ".repeat(prompt_prefix.len());
+        let rows: Vec<Line> = input_lines.into_iter()
+            .enumerate()
+            .skip(scroll)
+            .take(input_rows)
+            .map(|(n, line)| {
+                let prefix = if n == 0 { prompt_prefix.to_string() } else { indent.clone() };
+                let mut spans = vec![Span::styled(prefix, Style::default().fg(Color::Cyan))];
+                spans.extend(line.spans);
+                Line::from(spans)
+            })
+            .collect();
+        Paragraph::new(rows).render(chunks[1], buf);
 
         // Help
-        let help = Line::from(vec![
+        if self.state.history_search.is_some() {
+            let help = Line::from(vec![
+                Span::styled("C-r", Style::default().fg(Color::Yellow)),
+                Span::raw(" next match  "),
+                Span::styled("Enter", Style::default().fg(Color::Yellow)),
+                Span::raw(" edit  "),
+                Span::styled("Esc", Style::default().fg(Color::Yellow)),
+                Span::raw(" back"),
+            ]);
+            Paragraph::new(help)
+                .style(Style::default().fg(Color::DarkGray))
+                .render(chunks[2], buf);
+            return;
+        }
+        let mut help_spans = vec![
             Span::styled("Enter", Style::default().fg(Color::Yellow)),
             Span::raw(format!(" {}  ", action_label)),
             Span::styled("Esc", Style::default().fg(Color::Yellow)),
             Span::raw(" cancel"),
-        ]);
+        ];
+        if self.state.is_multiline() {
+            help_spans.push(Span::styled("  S-Enter", Style::default().fg(Color::Yellow)));
+            help_spans.push(Span::raw(" newline"));
+        }
+        if self.state.is_commit() {
+            help_spans.push(Span::styled("  C-g", Style::default().fg(Color::Yellow)));
+            help_spans.push(Span::raw(" draft with Claude"));
+        }
+        if self.state.mode == PromptMode::SendToClaude {
+            help_spans.push(Span::styled("  C-t", Style::default().fg(Color::Yellow)));
+            help_spans.push(Span::raw(" templates"));
+            help_spans.push(Span::styled("  ↑/↓ C-r", Style::default().fg(Color::Yellow)));
+            help_spans.push(Span::raw(" history"));
+        }
+        let help = Line::from(help_spans);
         Paragraph::new(help)
             .style(Style::default().fg(Color::DarkGray))
             .render(chunks[2], buf);
     }
 }
+
+/// Size summary and warnings shown under the send dialog's files.
+fn context_lines(context: &ContextSize) -> Vec<Line<'static>> {
+    let color = if context.warnings.is_empty() { Color::DarkGray } else { Color::Yellow };
+    let mut lines = vec![Line::from(vec![
+        Span::styled("Context: ", Style::default().fg(Color::DarkGray)),
+        Span::styled(context.summary(), Style::default().fg(color)),
+    ])];
+    let shown = if context.warnings.len() > MAX_WARNING_ROWS { MAX_WARNING_ROWS - 1 } else { MAX_WARNING_ROWS };
+    lines.extend(context.warnings.iter().take(shown).map(|w| {
+        Line::from(Span::styled(format!("  ⚠ {}", w), Style::default().fg(Color::Yellow)))
+    }));
+    if context.warnings.len() > shown {
+        lines.push(Line::from(Span::styled(
+            format!("  ⚠ and {} more", context.warnings.len() - shown),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    lines
+}
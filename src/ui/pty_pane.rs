@@ -5,17 +5,20 @@ use ratatui::{
 };
 
 use crate::app::PtySelection;
+use crate::pty::links::Link;
 use crate::pty::terminal_emulator::TerminalEmulator;
 
 pub struct PtyPane<'a> {
     emulator: &'a TerminalEmulator,
     focused: bool,
     selection: &'a PtySelection,
+    /// Links to underline (hovered, or all while Ctrl is held)
+    links: &'a [Link],
 }
 
 impl<'a> PtyPane<'a> {
-    pub fn new(emulator: &'a TerminalEmulator, focused: bool, selection: &'a PtySelection) -> Self {
-        Self { emulator, focused, selection }
+    pub fn new(emulator: &'a TerminalEmulator, focused: bool, selection: &'a PtySelection, links: &'a [Link]) -> Self {
+        Self { emulator, focused, selection, links }
     }
 }
 
@@ -31,6 +34,6 @@ impl Widget for PtyPane<'_> {
 
         let inner = block.inner(area);
         block.render(area, buf);
-        self.emulator.render(inner, buf, self.focused, self.selection);
+        self.emulator.render(inner, buf, self.focused, self.selection, self.links);
     }
 }
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -6,12 +8,79 @@ use ratatui::{
     widgets::{Block, Borders, List, ListItem, ListState, StatefulWidget},
 };
 
-use crate::git::status::FileStatus;
+use crate::git::status::{BranchInfo, FileStatus, FileStatusKind, StageState};
+
+/// Status groups shown in tree view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    Conflicted,
+    Staged,
+    Unstaged,
+    Untracked,
+}
+
+impl Section {
+    const ALL: [Section; 4] = [
+        Section::Conflicted,
+        Section::Staged,
+        Section::Unstaged,
+        Section::Untracked,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Section::Conflicted => "Conflicted",
+            Section::Staged => "Staged",
+            Section::Unstaged => "Unstaged",
+            Section::Untracked => "Untracked",
+        }
+    }
+
+    /// Partially staged files show up in both Staged and Unstaged.
+    fn contains(&self, file: &FileStatus) -> bool {
+        let conflicted = file.kind == FileStatusKind::Conflicted;
+        let untracked = file.worktree_status == Some(FileStatusKind::Untracked);
+        match self {
+            Section::Conflicted => conflicted,
+            Section::Staged => !conflicted && file.index_status.is_some(),
+            Section::Unstaged => !conflicted && !untracked && file.worktree_status.is_some(),
+            Section::Untracked => untracked,
+        }
+    }
+}
+
+/// One visible line of the status list.
+#[derive(Debug, Clone)]
+pub enum StatusRow {
+    Section { section: Section, count: usize },
+    Dir {
+        section: Option<Section>,
+        path: String,
+        depth: usize,
+        /// Indices into the file list of every file below this directory
+        files: Vec<usize>,
+    },
+    File { index: usize, depth: usize },
+}
 
 pub struct StatusListState {
     pub list_state: ListState,
-    pub selected: std::collections::HashSet<usize>,
+    /// Selected file indices (multi-select)
+    pub selected: HashSet<usize>,
     pub multi_select: bool,
+    /// Group by section and directory instead of a flat list
+    pub tree_view: bool,
+    /// Collapsed section/directory keys (see `fold_key`)
+    pub collapsed: HashSet<String>,
+    /// Case-insensitive path filter
+    pub filter: String,
+    /// Filter box is taking keyboard input
+    pub filtering: bool,
+    /// Paths changed since the last prompt was sent
+    pub turn_changed: HashSet<String>,
+    /// Show only `turn_changed`
+    pub turn_only: bool,
+    pub rows: Vec<StatusRow>,
 }
 
 impl StatusListState {
@@ -20,36 +89,76 @@ impl StatusListState {
         list_state.select(Some(0));
         Self {
             list_state,
-            selected: std::collections::HashSet::new(),
+            selected: HashSet::new(),
             multi_select: false,
+            tree_view: false,
+            collapsed: HashSet::new(),
+            filter: String::new(),
+            filtering: false,
+            turn_changed: HashSet::new(),
+            turn_only: false,
+            rows: Vec::new(),
         }
     }
 
+    /// File index of the highlighted row, if it is a file.
     pub fn selected_index(&self) -> Option<usize> {
-        self.list_state.selected()
+        match self.selected_row()? {
+            StatusRow::File { index, .. } => Some(*index),
+            _ => None,
+        }
     }
 
-    pub fn move_up(&mut self, len: usize) {
+    pub fn selected_row(&self) -> Option<&StatusRow> {
+        self.list_state.selected().and_then(|i| self.rows.get(i))
+    }
+
+    /// The section whose header row is highlighted.
+    pub fn selected_section(&self) -> Option<Section> {
+        match self.selected_row() {
+            Some(StatusRow::Section { section, .. }) => Some(*section),
+            _ => None,
+        }
+    }
+
+    /// File indices covered by the highlighted row (one file, or a whole directory/section).
+    pub fn selected_row_files(&self, files: &[FileStatus]) -> Vec<usize> {
+        match self.selected_row() {
+            Some(StatusRow::File { index, .. }) => vec![*index],
+            Some(StatusRow::Dir { files, .. }) => files.clone(),
+            Some(StatusRow::Section { section, .. }) => files.iter()
+                .enumerate()
+                .filter(|(_, f)| section.contains(f) && self.matches_filter(f))
+                .map(|(i, _)| i)
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    pub fn move_up(&mut self) {
+        let len = self.rows.len();
         if len == 0 { return; }
         let i = self.list_state.selected().unwrap_or(0);
         let next = if i == 0 { len - 1 } else { i - 1 };
         self.list_state.select(Some(next));
     }
 
-    pub fn move_down(&mut self, len: usize) {
+    pub fn move_down(&mut self) {
+        let len = self.rows.len();
         if len == 0 { return; }
         let i = self.list_state.selected().unwrap_or(0);
         let next = if i >= len - 1 { 0 } else { i + 1 };
         self.list_state.select(Some(next));
     }
 
-    pub fn toggle_select(&mut self) {
-        if let Some(i) = self.list_state.selected() {
-            if self.selected.contains(&i) {
+    pub fn toggle_select(&mut self, files: &[FileStatus]) {
+        let indices = self.selected_row_files(files);
+        if indices.iter().all(|i| self.selected.contains(i)) {
+            for i in indices {
                 self.selected.remove(&i);
-            } else {
-                self.selected.insert(i);
             }
+        } else {
+            self.selected.extend(indices);
         }
     }
 
@@ -62,31 +171,317 @@ impl StatusListState {
 
     pub fn selected_files<'a>(&self, files: &'a [FileStatus]) -> Vec<&'a FileStatus> {
         if self.multi_select && !self.selected.is_empty() {
-            self.selected.iter()
-                .filter_map(|&i| files.get(i))
+            let mut indices: Vec<usize> = self.selected.iter().copied().collect();
+            indices.sort_unstable();
+            indices.into_iter()
+                .filter_map(|i| files.get(i))
                 .collect()
-        } else if let Some(i) = self.list_state.selected() {
-            files.get(i).into_iter().collect()
         } else {
-            vec![]
+            self.selected_row_files(files).into_iter()
+                .filter_map(|i| files.get(i))
+                .collect()
+        }
+    }
+
+    /// Paths to reference in the agent: a directory row is sent as `dir/`,
+    /// everything else as individual files.
+    pub fn selected_refs(&self, files: &[FileStatus]) -> Vec<String> {
+        if let (false, Some(StatusRow::Dir { path, .. })) = (self.multi_select, self.selected_row()) {
+            return vec![format!("{}/", path)];
+        }
+        self.selected_files(files).iter().map(|f| f.path.clone()).collect()
+    }
+
+    pub fn toggle_tree_view(&mut self, files: &[FileStatus]) {
+        self.tree_view = !self.tree_view;
+        self.rebuild(files);
+    }
+
+    /// Collapse or expand the highlighted section/directory.
+    /// Returns false when the row is a file.
+    pub fn toggle_fold(&mut self, files: &[FileStatus]) -> bool {
+        let key = match self.selected_row() {
+            Some(StatusRow::Section { section, .. }) => fold_key(Some(*section), ""),
+            Some(StatusRow::Dir { section, path, .. }) => fold_key(*section, path),
+            _ => return false,
+        };
+        if !self.collapsed.remove(&key) {
+            self.collapsed.insert(key);
         }
+        self.rebuild(files);
+        true
+    }
+
+    pub fn start_filter(&mut self) {
+        self.filtering = true;
+    }
+
+    pub fn push_filter(&mut self, c: char, files: &[FileStatus]) {
+        self.filter.push(c);
+        self.rebuild(files);
+        self.list_state.select(Some(0));
+    }
+
+    pub fn pop_filter(&mut self, files: &[FileStatus]) {
+        self.filter.pop();
+        self.rebuild(files);
+    }
+
+    pub fn clear_filter(&mut self, files: &[FileStatus]) {
+        self.filter.clear();
+        self.filtering = false;
+        self.rebuild(files);
+    }
+
+    pub fn toggle_turn_only(&mut self, files: &[FileStatus]) {
+        self.turn_only = !self.turn_only;
+        self.rebuild(files);
+    }
+
+    pub fn set_turn_changed(&mut self, changed: HashSet<String>, files: &[FileStatus]) {
+        if changed != self.turn_changed {
+            self.turn_changed = changed;
+            self.rebuild(files);
+        }
+    }
+
+    fn matches_filter(&self, file: &FileStatus) -> bool {
+        if self.turn_only && !self.turn_changed.contains(&file.path) {
+            return false;
+        }
+        self.filter.is_empty()
+            || file.display_path().to_lowercase().contains(&self.filter.to_lowercase())
+    }
+
+    /// Recompute visible rows after files, filter, folding or view mode change.
+    /// Keeps the highlight on the same file or directory when it still exists.
+    pub fn rebuild(&mut self, files: &[FileStatus]) {
+        let previous = self.selected_row().map(|r| row_key(r, files));
+
+        let visible: Vec<usize> = (0..files.len())
+            .filter(|&i| self.matches_filter(&files[i]))
+            .collect();
+
+        let mut rows = Vec::new();
+        if self.tree_view {
+            for section in Section::ALL {
+                let entries: Vec<(usize, &str)> = visible.iter()
+                    .filter(|&&i| section.contains(&files[i]))
+                    .map(|&i| (i, files[i].path.as_str()))
+                    .collect();
+                if entries.is_empty() {
+                    continue;
+                }
+                rows.push(StatusRow::Section { section, count: entries.len() });
+                if !self.collapsed.contains(&fold_key(Some(section), "")) {
+                    push_tree(&mut rows, &entries, "", 1, Some(section), &self.collapsed);
+                }
+            }
+        } else {
+            rows.extend(visible.into_iter().map(|index| StatusRow::File { index, depth: 0 }));
+        }
+        self.rows = rows;
+        self.selected.retain(|&i| i < files.len());
+
+        let restored = previous.and_then(|key| {
+            self.rows.iter().position(|r| row_key(r, files) == key)
+        });
+        match restored {
+            Some(i) => self.list_state.select(Some(i)),
+            None => {
+                let i = self.list_state.selected().unwrap_or(0);
+                self.list_state.select(Some(i.min(self.rows.len().saturating_sub(1))));
+            }
+        }
+    }
+}
+
+fn fold_key(section: Option<Section>, path: &str) -> String {
+    format!("{:?}:{}", section, path)
+}
+
+/// Stable identity of a row across rebuilds.
+fn row_key(row: &StatusRow, files: &[FileStatus]) -> String {
+    match row {
+        StatusRow::Section { section, .. } => format!("section:{:?}", section),
+        StatusRow::Dir { section, path, .. } => format!("dir:{:?}:{}", section, path),
+        StatusRow::File { index, .. } => {
+            format!("file:{}", files.get(*index).map(|f| f.path.as_str()).unwrap_or(""))
+        }
+    }
+}
+
+/// Emit directory and file rows for `entries` (paths relative to `prefix`).
+/// Directories come first, each followed by its (unless collapsed) contents.
+fn push_tree(
+    rows: &mut Vec<StatusRow>,
+    entries: &[(usize, &str)],
+    prefix: &str,
+    depth: usize,
+    section: Option<Section>,
+    collapsed: &HashSet<String>,
+) {
+    let mut dirs: Vec<(&str, Vec<(usize, &str)>)> = Vec::new();
+    let mut leaves: Vec<(usize, &str)> = Vec::new();
+    for &(index, rel) in entries {
+        match rel.split_once('/') {
+            Some((dir, rest)) => match dirs.iter_mut().find(|(d, _)| *d == dir) {
+                Some((_, children)) => children.push((index, rest)),
+                None => dirs.push((dir, vec![(index, rest)])),
+            },
+            None => leaves.push((index, rel)),
+        }
+    }
+    dirs.sort_by(|a, b| a.0.cmp(b.0));
+    leaves.sort_by(|a, b| a.1.cmp(b.1));
+
+    for (dir, children) in dirs {
+        let path = format!("{}{}", prefix, dir);
+        rows.push(StatusRow::Dir {
+            section,
+            path: path.clone(),
+            depth,
+            files: children.iter().map(|(i, _)| *i).collect(),
+        });
+        if !collapsed.contains(&fold_key(section, &path)) {
+            push_tree(rows, &children, &format!("{}/", path), depth + 1, section, collapsed);
+        }
+    }
+    for (index, _) in leaves {
+        rows.push(StatusRow::File { index, depth });
     }
 }
 
 pub struct StatusListWidget<'a> {
     files: &'a [FileStatus],
     focused: bool,
-    title: String,
+    title: Line<'a>,
 }
 
 impl<'a> StatusListWidget<'a> {
-    pub fn new(files: &'a [FileStatus], focused: bool, branch: &str) -> Self {
+    /// `submodule` is the path being browsed when drilled into a submodule.
+    pub fn new(files: &'a [FileStatus], focused: bool, branch: &BranchInfo, submodule: Option<&str>) -> Self {
+        let mut spans = Vec::new();
+        if let Some(path) = submodule {
+            spans.push(Span::styled(
+                format!(" {} \u{25b8}", path),
+                Style::default().fg(Color::Blue),
+            ));
+        }
+        spans.push(Span::raw(format!(" {} ", branch.name)));
+        if let Some(ref tag) = branch.tag {
+            spans.push(Span::styled(
+                format!("\u{25c6}{} ", tag),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        if branch.ahead > 0 {
+            spans.push(Span::styled(
+                format!("\u{2191}{} ", branch.ahead),
+                Style::default().fg(Color::Green),
+            ));
+        }
+        if branch.behind > 0 {
+            spans.push(Span::styled(
+                format!("\u{2193}{} ", branch.behind),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        spans.push(Span::raw(format!("({}) ", files.len())));
         Self {
             files,
             focused,
-            title: format!(" {} ({}) ", branch, files.len()),
+            title: Line::from(spans),
         }
     }
+
+    /// Replace the branch with the revision range when in compare mode.
+    pub fn compare(mut self, label: Option<&str>) -> Self {
+        if let Some(label) = label {
+            let count = self.files.len();
+            self.title = Line::from(vec![
+                Span::styled(format!(" \u{21c4} {} ", label), Style::default().fg(Color::Magenta)),
+                Span::raw(format!("({}) ", count)),
+            ]);
+        }
+        self
+    }
+
+    /// `changed`: touched since the last prompt was sent.
+    fn file_line(&self, index: usize, depth: usize, tree: bool, marker: &'a str, changed: bool) -> Line<'a> {
+        let file = &self.files[index];
+        let name = match (tree, &file.old_path) {
+            (true, Some(old)) => format!(
+                "{} \u{2192} {}",
+                old,
+                file.path.rsplit('/').next().unwrap_or(&file.path),
+            ),
+            (true, None) => file.path.rsplit('/').next().unwrap_or(&file.path).to_string(),
+            (false, _) => file.display_path(),
+        };
+        let mut spans = vec![
+            Span::raw("  ".repeat(depth)),
+            Span::styled(marker, Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!("{} ", file.icon()),
+                Style::default().fg(Color::Green),
+            ),
+            Span::styled(
+                format!("{} ", file.kind_icon()),
+                Style::default().fg(file.kind_color()),
+            ),
+            Span::raw(name),
+        ];
+        if changed {
+            spans.push(Span::styled(" \u{2022}", Style::default().fg(Color::Magenta)));
+        }
+        if let Some(label) = file.submodule.as_ref().map(|s| s.label()).filter(|l| !l.is_empty()) {
+            spans.push(Span::styled(
+                format!(" ({})", label),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        Line::from(spans)
+    }
+
+    /// Directory line with aggregated stage state and per-kind counts.
+    fn dir_line(&self, path: &str, depth: usize, indices: &[usize], folded: bool, marker: &'a str) -> Line<'a> {
+        let staged = indices.iter()
+            .filter(|&&i| self.files[i].stage_state == StageState::Staged)
+            .count();
+        let icon = if staged == indices.len() {
+            "✓"
+        } else if staged > 0 || indices.iter().any(|&i| self.files[i].stage_state == StageState::Partial) {
+            "±"
+        } else {
+            " "
+        };
+
+        let mut counts: Vec<(&str, Color, usize)> = Vec::new();
+        for &i in indices {
+            let f = &self.files[i];
+            let (k, c) = (f.kind_icon(), f.kind_color());
+            match counts.iter_mut().find(|(ck, _, _)| *ck == k) {
+                Some(entry) => entry.2 += 1,
+                None => counts.push((k, c, 1)),
+            }
+        }
+
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let mut spans = vec![
+            Span::raw("  ".repeat(depth)),
+            Span::styled(marker, Style::default().fg(Color::Cyan)),
+            Span::styled(format!("{} ", icon), Style::default().fg(Color::Green)),
+            Span::styled(
+                format!("{} {}/ ", if folded { "▸" } else { "▾" }, name),
+                Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD),
+            ),
+        ];
+        for (k, c, n) in counts {
+            spans.push(Span::styled(format!("{}{} ", k, n), Style::default().fg(c)));
+        }
+        Line::from(spans)
+    }
 }
 
 impl StatefulWidget for StatusListWidget<'_> {
@@ -99,32 +494,56 @@ impl StatefulWidget for StatusListWidget<'_> {
             Style::default().fg(Color::DarkGray)
         };
 
-        let block = Block::default()
-            .title(self.title.as_str())
+        let mut block = Block::default()
+            .title(self.title.clone())
             .borders(Borders::ALL)
             .border_style(border_style);
 
-        let items: Vec<ListItem> = self.files.iter().enumerate().map(|(i, file)| {
-            let is_selected = state.selected.contains(&i);
-            let marker = if state.multi_select {
-                if is_selected { "● " } else { "○ " }
-            } else {
-                ""
-            };
+        if state.filtering || !state.filter.is_empty() {
+            let cursor = if state.filtering { "_" } else { "" };
+            block = block.title_bottom(Line::from(vec![
+                Span::styled(" /", Style::default().fg(Color::Yellow)),
+                Span::raw(format!("{}{} ", state.filter, cursor)),
+            ]));
+        }
+        if state.turn_only {
+            block = block.title_bottom(Line::from(Span::styled(
+                " \u{2022} since last prompt ",
+                Style::default().fg(Color::Magenta),
+            )).right_aligned());
+        }
 
-            let line = Line::from(vec![
-                Span::styled(marker, Style::default().fg(Color::Cyan)),
-                Span::styled(
-                    format!("{} ", file.icon()),
-                    Style::default().fg(Color::Green),
-                ),
-                Span::styled(
-                    format!("{} ", file.kind_icon()),
-                    Style::default().fg(file.kind_color()),
-                ),
-                Span::raw(&file.path),
-            ]);
+        let marker_for = |indices: &[usize]| -> &'static str {
+            if !state.multi_select {
+                ""
+            } else if !indices.is_empty() && indices.iter().all(|i| state.selected.contains(i)) {
+                "● "
+            } else {
+                "○ "
+            }
+        };
 
+        let items: Vec<ListItem> = state.rows.iter().map(|row| {
+            let line = match row {
+                StatusRow::Section { section, count } => {
+                    let folded = state.collapsed.contains(&fold_key(Some(*section), ""));
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{} {} ", if folded { "▸" } else { "▾" }, section.title()),
+                            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                        ),
+                        Span::styled(format!("({})", count), Style::default().fg(Color::DarkGray)),
+                    ])
+                }
+                StatusRow::Dir { section, path, depth, files } => {
+                    let folded = state.collapsed.contains(&fold_key(*section, path));
+                    self.dir_line(path, *depth, files, folded, marker_for(files))
+                }
+                StatusRow::File { index, depth } => {
+                    let changed = self.files.get(*index).is_some_and(|f| state.turn_changed.contains(&f.path));
+                    self.file_line(*index, *depth, state.tree_view, marker_for(&[*index]), changed)
+                }
+            };
             ListItem::new(line)
         }).collect();
 
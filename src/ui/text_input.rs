@@ -0,0 +1,385 @@
+//! Multi-line text input with readline-style editing, used by every prompt
+//! dialog mode.
+
+use std::ops::Range;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use unicode_width::UnicodeWidthChar;
+
+/// Undo steps kept per dialog.
+const MAX_UNDO: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditKind {
+    Insert,
+    Delete,
+    Other,
+}
+
+#[derive(Debug, Default)]
+pub struct TextInput {
+    text: String,
+    /// Byte offset into `text`
+    cursor: usize,
+    /// Other end of the selection, while extending it with Shift
+    anchor: Option<usize>,
+    /// Text removed by the last kill, for Ctrl-Y
+    kill: String,
+    undo: Vec<(String, usize)>,
+    redo: Vec<(String, usize)>,
+    /// Typing and deleting runs are undone together
+    last_edit: Option<EditKind>,
+    /// Column Up/Down keep while crossing shorter lines
+    goal_col: Option<usize>,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Start over with `text`, cursor at the end and no undo history.
+    pub fn reset(&mut self, text: impl Into<String>) {
+        *self = Self { kill: std::mem::take(&mut self.kill), ..Self::default() };
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    /// Replace everything with `text` as one undoable edit.
+    pub fn replace(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        if text != self.text {
+            self.edit(0..self.text.len(), &text, EditKind::Other);
+        }
+    }
+
+    /// Selected byte range, if any.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        (anchor != self.cursor).then(|| anchor.min(self.cursor)..anchor.max(self.cursor))
+    }
+
+    pub fn on_first_line(&self) -> bool {
+        !self.text[..self.cursor].contains('\n')
+    }
+
+    pub fn on_last_line(&self) -> bool {
+        !self.text[self.cursor..].contains('\n')
+    }
+
+    pub fn insert_str(&mut self, s: &str) {
+        let range = self.selection().unwrap_or(self.cursor..self.cursor);
+        self.edit(range, s, EditKind::Insert);
+    }
+
+    /// Apply an editing key. `multiline` allows Shift+Enter, Alt+Enter and
+    /// Ctrl-J to insert a newline. Returns false for keys it doesn't handle,
+    /// including Up/Down on the first/last line.
+    pub fn handle_key(&mut self, key: KeyEvent, multiline: bool) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        if key.code != KeyCode::Up && key.code != KeyCode::Down {
+            self.goal_col = None;
+        }
+        match key.code {
+            // AltGr arrives as Ctrl+Alt on some platforms
+            KeyCode::Char(c) if ctrl == alt => {
+                let mut buf = [0u8; 4];
+                self.insert_str(c.encode_utf8(&mut buf));
+            }
+            KeyCode::Char(c) if ctrl => match c.to_ascii_lowercase() {
+                'a' => self.move_to(line_start(&self.text, self.cursor), shift),
+                'e' => self.move_to(line_end(&self.text, self.cursor), shift),
+                'b' => self.move_to(prev_char(&self.text, self.cursor), shift),
+                'f' => self.move_to(next_char(&self.text, self.cursor), shift),
+                'h' => self.delete_back(),
+                'd' => self.delete_forward(),
+                'w' => match self.selection() {
+                    Some(range) => self.kill(range),
+                    None => self.kill(blank_word_left(&self.text, self.cursor)..self.cursor),
+                },
+                'u' => self.kill(line_start(&self.text, self.cursor)..self.cursor),
+                'k' => {
+                    let end = line_end(&self.text, self.cursor);
+                    // At the end of a line, join it with the next
+                    let end = if end == self.cursor { next_char(&self.text, end) } else { end };
+                    self.kill(self.cursor..end);
+                }
+                'y' => {
+                    let kill = self.kill.clone();
+                    self.insert_str(&kill);
+                    self.last_edit = None;
+                }
+                'z' if shift => self.redo(),
+                'z' | '_' | '/' | '7' => self.undo(),
+                'j' if multiline => self.insert_str("\n"),
+                _ => return false,
+            },
+            KeyCode::Char(c) => match c {
+                'b' => self.move_to(word_left(&self.text, self.cursor), shift),
+                'f' => self.move_to(word_right(&self.text, self.cursor), shift),
+                'd' => self.kill(self.cursor..word_right(&self.text, self.cursor)),
+                _ => return false,
+            },
+            KeyCode::Left if ctrl || alt => self.move_to(word_left(&self.text, self.cursor), shift),
+            KeyCode::Right if ctrl || alt => self.move_to(word_right(&self.text, self.cursor), shift),
+            KeyCode::Left => match (self.selection(), shift) {
+                (Some(range), false) => self.move_to(range.start, false),
+                _ => self.move_to(prev_char(&self.text, self.cursor), shift),
+            },
+            KeyCode::Right => match (self.selection(), shift) {
+                (Some(range), false) => self.move_to(range.end, false),
+                _ => self.move_to(next_char(&self.text, self.cursor), shift),
+            },
+            KeyCode::Home if ctrl => self.move_to(0, shift),
+            KeyCode::End if ctrl => self.move_to(self.text.len(), shift),
+            KeyCode::Home => self.move_to(line_start(&self.text, self.cursor), shift),
+            KeyCode::End => self.move_to(line_end(&self.text, self.cursor), shift),
+            KeyCode::Up => return self.vertical(false, shift),
+            KeyCode::Down => return self.vertical(true, shift),
+            KeyCode::Backspace if ctrl || alt => match self.selection() {
+                Some(range) => self.kill(range),
+                None => self.kill(word_left(&self.text, self.cursor)..self.cursor),
+            },
+            KeyCode::Backspace => self.delete_back(),
+            KeyCode::Delete if ctrl || alt => match self.selection() {
+                Some(range) => self.kill(range),
+                None => self.kill(self.cursor..word_right(&self.text, self.cursor)),
+            },
+            KeyCode::Delete => self.delete_forward(),
+            KeyCode::Enter if multiline && (shift || alt) => self.insert_str("\n"),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Visual rows for `width` columns: byte ranges of `text` without their
+    /// newline, wrapped at the last space that fits.
+    pub fn wrap(&self, width: usize) -> Vec<Range<usize>> {
+        let width = width.max(1);
+        let mut rows = Vec::new();
+        let mut line_start = 0;
+        for line in self.text.split('\n') {
+            let mut start = line_start;
+            let mut col = 0;
+            let mut last_space: Option<usize> = None;
+            for (offset, c) in line.char_indices() {
+                let i = line_start + offset;
+                let w = c.width().unwrap_or(0);
+                // A single space may hang past the edge, into the cursor column
+                let hangs = c == ' ' && col == width;
+                if col + w > width && i > start && !hangs {
+                    let brk = last_space.filter(|&b| b > start).unwrap_or(i);
+                    rows.push(start..brk);
+                    start = brk;
+                    col = str_width(&self.text[start..i]);
+                    last_space = None;
+                }
+                col += w;
+                if c == ' ' {
+                    last_space = Some(i + 1);
+                }
+            }
+            rows.push(start..line_start + line.len());
+            line_start += line.len() + 1;
+        }
+        rows
+    }
+
+    /// Styled rows for `width` columns (one kept free for the cursor) and
+    /// the index of the row holding the cursor.
+    pub fn render_lines(&self, width: usize) -> (Vec<Line<'static>>, usize) {
+        let rows = self.wrap(width.saturating_sub(1));
+        let cursor_row = rows.iter().rposition(|r| r.start <= self.cursor).unwrap_or(0);
+        let selection = self.selection().unwrap_or(0..0);
+        let cursor_style = Style::default().add_modifier(Modifier::REVERSED);
+        let selected_style = Style::default().bg(Color::DarkGray);
+        let lines = rows.iter()
+            .enumerate()
+            .map(|(n, row)| {
+                let mut spans: Vec<Span<'static>> = Vec::new();
+                for (offset, c) in self.text[row.clone()].char_indices() {
+                    let i = row.start + offset;
+                    let style = if n == cursor_row && i == self.cursor {
+                        cursor_style
+                    } else if selection.contains(&i) {
+                        selected_style
+                    } else {
+                        Style::default()
+                    };
+                    spans.push(Span::styled(c.to_string(), style));
+                }
+                if n == cursor_row && self.cursor == row.end {
+                    spans.push(Span::styled(" ", cursor_style));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        (lines, cursor_row)
+    }
+
+    fn edit(&mut self, range: Range<usize>, with: &str, kind: EditKind) {
+        let grouped = kind != EditKind::Other
+            && self.last_edit == Some(kind)
+            && !with.contains(char::is_whitespace);
+        if !grouped {
+            self.undo.push((self.text.clone(), self.cursor));
+            if self.undo.len() > MAX_UNDO {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.text.replace_range(range.clone(), with);
+        self.cursor = range.start + with.len();
+        self.anchor = None;
+        self.last_edit = Some(kind);
+    }
+
+    fn move_to(&mut self, pos: usize, extend: bool) {
+        if extend {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = pos;
+        self.last_edit = None;
+    }
+
+    fn delete_back(&mut self) {
+        let range = self.selection().unwrap_or(prev_char(&self.text, self.cursor)..self.cursor);
+        if !range.is_empty() {
+            self.edit(range, "", EditKind::Delete);
+        }
+    }
+
+    fn delete_forward(&mut self) {
+        let range = self.selection().unwrap_or(self.cursor..next_char(&self.text, self.cursor));
+        if !range.is_empty() {
+            self.edit(range, "", EditKind::Delete);
+        }
+    }
+
+    fn kill(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        self.kill = self.text[range.clone()].to_string();
+        self.edit(range, "", EditKind::Other);
+    }
+
+    fn undo(&mut self) {
+        if let Some((text, cursor)) = self.undo.pop() {
+            self.redo.push((std::mem::replace(&mut self.text, text), self.cursor));
+            self.cursor = cursor;
+            self.anchor = None;
+            self.last_edit = None;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some((text, cursor)) = self.redo.pop() {
+            self.undo.push((std::mem::replace(&mut self.text, text), self.cursor));
+            self.cursor = cursor;
+            self.anchor = None;
+            self.last_edit = None;
+        }
+    }
+
+    /// Move to the same column on the line above or below. False when
+    /// there is no such line.
+    fn vertical(&mut self, down: bool, extend: bool) -> bool {
+        let start = line_start(&self.text, self.cursor);
+        let target_start = if down {
+            match self.text[self.cursor..].find('\n') {
+                Some(i) => self.cursor + i + 1,
+                None => return false,
+            }
+        } else if start == 0 {
+            return false;
+        } else {
+            line_start(&self.text, start - 1)
+        };
+        let col = *self.goal_col.get_or_insert_with(|| self.text[start..self.cursor].chars().count());
+        let target_end = line_end(&self.text, target_start);
+        let pos = self.text[target_start..target_end]
+            .char_indices()
+            .nth(col)
+            .map_or(target_end, |(i, _)| target_start + i);
+        self.move_to(pos, extend);
+        true
+    }
+}
+
+fn str_width(s: &str) -> usize {
+    s.chars().map(|c| c.width().unwrap_or(0)).sum()
+}
+
+fn prev_char(text: &str, i: usize) -> usize {
+    text[..i].char_indices().next_back().map_or(0, |(p, _)| p)
+}
+
+fn next_char(text: &str, i: usize) -> usize {
+    text[i..].chars().next().map_or(i, |c| i + c.len_utf8())
+}
+
+fn line_start(text: &str, i: usize) -> usize {
+    text[..i].rfind('\n').map_or(0, |p| p + 1)
+}
+
+fn line_end(text: &str, i: usize) -> usize {
+    text[i..].find('\n').map_or(text.len(), |p| i + p)
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Start of the word before `i` (Alt-B, Alt-Backspace).
+fn word_left(text: &str, i: usize) -> usize {
+    let mut pos = i;
+    let mut seen_word = false;
+    for (p, c) in text[..i].char_indices().rev() {
+        if is_word(c) {
+            seen_word = true;
+        } else if seen_word {
+            break;
+        }
+        pos = p;
+    }
+    pos
+}
+
+/// End of the word after `i` (Alt-F, Alt-D).
+fn word_right(text: &str, i: usize) -> usize {
+    let mut seen_word = false;
+    for (p, c) in text[i..].char_indices() {
+        if is_word(c) {
+            seen_word = true;
+        } else if seen_word {
+            return i + p;
+        }
+    }
+    text.len()
+}
+
+/// Start of the whitespace-delimited word before `i` (Ctrl-W).
+fn blank_word_left(text: &str, i: usize) -> usize {
+    let trimmed = text[..i].trim_end();
+    trimmed.char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(p, c)| p + c.len_utf8())
+}